@@ -0,0 +1,176 @@
+use crate::encode::{collect_output, set_image_info, PixelLayout};
+use crate::EncodeOptions;
+use crate::JxlAnimationHeader;
+use crate::JxlBlendMode;
+use crate::JxlBool;
+use crate::JxlEncoder;
+use crate::JxlError;
+use crate::JxlFrameHeader;
+use crate::JxlLibrary;
+
+use image::ExtendedColorType;
+use std::sync::Arc;
+
+/// Position and size of a frame that covers only part of the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameCrop {
+    /// Offset from the left edge of the canvas; may be negative.
+    pub x0: i32,
+    /// Offset from the top edge of the canvas; may be negative.
+    pub y0: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How one frame of an `AnimationEncoder` is shown.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AnimationFrame {
+    /// Display time in ticks of the animation header; see `JxlAnimationHeader::duration_to_ticks`.
+    /// `0` makes the frame a layer that is blended into the next one.
+    pub duration: u32,
+    /// Frame name, empty for none.
+    pub name: String,
+    /// Area of the canvas the pixels cover; `None` for the whole canvas.
+    pub crop: Option<FrameCrop>,
+    /// How the frame is combined with what is below it. Defaults to `Replace`.
+    pub blend_mode: JxlBlendMode,
+    /// Reference slot (0 to 3) the frame is blended onto. Defaults to 0.
+    pub blend_source: u32,
+    /// Reference slot (0 to 3) the resulting canvas is saved to, for later frames to blend onto.
+    pub save_as_reference: u32,
+}
+
+/// Encodes an animation frame by frame.
+///
+/// Every frame uses the pixel layout given to `new`. Frames are kept by libjxl until
+/// `finish`, which closes the frames and input before any output is produced, so the
+/// last frame is always marked correctly. Boxes can be added through `encoder_mut` after
+/// `close_frames`.
+pub struct AnimationEncoder {
+    encoder: JxlEncoder,
+    layout: PixelLayout,
+    options: EncodeOptions,
+    width: u32,
+    height: u32,
+    num_frames: usize,
+    frames_closed: bool,
+}
+
+impl AnimationEncoder {
+    /// Starts a `width` x `height` animation of `color_type` pixels.
+    ///
+    /// `animation` sets the tick rate and loop count (`num_loops` 0 loops forever).
+    pub fn new(
+        lib: &Arc<JxlLibrary>,
+        width: u32,
+        height: u32,
+        color_type: ExtendedColorType,
+        animation: &JxlAnimationHeader,
+        options: &EncodeOptions,
+    ) -> Result<Self, JxlError> {
+        if animation.tps_numerator == 0 || animation.tps_denominator == 0 {
+            return Err(JxlError::InvalidInput(
+                "Animation ticks per second must not be zero".to_string(),
+            ));
+        }
+        let layout = PixelLayout::from_color_type(color_type)?;
+        let mut encoder = JxlEncoder::with_library(lib.clone(), None)?;
+        set_image_info(
+            &mut encoder,
+            &layout,
+            width,
+            height,
+            options,
+            Some(animation),
+        )?;
+
+        Ok(AnimationEncoder {
+            encoder,
+            layout,
+            options: options.clone(),
+            width,
+            height,
+            num_frames: 0,
+            frames_closed: false,
+        })
+    }
+
+    /// Returns the wrapped encoder, e.g. to attach a parallel runner or add boxes.
+    pub fn encoder_mut(&mut self) -> &mut JxlEncoder {
+        &mut self.encoder
+    }
+
+    /// Returns how many frames were added.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Adds one frame. `pixels` covers `frame.crop`, or the whole canvas without one.
+    /// - JxlEncoderSetFrameHeader() / JxlEncoderAddImageFrame()
+    pub fn add_frame(&mut self, pixels: &[u8], frame: &AnimationFrame) -> Result<(), JxlError> {
+        if self.frames_closed {
+            return Err(JxlError::UnsupportedOperation(
+                "Frames were already closed".to_string(),
+            ));
+        }
+        if frame.blend_source > 3 || frame.save_as_reference > 3 {
+            return Err(JxlError::InvalidInput(
+                "Reference slots must be 0 to 3".to_string(),
+            ));
+        }
+        let (width, height) = match frame.crop {
+            Some(crop) => (crop.width, crop.height),
+            None => (self.width, self.height),
+        };
+        self.layout.check_len(pixels, width, height)?;
+
+        let mut header = JxlFrameHeader::default();
+        self.encoder.init_frame_header(&mut header)?;
+        header.duration = frame.duration;
+        if let Some(crop) = frame.crop {
+            header.layer_info.have_crop = JxlBool::True;
+            header.layer_info.crop_x0 = crop.x0;
+            header.layer_info.crop_y0 = crop.y0;
+            header.layer_info.xsize = crop.width;
+            header.layer_info.ysize = crop.height;
+        }
+        header.layer_info.blend_info.blendmode = frame.blend_mode;
+        header.layer_info.blend_info.source = frame.blend_source;
+        // 알파는 첫 번째 extra channel
+        header.layer_info.blend_info.alpha = 0;
+        header.layer_info.save_as_reference = frame.save_as_reference;
+
+        let frame_settings = self.encoder.create_frame_settings()?;
+        self.options.apply_to(&frame_settings)?;
+        frame_settings.set_header(&header)?;
+        if self.layout.has_alpha {
+            // 알파 채널도 색 채널과 같은 방식으로 합성
+            frame_settings.set_extra_channel_blend_info(0, &header.layer_info.blend_info)?;
+        }
+        if !frame.name.is_empty() {
+            frame_settings.set_name(&frame.name)?;
+        }
+        frame_settings.add_image_frame(&self.layout.format, pixels)?;
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    /// Declares that no more frames follow; boxes may still be added. - JxlEncoderCloseFrames()
+    pub fn close_frames(&mut self) -> Result<(), JxlError> {
+        if !self.frames_closed {
+            self.encoder.close_frames()?;
+            self.frames_closed = true;
+        }
+        Ok(())
+    }
+
+    /// Closes the input and returns the encoded animation. - JxlEncoderCloseInput()
+    pub fn finish(mut self) -> Result<Vec<u8>, JxlError> {
+        if self.num_frames == 0 {
+            return Err(JxlError::FrameError("No frames were added".to_string()));
+        }
+        self.close_frames()?;
+        self.encoder.close_input()?;
+        collect_output(&mut self.encoder)
+    }
+}
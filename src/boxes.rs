@@ -0,0 +1,183 @@
+use crate::JxlDecoder;
+use crate::JxlDecoderStatus;
+use crate::JxlError;
+use crate::JxlLibrary;
+
+use std::iter::FusedIterator;
+use std::sync::Arc;
+
+/// Smallest box buffer handed to libjxl; it doubles whenever libjxl runs out.
+const MIN_BOX_BUFFER_SIZE: usize = 256;
+
+/// One box of a JPEG XL container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JxlBoxEntry {
+    /// Box type such as `*b"Exif"`; the inner type of a decompressed `brob` box.
+    pub box_type: [u8; 4],
+    /// Size of the box in the file, header included; 0 if it runs to the end of the file.
+    /// - JxlDecoderGetBoxSizeRaw()
+    pub raw_size: u64,
+    /// Whether the box is stored Brotli-compressed as `brob`.
+    pub compressed: bool,
+    /// Box contents without the header. Decompressed if requested; otherwise the contents of
+    /// a `brob` box start with the inner type.
+    pub contents: Vec<u8>,
+}
+
+/// Iterator over the boxes of a container, read one at a time. Created by
+/// `JxlDecoder::boxes`.
+///
+/// A bare codestream has no boxes. The codestream boxes (`jxlc`, `jxlp`) are read like any
+/// other box.
+pub struct JxlBoxes<'a> {
+    decoder: &'a JxlDecoder,
+    /// Box whose contents libjxl is writing into.
+    current: Option<JxlBoxEntry>,
+    /// A `Box` event was seen but its buffer isn't set yet.
+    pending: bool,
+    decompress: bool,
+    size_hint: usize,
+    done: bool,
+}
+
+impl JxlDecoder {
+    /// Reads the boxes of `data` one by one. With `decompress`, `brob` boxes are decompressed
+    /// into their inner type, which needs libjxl built with Brotli.
+    ///
+    /// Resets the decoder first; an attached parallel runner is kept.
+    /// - JxlDecoderSetDecompressBoxes()
+    pub fn boxes<'a>(&'a self, data: &'a [u8], decompress: bool) -> Result<JxlBoxes<'a>, JxlError> {
+        self.reset()?;
+        self.subscribe_events(JxlDecoderStatus::Box.bits())?;
+        self.set_decompress_boxes(decompress)?;
+        self.set_input(data)?;
+        self.close_input();
+
+        Ok(JxlBoxes {
+            decoder: self,
+            current: None,
+            pending: false,
+            decompress,
+            size_hint: data.len(),
+            done: false,
+        })
+    }
+}
+
+/// Reads every box of `data` with a fresh decoder on `lib`; see `JxlDecoder::boxes`.
+pub fn read_boxes(
+    lib: &Arc<JxlLibrary>,
+    data: &[u8],
+    decompress: bool,
+) -> Result<Vec<JxlBoxEntry>, JxlError> {
+    let decoder = JxlDecoder::with_library(lib.clone())?;
+    let boxes = decoder.boxes(data, decompress)?;
+    boxes.collect()
+}
+
+impl JxlBoxes<'_> {
+    /// Starts the box announced by the last `Box` event.
+    fn start_box(&mut self) -> Result<(), JxlError> {
+        let raw_type = self.decoder.get_box_type(false)?;
+        let compressed = &raw_type == b"brob";
+        let box_type = if compressed && self.decompress {
+            self.decoder.get_box_type(true)?
+        } else {
+            raw_type
+        };
+        let raw_size = self.decoder.get_box_size_raw()?;
+
+        // 압축된 박스는 풀면 커지므로 여유를 둠
+        let size = match usize::try_from(raw_size) {
+            Ok(0) | Err(_) => self.size_hint,
+            Ok(size) => size.min(self.size_hint),
+        };
+        let size = if compressed && self.decompress {
+            size.saturating_mul(4)
+        } else {
+            size
+        };
+        let mut contents = vec![0; size.max(MIN_BOX_BUFFER_SIZE)];
+        self.decoder.set_box_buffer(&mut contents)?;
+        self.current = Some(JxlBoxEntry {
+            box_type,
+            raw_size,
+            compressed,
+            contents,
+        });
+        Ok(())
+    }
+
+    /// Gives libjxl a larger buffer for the current box.
+    fn grow_box(&mut self) -> Result<(), JxlError> {
+        let current = self.current.as_mut().ok_or(JxlError::BoxBufferError)?;
+        let written = current.contents.len() - self.decoder.release_box_buffer();
+        let len = current.contents.len() * 2;
+        current.contents.resize(len, 0);
+        self.decoder
+            .set_box_buffer(&mut current.contents[written..])
+    }
+
+    /// Takes back the buffer of the current box and trims it to what was written.
+    fn finish_box(&mut self) -> Option<JxlBoxEntry> {
+        let mut entry = self.current.take()?;
+        let written = entry.contents.len() - self.decoder.release_box_buffer();
+        entry.contents.truncate(written);
+        Some(entry)
+    }
+
+    fn advance(&mut self) -> Result<Option<JxlBoxEntry>, JxlError> {
+        if std::mem::take(&mut self.pending) {
+            self.start_box()?;
+        }
+        loop {
+            match self.decoder.process_input()? {
+                JxlDecoderStatus::Box => {
+                    if let Some(entry) = self.finish_box() {
+                        self.pending = true;
+                        return Ok(Some(entry));
+                    }
+                    self.start_box()?;
+                }
+                JxlDecoderStatus::BoxNeedMoreOutput => self.grow_box()?,
+                JxlDecoderStatus::Success => {
+                    self.done = true;
+                    return Ok(self.finish_box());
+                }
+                JxlDecoderStatus::NeedMoreInput => return Err(JxlError::NotEnoughInput),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Iterator for JxlBoxes<'_> {
+    type Item = Result<JxlBoxEntry, JxlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.advance() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                self.finish_box();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl FusedIterator for JxlBoxes<'_> {}
+
+impl Drop for JxlBoxes<'_> {
+    fn drop(&mut self) {
+        // 버퍼가 해제되기 전에 디코더가 더 이상 가리키지 않게 함
+        self.finish_box();
+    }
+}
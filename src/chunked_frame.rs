@@ -0,0 +1,380 @@
+use crate::decode::bytes_per_sample;
+use crate::FrameSettings;
+use crate::JxlChunkedFrameInputSource;
+use crate::JxlError;
+use crate::JxlPixelFormat;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Mutex;
+
+/// Pixels of one rect, and the distance in bytes between the starts of consecutive rows.
+pub type ChunkedRect<'a> = (Cow<'a, [u8]>, usize);
+
+/// Provides a frame to `FrameSettings::add_chunked_frame_from` one rect at a time, so the whole
+/// frame never has to be in memory.
+///
+/// libjxl may request rects from its worker threads, hence `Sync`.
+pub trait ChunkedFrameSource: Sync {
+    /// Layout of the color samples returned by `color_rect`.
+    fn pixel_format(&self) -> JxlPixelFormat;
+
+    /// Returns the color samples of the `width` x `height` rect at (`x`, `y`).
+    ///
+    /// Borrowed data must stay valid until `release` is called for it; owned data is kept
+    /// alive by the encoder.
+    fn color_rect(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ChunkedRect<'_>, JxlError>;
+
+    /// Layout of extra channel `index`. Defaults to one channel of `pixel_format`'s type.
+    fn extra_channel_format(&self, index: usize) -> JxlPixelFormat {
+        let _ = index;
+        JxlPixelFormat {
+            num_channels: 1,
+            ..self.pixel_format()
+        }
+    }
+
+    /// Returns the samples of extra channel `index` for the given rect.
+    fn extra_channel_rect(
+        &self,
+        index: usize,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ChunkedRect<'_>, JxlError> {
+        let _ = (x, y, width, height);
+        Err(JxlError::ExtraChannelError(format!(
+            "No data for extra channel {index}"
+        )))
+    }
+
+    /// Called once libjxl is done with a borrowed rect returned by this source.
+    fn release(&self, data: &[u8]) {
+        let _ = data;
+    }
+}
+
+impl FrameSettings<'_> {
+    /// Adds a frame whose pixels are pulled from `source` rect by rect. - JxlEncoderAddChunkedFrame()
+    ///
+    /// The basic info must describe the frame size. An error returned by `source` makes this
+    /// fail with that error; libjxl sees zeroed pixels for the failed rect in the meantime.
+    pub fn add_chunked_frame_from<S: ChunkedFrameSource + ?Sized>(
+        &self,
+        is_last_frame: bool,
+        source: &S,
+    ) -> Result<(), JxlError> {
+        let adapter = SourceAdapter {
+            source,
+            held: Mutex::new(HashMap::new()),
+            error: Mutex::new(None),
+        };
+        let input = JxlChunkedFrameInputSource {
+            opaque: &adapter as *const SourceAdapter<S> as *mut c_void,
+            get_color_channels_pixel_format: Some(get_color_channels_pixel_format::<S>),
+            get_color_channel_data_at: Some(get_color_channel_data_at::<S>),
+            get_extra_channel_pixel_format: Some(get_extra_channel_pixel_format::<S>),
+            get_extra_channel_data_at: Some(get_extra_channel_data_at::<S>),
+            release_buffer: Some(release_buffer::<S>),
+        };
+        // adapter 는 이 호출이 끝날 때까지 살아 있음
+        let result = unsafe { self.add_chunked_frame(is_last_frame, input) };
+
+        match adapter
+            .error
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+        {
+            Some(e) => Err(e),
+            None => result,
+        }
+    }
+}
+
+/// What `JxlChunkedFrameInputSource::opaque` points at during `add_chunked_frame_from`.
+struct SourceAdapter<'a, S: ?Sized> {
+    source: &'a S,
+    /// Rects handed to libjxl and not released yet, by address.
+    held: Mutex<HashMap<usize, Vec<Held>>>,
+    error: Mutex<Option<JxlError>>,
+}
+
+struct Held {
+    len: usize,
+    /// Rects the source returned by value live here until libjxl releases them.
+    owned: Option<Vec<u8>>,
+}
+
+impl<S: ChunkedFrameSource + ?Sized> SourceAdapter<'_, S> {
+    /// Checks a rect returned by the source and hands it to libjxl.
+    ///
+    /// Rects that are missing, too short or panicked are replaced by zeros after recording
+    /// the error, so libjxl never reads out of bounds.
+    fn provide<'s>(
+        &'s self,
+        format: &JxlPixelFormat,
+        width: usize,
+        height: usize,
+        row_offset: *mut usize,
+        rect: impl FnOnce() -> Result<ChunkedRect<'s>, JxlError>,
+    ) -> *const c_void {
+        let pixel_size = format.num_channels as usize * bytes_per_sample(format.data_type);
+        let row_size = width * pixel_size;
+
+        let checked = match panic::catch_unwind(AssertUnwindSafe(rect)) {
+            Ok(Ok((data, stride))) => {
+                let needed = match height {
+                    0 => 0,
+                    _ => (height - 1) * stride + row_size,
+                };
+                if stride >= row_size && data.len() >= needed {
+                    Ok((data, stride))
+                } else {
+                    Err(JxlError::InvalidInput(format!(
+                        "Rect of {width}x{height} needs {needed} bytes with stride {stride}, got {}",
+                        data.len()
+                    )))
+                }
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(JxlError::InvalidInput(
+                "Chunked frame source panicked".to_string(),
+            )),
+        };
+
+        let (data, stride) = match checked {
+            Ok(rect) => rect,
+            Err(e) => {
+                self.fail(e);
+                (Cow::Owned(vec![0u8; (row_size * height).max(1)]), row_size)
+            }
+        };
+
+        let address = data.as_ptr();
+        let held = match data {
+            Cow::Borrowed(data) => Held {
+                len: data.len(),
+                owned: None,
+            },
+            Cow::Owned(data) => Held {
+                len: data.len(),
+                owned: Some(data),
+            },
+        };
+        self.held
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(address as usize)
+            .or_default()
+            .push(held);
+        unsafe { *row_offset = stride };
+        address as *const c_void
+    }
+
+    fn release(&self, address: *const c_void) {
+        let held = {
+            let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+            let entries = held.get_mut(&(address as usize));
+            let entry = entries.and_then(|entries| entries.pop());
+            held.retain(|_, entries| !entries.is_empty());
+            entry
+        };
+        if let Some(Held { len, owned: None }) = held {
+            let data = unsafe { std::slice::from_raw_parts(address as *const u8, len) };
+            let source = self.source;
+            if panic::catch_unwind(AssertUnwindSafe(|| source.release(data))).is_err() {
+                self.fail(JxlError::InvalidInput(
+                    "Chunked frame source panicked".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn fail(&self, e: JxlError) {
+        let mut error = self.error.lock().unwrap_or_else(|e| e.into_inner());
+        if error.is_none() {
+            *error = Some(e);
+        }
+    }
+
+    fn format(&self, extra_channel: Option<usize>) -> JxlPixelFormat {
+        let source = self.source;
+        let format = panic::catch_unwind(AssertUnwindSafe(|| match extra_channel {
+            Some(index) => source.extra_channel_format(index),
+            None => source.pixel_format(),
+        }));
+        format.unwrap_or_else(|_| {
+            self.fail(JxlError::PixelFormatError);
+            JxlPixelFormat::default()
+        })
+    }
+}
+
+unsafe extern "C" fn get_color_channels_pixel_format<S: ChunkedFrameSource + ?Sized>(
+    opaque: *mut c_void,
+    pixel_format: *mut JxlPixelFormat,
+) {
+    let adapter = &*(opaque as *const SourceAdapter<S>);
+    ptr::write(pixel_format, adapter.format(None));
+}
+
+unsafe extern "C" fn get_color_channel_data_at<S: ChunkedFrameSource + ?Sized>(
+    opaque: *mut c_void,
+    xpos: usize,
+    ypos: usize,
+    xsize: usize,
+    ysize: usize,
+    row_offset: *mut usize,
+) -> *const c_void {
+    let adapter = &*(opaque as *const SourceAdapter<S>);
+    let format = adapter.format(None);
+    adapter.provide(&format, xsize, ysize, row_offset, || {
+        adapter.source.color_rect(xpos, ypos, xsize, ysize)
+    })
+}
+
+unsafe extern "C" fn get_extra_channel_pixel_format<S: ChunkedFrameSource + ?Sized>(
+    opaque: *mut c_void,
+    ec_index: usize,
+    pixel_format: *mut JxlPixelFormat,
+) {
+    let adapter = &*(opaque as *const SourceAdapter<S>);
+    ptr::write(pixel_format, adapter.format(Some(ec_index)));
+}
+
+unsafe extern "C" fn get_extra_channel_data_at<S: ChunkedFrameSource + ?Sized>(
+    opaque: *mut c_void,
+    ec_index: usize,
+    xpos: usize,
+    ypos: usize,
+    xsize: usize,
+    ysize: usize,
+    row_offset: *mut usize,
+) -> *const c_void {
+    let adapter = &*(opaque as *const SourceAdapter<S>);
+    let format = adapter.format(Some(ec_index));
+    adapter.provide(&format, xsize, ysize, row_offset, || {
+        adapter
+            .source
+            .extra_channel_rect(ec_index, xpos, ypos, xsize, ysize)
+    })
+}
+
+unsafe extern "C" fn release_buffer<S: ChunkedFrameSource + ?Sized>(
+    opaque: *mut c_void,
+    buf: *const c_void,
+) {
+    let adapter = &*(opaque as *const SourceAdapter<S>);
+    adapter.release(buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A 4x2 RGB frame where sample values encode their position.
+    struct Tiles {
+        data: Vec<u8>,
+        released: AtomicUsize,
+    }
+
+    impl ChunkedFrameSource for Tiles {
+        fn pixel_format(&self) -> JxlPixelFormat {
+            JxlPixelFormat::default()
+        }
+
+        fn color_rect(
+            &self,
+            x: usize,
+            y: usize,
+            width: usize,
+            height: usize,
+        ) -> Result<ChunkedRect<'_>, JxlError> {
+            match x {
+                0 => Ok((Cow::Borrowed(&self.data[(y * 4 + x) * 3..]), 12)),
+                1 => Ok((Cow::Owned(vec![7; width * height * 3]), width * 3)),
+                _ => Ok((Cow::Borrowed(&self.data[..1]), 12)),
+            }
+        }
+
+        fn release(&self, _data: &[u8]) {
+            self.released.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn adapter(source: &Tiles) -> SourceAdapter<'_, Tiles> {
+        SourceAdapter {
+            source,
+            held: Mutex::new(HashMap::new()),
+            error: Mutex::new(None),
+        }
+    }
+
+    unsafe fn rect(adapter: &SourceAdapter<Tiles>, x: usize) -> (*const c_void, usize) {
+        let opaque = adapter as *const SourceAdapter<Tiles> as *mut c_void;
+        let mut row_offset = 0;
+        let data = get_color_channel_data_at::<Tiles>(opaque, x, 0, 2, 2, &mut row_offset);
+        (data, row_offset)
+    }
+
+    #[test]
+    fn test_rects_are_held_until_released() {
+        let source = Tiles {
+            data: (0..24).collect(),
+            released: AtomicUsize::new(0),
+        };
+        let adapter = adapter(&source);
+        let opaque = &adapter as *const SourceAdapter<Tiles> as *mut c_void;
+
+        unsafe {
+            let (borrowed, stride) = rect(&adapter, 0);
+            assert_eq!(
+                (borrowed, stride),
+                (source.data.as_ptr() as *const c_void, 12)
+            );
+            let (owned, stride) = rect(&adapter, 1);
+            assert_eq!(stride, 6);
+            assert_eq!(*(owned as *const u8), 7);
+            assert_eq!(adapter.held.lock().unwrap().len(), 2);
+
+            release_buffer::<Tiles>(opaque, owned);
+            assert_eq!(source.released.load(Ordering::SeqCst), 0);
+            release_buffer::<Tiles>(opaque, borrowed);
+            assert_eq!(source.released.load(Ordering::SeqCst), 1);
+        }
+        assert!(adapter.held.lock().unwrap().is_empty());
+        assert!(adapter.error.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_short_rect_is_replaced_by_zeros() {
+        let source = Tiles {
+            data: (0..24).collect(),
+            released: AtomicUsize::new(0),
+        };
+        let adapter = adapter(&source);
+
+        unsafe {
+            let (data, stride) = rect(&adapter, 2);
+            assert_eq!(stride, 6);
+            assert!(std::slice::from_raw_parts(data as *const u8, 12)
+                .iter()
+                .all(|&v| v == 0));
+        }
+        assert!(matches!(
+            *adapter.error.lock().unwrap(),
+            Some(JxlError::InvalidInput(_))
+        ));
+    }
+}
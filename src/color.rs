@@ -2,7 +2,7 @@
 
 /// Color encoding information for JPEG XL images
 #[repr(C)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct JxlColorEncoding {
     /// Color space of the image data
     pub color_space: JxlColorSpace,
@@ -41,9 +41,10 @@ pub struct JxlColorEncoding {
 
 /// Represents color space for JPEG XL images
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum JxlColorSpace {
     /// Tristimulus RGB color space
+    #[default]
     RGB = 0,
 
     /// Luminance based color space.
@@ -61,9 +62,10 @@ pub enum JxlColorSpace {
 
 /// Represents white point.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum JxlWhitePoint {
     /// CIE Standard Illuminant D65: 0.3127, 0.3290
+    #[default]
     D65 = 1,
 
     /// White point must be read from the JxlColorEncoding white_point field,
@@ -80,10 +82,11 @@ pub enum JxlWhitePoint {
 
 /// Color primaries for JPEG XL images
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum JxlPrimaries {
     /**The CIE xy values of the red, green and blue primaries are: 0.639998686,
     0.330010138; 0.300003784, 0.600003357; 0.150002046, 0.059997204*/
+    #[default]
     SRGB = 1,
 
     /// Primaries must be read from the JxlColorEncoding primaries_red_xy,
@@ -102,7 +105,7 @@ pub enum JxlPrimaries {
 /// Enum values match a subset of CICP (Rec. ITU-T H.273 | ISO/IEC 23091-2:2019(E))
 /// unless specified otherwise.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum JxlTransferFunction {
     /// As specified in ITU-R BT.709-6
     /// JXL_TRANSFER_FUNCTION_709
@@ -118,6 +121,7 @@ pub enum JxlTransferFunction {
 
     /// As specified in IEC 61966-2-1 sRGB
     /// JXL_TRANSFER_FUNCTION_SRGB
+    #[default]
     SRGB = 13,
 
     /// As specified in SMPTE ST 2084
@@ -140,9 +144,10 @@ pub enum JxlTransferFunction {
 
 /// Represents rendering intent.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum JxlRenderingIntent {
     ///JXL_RENDERING_INTENT_PERCEPTUAL
+    #[default]
     Perceptual = 0,
     ///JXL_RENDERING_INTENT_RELATIVE
     Relative,
@@ -152,40 +157,9 @@ pub enum JxlRenderingIntent {
     Absolute,
 }
 
-// Default implementations for enums...
-
-impl Default for JxlColorSpace {
-    fn default() -> Self {
-        JxlColorSpace::RGB
-    }
-}
-
-impl Default for JxlWhitePoint {
-    fn default() -> Self {
-        JxlWhitePoint::D65
-    }
-}
-
-impl Default for JxlPrimaries {
-    fn default() -> Self {
-        JxlPrimaries::SRGB
-    }
-}
-
-impl Default for JxlTransferFunction {
-    fn default() -> Self {
-        JxlTransferFunction::SRGB
-    }
-}
-
-impl Default for JxlRenderingIntent {
-    fn default() -> Self {
-        JxlRenderingIntent::Perceptual
-    }
-}
-
 /// Represents a color management system interface.
 #[repr(C)]
+#[derive(Default)]
 pub struct JxlCmsInterface {
     pub get_color_profile_size: Option<unsafe extern "C" fn(*mut c_void, *mut usize) -> c_int>,
     pub get_color_profile: Option<unsafe extern "C" fn(*mut c_void, *mut u8, usize) -> c_int>,
@@ -193,15 +167,3 @@ pub struct JxlCmsInterface {
     pub set_color_profile: Option<unsafe extern "C" fn(*mut c_void, *const u8, usize) -> c_int>,
     // Add other CMS-related function pointers as needed
 }
-
-// Implement Default for JxlCmsInterface
-impl Default for JxlCmsInterface {
-    fn default() -> Self {
-        JxlCmsInterface {
-            get_color_profile_size: None,
-            get_color_profile: None,
-            set_color_profile_size: None,
-            set_color_profile: None,
-        }
-    }
-}
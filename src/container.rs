@@ -0,0 +1,608 @@
+use crate::metadata_boxes::exif_box_contents;
+use crate::JxlError;
+use crate::JxlSignature;
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// The `JXL ` signature box every JPEG XL container starts with.
+pub const JXL_CONTAINER_SIGNATURE: [u8; 12] = [
+    0x00, 0x00, 0x00, 0x0C, b'J', b'X', b'L', b' ', 0x0D, 0x0A, 0x87, 0x0A,
+];
+
+/// The first two bytes of a bare JPEG XL codestream.
+pub const JXL_CODESTREAM_SIGNATURE: [u8; 2] = [0xFF, 0x0A];
+
+/// Flag in the index of a `jxlp` box marking the last part of the codestream.
+const JXLP_LAST: u32 = 0x8000_0000;
+
+/// One ISOBMFF box, kept exactly as stored so unchanged boxes are written back byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerBox<'a> {
+    /// Box type such as `*b"Exif"`; `*b"brob"` for a Brotli-compressed box.
+    pub box_type: [u8; 4],
+    /// Header length: 8, or 16 with a 64-bit `largesize`.
+    header_len: usize,
+    /// Size field was 0: the box runs to the end of the file.
+    open_ended: bool,
+    /// Header and contents as stored.
+    bytes: Cow<'a, [u8]>,
+}
+
+impl ContainerBox<'_> {
+    /// Builds a box around `contents`, using a 64-bit size only when needed.
+    pub fn new(box_type: [u8; 4], contents: &[u8]) -> ContainerBox<'static> {
+        let mut bytes = Vec::with_capacity(16 + contents.len());
+        write_box_header(&mut bytes, box_type, contents.len());
+        let header_len = bytes.len();
+        bytes.extend_from_slice(contents);
+        ContainerBox {
+            box_type,
+            header_len,
+            open_ended: false,
+            bytes: Cow::Owned(bytes),
+        }
+    }
+
+    /// Builds an `Exif` box; takes the same input as `JxlEncoder::add_exif`.
+    pub fn exif(exif: &[u8]) -> Result<ContainerBox<'static>, JxlError> {
+        Ok(ContainerBox::new(*b"Exif", &exif_box_contents(exif)?))
+    }
+
+    /// Builds an `xml ` box holding an XMP packet.
+    pub fn xmp(xmp: &str) -> ContainerBox<'static> {
+        ContainerBox::new(*b"xml ", xmp.as_bytes())
+    }
+
+    /// Contents after the header.
+    pub fn contents(&self) -> &[u8] {
+        &self.bytes[self.header_len..]
+    }
+
+    /// Header and contents as stored in the file.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Whether the box is stored Brotli-compressed as `brob`.
+    pub fn is_compressed(&self) -> bool {
+        &self.box_type == b"brob"
+    }
+
+    /// Type of the data in the box: the inner type of a `brob` box, otherwise `box_type`.
+    pub fn content_type(&self) -> [u8; 4] {
+        match self.contents().first_chunk::<4>() {
+            Some(inner) if self.is_compressed() => *inner,
+            _ => self.box_type,
+        }
+    }
+
+    /// Whether the size field is 0, meaning the box runs to the end of the file.
+    pub fn is_open_ended(&self) -> bool {
+        self.open_ended
+    }
+}
+
+/// A structural problem found by `inspect_container`. Offsets are from the start of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerProblem {
+    /// The data is neither a container nor a bare codestream.
+    InvalidSignature,
+    /// Fewer bytes are left than a box header needs.
+    TruncatedHeader { offset: u64 },
+    /// The box claims more bytes than the file has.
+    TruncatedBox { offset: u64, box_type: [u8; 4] },
+    /// The size field is smaller than the header.
+    InvalidBoxSize { offset: u64, box_type: [u8; 4] },
+    /// The box after the signature isn't `ftyp`.
+    MissingFtyp,
+    /// `ftyp` doesn't declare the `jxl ` brand.
+    InvalidFtyp,
+    /// `jxll` doesn't hold a single byte of 5 or 10.
+    InvalidLevel { offset: u64 },
+    /// `jxll` comes after the codestream starts.
+    LevelAfterCodestream { offset: u64 },
+    /// There is no `jxlc` or `jxlp` box.
+    MissingCodestream,
+    /// A second `jxlc`, or `jxlc` together with `jxlp`.
+    MixedCodestreamBoxes { offset: u64 },
+    /// A `jxlp` box too short for its index.
+    TruncatedJxlp { offset: u64 },
+    /// A `jxlp` box whose index doesn't follow the previous one.
+    JxlpOutOfOrder {
+        offset: u64,
+        expected: u32,
+        found: u32,
+    },
+    /// A `jxlp` box after the one marked last.
+    JxlpAfterLast { offset: u64 },
+    /// No `jxlp` box is marked last.
+    MissingLastJxlp,
+}
+
+impl fmt::Display for ContainerProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |box_type: &[u8; 4]| String::from_utf8_lossy(box_type).into_owned();
+        match self {
+            Self::InvalidSignature => write!(f, "Not a JPEG XL file"),
+            Self::TruncatedHeader { offset } => write!(f, "Truncated box header at {offset}"),
+            Self::TruncatedBox { offset, box_type } => {
+                write!(f, "Box '{}' at {offset} is truncated", name(box_type))
+            }
+            Self::InvalidBoxSize { offset, box_type } => {
+                write!(
+                    f,
+                    "Box '{}' at {offset} is smaller than its header",
+                    name(box_type)
+                )
+            }
+            Self::MissingFtyp => write!(f, "Missing 'ftyp' box after the signature"),
+            Self::InvalidFtyp => write!(f, "'ftyp' box doesn't declare the 'jxl ' brand"),
+            Self::InvalidLevel { offset } => write!(f, "Invalid 'jxll' box at {offset}"),
+            Self::LevelAfterCodestream { offset } => {
+                write!(f, "'jxll' box at {offset} comes after the codestream")
+            }
+            Self::MissingCodestream => write!(f, "No 'jxlc' or 'jxlp' box"),
+            Self::MixedCodestreamBoxes { offset } => {
+                write!(f, "Extra codestream box at {offset}")
+            }
+            Self::TruncatedJxlp { offset } => write!(f, "'jxlp' box at {offset} has no index"),
+            Self::JxlpOutOfOrder {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'jxlp' box at {offset} has index {found}, expected {expected}"
+            ),
+            Self::JxlpAfterLast { offset } => {
+                write!(f, "'jxlp' box at {offset} comes after the last part")
+            }
+            Self::MissingLastJxlp => write!(f, "No 'jxlp' box is marked last"),
+        }
+    }
+}
+
+/// What `inspect_container` found in a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerInfo<'a> {
+    pub signature: JxlSignature,
+    /// Boxes in file order, up to the first one that couldn't be read. Empty for a bare
+    /// codestream.
+    pub boxes: Vec<ContainerBox<'a>>,
+    /// Codestream level from the `jxll` box; `None` means the default level 5.
+    pub level: Option<u8>,
+    pub problems: Vec<ContainerProblem>,
+}
+
+impl ContainerInfo<'_> {
+    /// Whether no structural problem was found.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A box as read from the file, borrowing its bytes.
+struct RawBox<'a> {
+    offset: u64,
+    box_type: [u8; 4],
+    header_len: usize,
+    open_ended: bool,
+    bytes: &'a [u8],
+}
+
+impl<'a> RawBox<'a> {
+    fn contents(&self) -> &'a [u8] {
+        &self.bytes[self.header_len..]
+    }
+}
+
+impl<'a> From<RawBox<'a>> for ContainerBox<'a> {
+    fn from(raw: RawBox<'a>) -> Self {
+        ContainerBox {
+            box_type: raw.box_type,
+            header_len: raw.header_len,
+            open_ended: raw.open_ended,
+            bytes: Cow::Borrowed(raw.bytes),
+        }
+    }
+}
+
+/// Writes a box header for `contents_len` bytes of contents.
+pub(crate) fn write_box_header(out: &mut Vec<u8>, box_type: [u8; 4], contents_len: usize) {
+    let size = contents_len as u64 + 8;
+    match u32::try_from(size) {
+        Ok(size) => {
+            out.extend_from_slice(&size.to_be_bytes());
+            out.extend_from_slice(&box_type);
+        }
+        Err(_) => {
+            out.extend_from_slice(&1u32.to_be_bytes());
+            out.extend_from_slice(&box_type);
+            out.extend_from_slice(&(size + 8).to_be_bytes());
+        }
+    }
+}
+
+/// Checks the signature like `JxlDecoder::check_signature`, without libjxl.
+pub fn detect_signature(data: &[u8]) -> JxlSignature {
+    if data.starts_with(&JXL_CODESTREAM_SIGNATURE) {
+        JxlSignature::Codestream
+    } else if data.starts_with(&JXL_CONTAINER_SIGNATURE) {
+        JxlSignature::Container
+    } else if JXL_CODESTREAM_SIGNATURE.starts_with(data)
+        || JXL_CONTAINER_SIGNATURE.starts_with(data)
+    {
+        JxlSignature::NotEnoughBytes
+    } else {
+        JxlSignature::Invalid
+    }
+}
+
+/// Reads the box starting at `offset`.
+fn read_box(data: &[u8], offset: usize) -> Result<RawBox<'_>, ContainerProblem> {
+    let rest = &data[offset..];
+    let truncated_header = ContainerProblem::TruncatedHeader {
+        offset: offset as u64,
+    };
+    if rest.len() < 8 {
+        return Err(truncated_header);
+    }
+    let size_field = u32::from_be_bytes(rest[0..4].try_into().unwrap());
+    let box_type: [u8; 4] = rest[4..8].try_into().unwrap();
+    let (size, header_len) = match size_field {
+        0 => (rest.len() as u64, 8),
+        1 => match rest.get(8..16) {
+            Some(largesize) => (u64::from_be_bytes(largesize.try_into().unwrap()), 16),
+            None => return Err(truncated_header),
+        },
+        size => (u64::from(size), 8),
+    };
+    if size < header_len as u64 {
+        return Err(ContainerProblem::InvalidBoxSize {
+            offset: offset as u64,
+            box_type,
+        });
+    }
+    match usize::try_from(size) {
+        Ok(size) if size <= rest.len() => Ok(RawBox {
+            offset: offset as u64,
+            box_type,
+            header_len,
+            open_ended: size_field == 0,
+            bytes: &rest[..size],
+        }),
+        _ => Err(ContainerProblem::TruncatedBox {
+            offset: offset as u64,
+            box_type,
+        }),
+    }
+}
+
+/// Reads every box of a container, stopping at the first one that can't be read.
+fn read_boxes_raw(data: &[u8]) -> (Vec<RawBox<'_>>, Option<ContainerProblem>) {
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        match read_box(data, offset) {
+            Ok(raw) => {
+                offset += raw.bytes.len();
+                boxes.push(raw);
+            }
+            Err(problem) => return (boxes, Some(problem)),
+        }
+    }
+    (boxes, None)
+}
+
+/// Checks box order and the `ftyp`, `jxll` and codestream boxes. Returns the level.
+fn check_structure(boxes: &[RawBox<'_>], problems: &mut Vec<ContainerProblem>) -> Option<u8> {
+    match boxes.get(1) {
+        Some(ftyp) if &ftyp.box_type == b"ftyp" => {
+            // 주 브랜드 "jxl ", 부 버전 0, 호환 브랜드에 "jxl " 포함
+            let contents = ftyp.contents();
+            let valid = contents.len() >= 8
+                && contents.starts_with(b"jxl ")
+                && contents[8..].chunks_exact(4).any(|brand| brand == b"jxl ");
+            if !valid {
+                problems.push(ContainerProblem::InvalidFtyp);
+            }
+        }
+        _ => problems.push(ContainerProblem::MissingFtyp),
+    }
+
+    let mut level = None;
+    let mut seen_jxlc = false;
+    let mut next_jxlp: Option<u32> = None;
+    let mut last_jxlp = false;
+    for raw in boxes {
+        let offset = raw.offset;
+        match &raw.box_type {
+            b"jxll" => {
+                if seen_jxlc || next_jxlp.is_some() {
+                    problems.push(ContainerProblem::LevelAfterCodestream { offset });
+                }
+                match raw.contents() {
+                    [l @ (5 | 10)] => level = Some(*l),
+                    _ => problems.push(ContainerProblem::InvalidLevel { offset }),
+                }
+            }
+            b"jxlc" => {
+                if seen_jxlc || next_jxlp.is_some() {
+                    problems.push(ContainerProblem::MixedCodestreamBoxes { offset });
+                }
+                seen_jxlc = true;
+            }
+            b"jxlp" => {
+                if seen_jxlc {
+                    problems.push(ContainerProblem::MixedCodestreamBoxes { offset });
+                }
+                let Some(index) = raw.contents().first_chunk::<4>() else {
+                    problems.push(ContainerProblem::TruncatedJxlp { offset });
+                    continue;
+                };
+                let index = u32::from_be_bytes(*index);
+                let expected = next_jxlp.unwrap_or(0);
+                let found = index & !JXLP_LAST;
+                if last_jxlp {
+                    problems.push(ContainerProblem::JxlpAfterLast { offset });
+                } else if found != expected {
+                    problems.push(ContainerProblem::JxlpOutOfOrder {
+                        offset,
+                        expected,
+                        found,
+                    });
+                }
+                next_jxlp = Some(found + 1);
+                last_jxlp |= index & JXLP_LAST != 0;
+            }
+            _ => {}
+        }
+    }
+    if !seen_jxlc && next_jxlp.is_none() {
+        problems.push(ContainerProblem::MissingCodestream);
+    } else if next_jxlp.is_some() && !last_jxlp {
+        problems.push(ContainerProblem::MissingLastJxlp);
+    }
+    level
+}
+
+/// Reads the structure of a JPEG XL file without decoding it and lists every problem found.
+/// Pure Rust, so it works where libjxl isn't installed.
+///
+/// A bare codestream has no boxes and no problems. Reading stops at the first box that
+/// doesn't fit in `data`; the boxes before it are still returned.
+pub fn inspect_container(data: &[u8]) -> ContainerInfo<'_> {
+    let signature = detect_signature(data);
+    let mut info = ContainerInfo {
+        signature,
+        boxes: Vec::new(),
+        level: None,
+        problems: Vec::new(),
+    };
+    match signature {
+        JxlSignature::Codestream => return info,
+        JxlSignature::Invalid => {
+            info.problems.push(ContainerProblem::InvalidSignature);
+            return info;
+        }
+        JxlSignature::NotEnoughBytes => {
+            info.problems
+                .push(ContainerProblem::TruncatedHeader { offset: 0 });
+            return info;
+        }
+        JxlSignature::Container => {}
+    }
+
+    let (boxes, problem) = read_boxes_raw(data);
+    info.problems.extend(problem);
+    info.level = check_structure(&boxes, &mut info.problems);
+    info.boxes = boxes.into_iter().map(ContainerBox::from).collect();
+    info
+}
+
+/// Splits a container into its boxes without copying. Fails on a bare codestream, a missing
+/// signature box or a box that doesn't fit in `data`.
+pub fn parse_container(data: &[u8]) -> Result<Vec<ContainerBox<'_>>, JxlError> {
+    match detect_signature(data) {
+        JxlSignature::Container => {}
+        JxlSignature::Codestream => {
+            return Err(JxlError::BoxError(
+                "Bare codestream has no boxes".to_string(),
+            ))
+        }
+        _ => {
+            return Err(JxlError::BoxError(
+                "Missing JPEG XL container signature".to_string(),
+            ))
+        }
+    }
+    match read_boxes_raw(data) {
+        (boxes, None) => Ok(boxes.into_iter().map(ContainerBox::from).collect()),
+        (_, Some(problem)) => Err(JxlError::BoxError(problem.to_string())),
+    }
+}
+
+/// Returns the bare codestream of a JPEG XL file: `data` itself for a bare codestream, the
+/// `jxlc` contents, or the `jxlp` parts joined in order. Fails on any structural problem.
+///
+/// Undoes `wrap_codestream`; metadata, `jbrd` and the `jxll` level are left behind, and the
+/// pixels aren't touched.
+pub fn extract_codestream(data: &[u8]) -> Result<Cow<'_, [u8]>, JxlError> {
+    match detect_signature(data) {
+        JxlSignature::Codestream => return Ok(Cow::Borrowed(data)),
+        JxlSignature::Container => {}
+        _ => {
+            return Err(JxlError::BoxError(
+                ContainerProblem::InvalidSignature.to_string(),
+            ))
+        }
+    }
+    let (boxes, problem) = read_boxes_raw(data);
+    let mut problems = Vec::from_iter(problem);
+    check_structure(&boxes, &mut problems);
+    if let Some(problem) = problems.first() {
+        return Err(JxlError::BoxError(problem.to_string()));
+    }
+
+    if let Some(jxlc) = boxes.iter().find(|b| &b.box_type == b"jxlc") {
+        return Ok(Cow::Borrowed(jxlc.contents()));
+    }
+    Ok(Cow::Owned(
+        boxes
+            .iter()
+            .filter(|b| &b.box_type == b"jxlp")
+            .flat_map(|b| &b.contents()[4..])
+            .copied()
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FTYP: &[u8] = b"jxl \0\0\0\0jxl ";
+
+    fn boxed(box_type: &[u8; 4], contents: &[u8]) -> Vec<u8> {
+        ContainerBox::new(*box_type, contents).bytes().to_vec()
+    }
+
+    fn jxlp(index: u32, contents: &[u8]) -> Vec<u8> {
+        boxed(b"jxlp", &[&index.to_be_bytes()[..], contents].concat())
+    }
+
+    fn container(boxes: &[Vec<u8>]) -> Vec<u8> {
+        [JXL_CONTAINER_SIGNATURE.to_vec(), boxed(b"ftyp", FTYP)]
+            .iter()
+            .chain(boxes)
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_signature() {
+        assert_eq!(detect_signature(&[0xFF, 0x0A, 0]), JxlSignature::Codestream);
+        assert_eq!(
+            detect_signature(&JXL_CONTAINER_SIGNATURE),
+            JxlSignature::Container
+        );
+        assert_eq!(
+            detect_signature(&JXL_CONTAINER_SIGNATURE[..5]),
+            JxlSignature::NotEnoughBytes
+        );
+        assert_eq!(detect_signature(&[]), JxlSignature::NotEnoughBytes);
+        assert_eq!(detect_signature(b"\x89PNG"), JxlSignature::Invalid);
+    }
+
+    #[test]
+    fn test_jxlp_parts_are_joined() {
+        let data = container(&[
+            boxed(b"jxll", &[10]),
+            boxed(b"Exif", b"\0\0\0\0II*\0"),
+            jxlp(0, &[0xFF, 0x0A, 1]),
+            jxlp(1, &[2, 3]),
+            jxlp(2 | JXLP_LAST, &[4]),
+        ]);
+        let info = inspect_container(&data);
+        assert!(info.is_valid(), "{:?}", info.problems);
+        assert_eq!(info.level, Some(10));
+        assert_eq!(info.boxes.len(), 7);
+        assert_eq!(
+            extract_codestream(&data).unwrap().as_ref(),
+            [0xFF, 0x0A, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_jxlc_and_bare_codestream_are_borrowed() {
+        let codestream = [0xFF, 0x0A, 9, 9];
+        let data = container(&[boxed(b"jxlc", &codestream)]);
+        assert!(matches!(
+            extract_codestream(&data).unwrap(),
+            Cow::Borrowed(c) if c == codestream
+        ));
+        assert!(matches!(
+            extract_codestream(&codestream).unwrap(),
+            Cow::Borrowed(c) if c == codestream
+        ));
+        assert!(inspect_container(&codestream).boxes.is_empty());
+    }
+
+    #[test]
+    fn test_largesize_box() {
+        let mut large = vec![0, 0, 0, 1];
+        large.extend_from_slice(b"jxlc");
+        large.extend_from_slice(&20u64.to_be_bytes());
+        large.extend_from_slice(&[0xFF, 0x0A, 5, 6]);
+        let data = container(&[large]);
+        let info = inspect_container(&data);
+        assert!(info.is_valid(), "{:?}", info.problems);
+        assert_eq!(info.boxes[2].contents(), [0xFF, 0x0A, 5, 6]);
+        assert_eq!(
+            extract_codestream(&data).unwrap().as_ref(),
+            [0xFF, 0x0A, 5, 6]
+        );
+    }
+
+    #[test]
+    fn test_structural_problems() {
+        let data = container(&[jxlp(1, &[0xFF]), jxlp(0, &[0x0A])]);
+        assert_eq!(
+            inspect_container(&data).problems,
+            [
+                ContainerProblem::JxlpOutOfOrder {
+                    offset: 32,
+                    expected: 0,
+                    found: 1
+                },
+                ContainerProblem::JxlpOutOfOrder {
+                    offset: 45,
+                    expected: 2,
+                    found: 0
+                },
+                ContainerProblem::MissingLastJxlp,
+            ]
+        );
+        assert!(extract_codestream(&data).is_err());
+
+        let data = container(&[boxed(b"jxlc", &[0xFF, 0x0A]), boxed(b"jxll", &[7])]);
+        assert_eq!(
+            inspect_container(&data).problems,
+            [
+                ContainerProblem::LevelAfterCodestream { offset: 42 },
+                ContainerProblem::InvalidLevel { offset: 42 },
+            ]
+        );
+
+        let mut data = container(&[boxed(b"jxlc", &[0xFF, 0x0A, 1, 2])]);
+        data.truncate(data.len() - 1);
+        let info = inspect_container(&data);
+        assert_eq!(info.boxes.len(), 2);
+        assert_eq!(
+            info.problems,
+            [
+                ContainerProblem::TruncatedBox {
+                    offset: 32,
+                    box_type: *b"jxlc"
+                },
+                ContainerProblem::MissingCodestream,
+            ]
+        );
+
+        let no_ftyp = [
+            JXL_CONTAINER_SIGNATURE.to_vec(),
+            boxed(b"jxlc", &[0xFF, 0x0A]),
+        ]
+        .concat();
+        assert_eq!(
+            inspect_container(&no_ftyp).problems,
+            [ContainerProblem::MissingFtyp]
+        );
+        assert_eq!(
+            inspect_container(b"GIF89a").problems,
+            [ContainerProblem::InvalidSignature]
+        );
+    }
+}
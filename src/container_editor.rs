@@ -0,0 +1,323 @@
+use crate::container::write_box_header;
+use crate::detect_signature;
+use crate::parse_container;
+use crate::ContainerBox;
+use crate::JxlError;
+use crate::JxlSignature;
+use crate::JXL_CONTAINER_SIGNATURE;
+
+use std::io::Write;
+
+/// Boxes that make up the image itself; the editor never drops or adds them.
+const STRUCTURAL_BOXES: [&[u8; 4]; 6] = [b"JXL ", b"ftyp", b"jxll", b"jxlc", b"jxlp", b"brob"];
+
+/// Edits the metadata boxes of a JPEG XL container without touching the codestream.
+///
+/// Boxes are matched by `ContainerBox::content_type`, so a compressed `brob` Exif box counts
+/// as `Exif`. Every box that isn't removed or replaced is written back as it was read.
+/// Dropping `Exif` or `xml ` from a file with a `jbrd` box breaks JPEG reconstruction; drop
+/// `jbrd` as well in that case.
+#[derive(Debug, Clone)]
+pub struct ContainerEditor<'a> {
+    boxes: Vec<ContainerBox<'a>>,
+}
+
+impl<'a> ContainerEditor<'a> {
+    /// Parses the boxes of `data`; see `parse_container`.
+    pub fn parse(data: &'a [u8]) -> Result<Self, JxlError> {
+        Ok(ContainerEditor {
+            boxes: parse_container(data)?,
+        })
+    }
+
+    /// Starts a container around a bare codestream: signature, `ftyp` and one `jxlc` box.
+    pub fn from_codestream(codestream: &[u8]) -> Result<Self, JxlError> {
+        if detect_signature(codestream) != JxlSignature::Codestream {
+            return Err(JxlError::InvalidInput(
+                "Expected a bare JPEG XL codestream".to_string(),
+            ));
+        }
+        let mut ftyp = Vec::with_capacity(12);
+        ftyp.extend_from_slice(b"jxl ");
+        ftyp.extend_from_slice(&0u32.to_be_bytes());
+        ftyp.extend_from_slice(b"jxl ");
+        Ok(ContainerEditor {
+            boxes: vec![
+                ContainerBox::new(*b"JXL ", &JXL_CONTAINER_SIGNATURE[8..]),
+                ContainerBox::new(*b"ftyp", &ftyp),
+                ContainerBox::new(*b"jxlc", codestream),
+            ],
+        })
+    }
+
+    /// Returns the boxes in file order.
+    pub fn boxes(&self) -> &[ContainerBox<'a>] {
+        &self.boxes
+    }
+
+    /// Returns the first box holding `box_type`.
+    pub fn find(&self, box_type: &[u8; 4]) -> Option<&ContainerBox<'a>> {
+        self.boxes.iter().find(|b| &b.content_type() == box_type)
+    }
+
+    /// Drops every box holding `box_type` and returns how many were dropped.
+    pub fn remove(&mut self, box_type: &[u8; 4]) -> Result<usize, JxlError> {
+        check_editable(box_type)?;
+        let len = self.boxes.len();
+        self.boxes.retain(|b| &b.content_type() != box_type);
+        Ok(len - self.boxes.len())
+    }
+
+    /// Adds a box in front of the codestream, after any boxes already there.
+    pub fn insert(&mut self, box_type: &[u8; 4], contents: &[u8]) -> Result<(), JxlError> {
+        check_editable(box_type)?;
+        let index = self.codestream_index();
+        self.boxes
+            .insert(index, ContainerBox::new(*box_type, contents));
+        Ok(())
+    }
+
+    /// Replaces every box holding `box_type` with one box at the place of the first, or
+    /// inserts it if there was none.
+    pub fn replace(&mut self, box_type: &[u8; 4], contents: &[u8]) -> Result<(), JxlError> {
+        check_editable(box_type)?;
+        let index = self
+            .boxes
+            .iter()
+            .position(|b| &b.content_type() == box_type);
+        self.remove(box_type)?;
+        let index = index.unwrap_or_else(|| self.codestream_index());
+        self.boxes
+            .insert(index, ContainerBox::new(*box_type, contents));
+        Ok(())
+    }
+
+    /// Replaces the Exif data; takes the same input as `JxlEncoder::add_exif`.
+    pub fn set_exif(&mut self, exif: &[u8]) -> Result<(), JxlError> {
+        self.replace(b"Exif", ContainerBox::exif(exif)?.contents())
+    }
+
+    /// Replaces the XMP packet.
+    pub fn set_xmp(&mut self, xmp: &str) -> Result<(), JxlError> {
+        self.replace(b"xml ", xmp.as_bytes())
+    }
+
+    /// Sets the codestream level (5 or 10) in a `jxll` box after `ftyp`, or drops the box
+    /// with `None`. The level isn't checked against the codestream.
+    pub fn set_level(&mut self, level: Option<u8>) -> Result<(), JxlError> {
+        if level.is_some_and(|level| level != 5 && level != 10) {
+            return Err(JxlError::InvalidInput(
+                "Codestream level must be 5 or 10".to_string(),
+            ));
+        }
+        self.boxes.retain(|b| &b.box_type != b"jxll");
+        if let Some(level) = level {
+            let index = self
+                .boxes
+                .iter()
+                .position(|b| !matches!(&b.box_type, b"JXL " | b"ftyp"))
+                .unwrap_or(self.boxes.len());
+            self.boxes
+                .insert(index, ContainerBox::new(*b"jxll", &[level]));
+        }
+        Ok(())
+    }
+
+    /// Writes the edited file.
+    pub fn write_to<W: Write>(&self, mut out: W) -> Result<(), JxlError> {
+        let last = self.boxes.len().saturating_sub(1);
+        let mut header = Vec::with_capacity(16);
+        for (i, b) in self.boxes.iter().enumerate() {
+            // 크기가 0인 박스는 파일 끝까지이므로 마지막이 아니면 크기를 기록
+            if b.is_open_ended() && i != last {
+                header.clear();
+                write_box_header(&mut header, b.box_type, b.contents().len());
+                out.write_all(&header)?;
+                out.write_all(b.contents())?;
+            } else {
+                out.write_all(b.bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the edited file.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.boxes.iter().map(|b| b.bytes().len() + 8).sum());
+        self.write_to(&mut out)
+            .expect("writing to a Vec can't fail");
+        out
+    }
+
+    /// Index of the first codestream box, or the end if there is none.
+    fn codestream_index(&self) -> usize {
+        self.boxes
+            .iter()
+            .position(|b| matches!(&b.box_type, b"jxlc" | b"jxlp"))
+            .unwrap_or(self.boxes.len())
+    }
+}
+
+/// Wraps a bare codestream into a container with an optional `jxll` level box and `metadata`
+/// boxes in front of the codestream. The codestream is copied as is; `extract_codestream`
+/// goes the other way.
+///
+/// Pass `Some(10)` for level 10 codestreams; a bare codestream is level 5 unless told
+/// otherwise. Metadata boxes are written as they are, so compressed `brob` boxes stay
+/// compressed.
+pub fn wrap_codestream(
+    codestream: &[u8],
+    level: Option<u8>,
+    metadata: &[ContainerBox<'_>],
+) -> Result<Vec<u8>, JxlError> {
+    let mut editor = ContainerEditor::from_codestream(codestream)?;
+    editor.set_level(level)?;
+    for b in metadata {
+        check_editable(&b.content_type())?;
+        let index = editor.codestream_index();
+        editor.boxes.insert(index, b.clone());
+    }
+    Ok(editor.to_vec())
+}
+
+fn check_editable(box_type: &[u8; 4]) -> Result<(), JxlError> {
+    if STRUCTURAL_BOXES.contains(&box_type) {
+        return Err(JxlError::BoxError(format!(
+            "'{}' boxes can't be added or removed",
+            String::from_utf8_lossy(box_type)
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_codestream;
+    use crate::inspect_container;
+
+    fn boxed(box_type: &[u8; 4], contents: &[u8]) -> Vec<u8> {
+        ContainerBox::new(*box_type, contents).bytes().to_vec()
+    }
+
+    fn sample() -> Vec<u8> {
+        [
+            JXL_CONTAINER_SIGNATURE.to_vec(),
+            boxed(b"ftyp", b"jxl \0\0\0\0jxl "),
+            boxed(b"Exif", b"\0\0\0\0II*\0gps"),
+            boxed(b"brob", b"xml compressed"),
+            // 코드스트림은 파일 끝까지 이어지는 박스로 저장
+            [&[0, 0, 0, 0][..], b"jxlc", &[0xFF, 0x0A, 1, 2, 3]].concat(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_unchanged_round_trip() {
+        let data = sample();
+        let editor = ContainerEditor::parse(&data).unwrap();
+        let types: Vec<_> = editor.boxes().iter().map(|b| b.content_type()).collect();
+        assert_eq!(types, [*b"JXL ", *b"ftyp", *b"Exif", *b"xml ", *b"jxlc"]);
+        assert!(editor.boxes()[3].is_compressed());
+        assert_eq!(editor.to_vec(), data);
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let data = sample();
+        let mut editor = ContainerEditor::parse(&data).unwrap();
+        assert_eq!(editor.remove(b"Exif").unwrap(), 1);
+        assert_eq!(editor.remove(b"Exif").unwrap(), 0);
+        editor.set_xmp("<x:xmpmeta/>").unwrap();
+        editor.insert(b"jumb", b"c2pa").unwrap();
+
+        let edited = editor.to_vec();
+        let boxes = parse_container(&edited).unwrap();
+        let types: Vec<_> = boxes.iter().map(|b| b.box_type).collect();
+        assert_eq!(types, [*b"JXL ", *b"ftyp", *b"xml ", *b"jumb", *b"jxlc"]);
+        assert_eq!(boxes[2].contents(), b"<x:xmpmeta/>");
+        assert!(edited.ends_with(&data[data.len() - 13..]));
+    }
+
+    #[test]
+    fn test_set_exif_adds_offset() {
+        let data = sample();
+        let mut editor = ContainerEditor::parse(&data).unwrap();
+        editor.set_exif(b"Exif\0\0MM\0*").unwrap();
+        assert_eq!(editor.boxes()[2].contents(), b"\0\0\0\0MM\0*");
+        assert!(editor.set_exif(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_open_ended_box_gets_size() {
+        let data = [
+            JXL_CONTAINER_SIGNATURE.to_vec(),
+            boxed(b"ftyp", b"jxl \0\0\0\0jxl "),
+            [&[0, 0, 0, 0][..], b"jxlc", &[0xFF, 0x0A, 1, 2, 3]].concat(),
+        ]
+        .concat();
+        let mut editor = ContainerEditor::parse(&data).unwrap();
+        editor.boxes.push(ContainerBox::new(*b"Exif", b"late"));
+
+        let edited = editor.to_vec();
+        let boxes = parse_container(&edited).unwrap();
+        assert_eq!(boxes[2].contents(), [0xFF, 0x0A, 1, 2, 3]);
+        assert!(!boxes[2].is_open_ended());
+        assert_eq!(boxes[3].contents(), b"late");
+    }
+
+    #[test]
+    fn test_wrap_and_unwrap_codestream() {
+        let codestream = [0xFF, 0x0A, 7, 8, 9];
+        let metadata = [
+            ContainerBox::exif(b"II*\0\x08\0\0\0").unwrap(),
+            ContainerBox::xmp("<x:xmpmeta/>"),
+            ContainerBox::new(*b"brob", b"jumbcompressed"),
+        ];
+        let data = wrap_codestream(&codestream, Some(10), &metadata).unwrap();
+
+        let info = inspect_container(&data);
+        assert!(info.is_valid(), "{:?}", info.problems);
+        assert_eq!(info.level, Some(10));
+        let types: Vec<_> = info.boxes.iter().map(|b| b.box_type).collect();
+        assert_eq!(
+            types,
+            [*b"JXL ", *b"ftyp", *b"jxll", *b"Exif", *b"xml ", *b"brob", *b"jxlc"]
+        );
+        assert_eq!(info.boxes[3].contents(), b"\0\0\0\0II*\0\x08\0\0\0");
+        assert_eq!(info.boxes[5].bytes(), boxed(b"brob", b"jumbcompressed"));
+        assert_eq!(extract_codestream(&data).unwrap().as_ref(), codestream);
+
+        let bare = wrap_codestream(&codestream, None, &[]).unwrap();
+        assert_eq!(inspect_container(&bare).level, None);
+        assert_eq!(extract_codestream(&bare).unwrap().as_ref(), codestream);
+
+        assert!(wrap_codestream(&codestream, Some(7), &[]).is_err());
+        assert!(wrap_codestream(&data, None, &[]).is_err());
+        assert!(wrap_codestream(&codestream, None, &[ContainerBox::new(*b"jxlc", &[])]).is_err());
+        let brob_jxlc = ContainerBox::new(*b"brob", b"jxlc\xFF\x0A");
+        assert!(wrap_codestream(&codestream, None, &[brob_jxlc]).is_err());
+    }
+
+    #[test]
+    fn test_set_level_replaces_level_box() {
+        let data = sample();
+        let mut editor = ContainerEditor::parse(&data).unwrap();
+        editor.set_level(Some(5)).unwrap();
+        editor.set_level(Some(10)).unwrap();
+        assert_eq!(editor.boxes()[2].box_type, *b"jxll");
+        assert_eq!(editor.boxes()[2].contents(), [10]);
+        editor.set_level(None).unwrap();
+        assert_eq!(editor.to_vec(), data);
+    }
+
+    #[test]
+    fn test_structural_boxes_are_kept() {
+        let data = sample();
+        let mut editor = ContainerEditor::parse(&data).unwrap();
+        assert!(editor.remove(b"jxlc").is_err());
+        assert!(editor.insert(b"ftyp", b"").is_err());
+        assert!(ContainerEditor::parse(&[0xFF, 0x0A, 0]).is_err());
+        assert!(ContainerEditor::parse(&data[..data.len() - 20]).is_err());
+    }
+}
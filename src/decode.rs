@@ -0,0 +1,412 @@
+use crate::JxlBasicInfo;
+use crate::JxlColorEncoding;
+use crate::JxlColorProfileTarget;
+use crate::JxlDataType;
+use crate::JxlDecoder;
+use crate::JxlDecoderStatus;
+use crate::JxlEndianness;
+use crate::JxlError;
+use crate::JxlExtraChannelInfo;
+use crate::JxlFrameHeader;
+use crate::JxlLibrary;
+use crate::JxlPixelFormat;
+
+use std::mem;
+use std::sync::Arc;
+
+/// Options for `JxlDecoder::decode` and `decode_to_image`.
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// Sample type of the output. `None` picks u8, u16 or f32 from the image's bit depth.
+    pub data_type: Option<JxlDataType>,
+    /// Interleaved channels per pixel. `None` uses the color channels, plus alpha if present.
+    pub num_channels: Option<u32>,
+    /// Decode every extra channel (alpha, depth, ...) into its own buffer. Default `true`.
+    pub extra_channels: bool,
+    /// Coalesce animation frames into full canvases. Default `true`. - JxlDecoderSetCoalescing()
+    pub coalescing: bool,
+    /// Leave the pixels in stored orientation. Default `false`. - JxlDecoderSetKeepOrientation()
+    pub keep_orientation: bool,
+    /// Undo premultiplied alpha. Default `false`. - JxlDecoderSetUnpremultiplyAlpha()
+    pub unpremultiply_alpha: bool,
+    /// Render spot colors into the color channels. Default `true`. - JxlDecoderSetRenderSpotcolors()
+    pub render_spotcolors: bool,
+    /// Target display peak luminance for HDR tone mapping. - JxlDecoderSetDesiredIntensityTarget()
+    pub desired_intensity_target: Option<f32>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            data_type: None,
+            num_channels: None,
+            extra_channels: true,
+            coalescing: true,
+            keep_orientation: false,
+            unpremultiply_alpha: false,
+            render_spotcolors: true,
+            desired_intensity_target: None,
+        }
+    }
+}
+
+/// Decoded samples, typed by `JxlDataType`, in native endianness.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PixelBuffer {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    /// IEEE 754 half-precision values, stored as their bit patterns.
+    F16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+impl PixelBuffer {
+    /// Allocates a zeroed buffer holding at least `byte_size` bytes.
+    fn with_byte_size(data_type: JxlDataType, byte_size: usize) -> Self {
+        let len = byte_size.div_ceil(bytes_per_sample(data_type));
+        match data_type {
+            JxlDataType::Uint8 => PixelBuffer::U8(vec![0; len]),
+            JxlDataType::Uint16 => PixelBuffer::U16(vec![0; len]),
+            JxlDataType::Float16 => PixelBuffer::F16(vec![0; len]),
+            JxlDataType::Float => PixelBuffer::F32(vec![0.0; len]),
+        }
+    }
+
+    /// Returns the sample type.
+    pub fn data_type(&self) -> JxlDataType {
+        match self {
+            PixelBuffer::U8(_) => JxlDataType::Uint8,
+            PixelBuffer::U16(_) => JxlDataType::Uint16,
+            PixelBuffer::F16(_) => JxlDataType::Float16,
+            PixelBuffer::F32(_) => JxlDataType::Float,
+        }
+    }
+
+    /// Returns the number of samples.
+    pub fn len(&self) -> usize {
+        match self {
+            PixelBuffer::U8(v) => v.len(),
+            PixelBuffer::U16(v) | PixelBuffer::F16(v) => v.len(),
+            PixelBuffer::F32(v) => v.len(),
+        }
+    }
+
+    /// Returns `true` if the buffer holds no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Views the samples as raw native-endian bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            PixelBuffer::U8(v) => v,
+            PixelBuffer::U16(v) | PixelBuffer::F16(v) => unsafe {
+                std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len() * 2)
+            },
+            PixelBuffer::F32(v) => unsafe {
+                std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len() * 4)
+            },
+        }
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        match self {
+            PixelBuffer::U8(v) => v,
+            PixelBuffer::U16(v) | PixelBuffer::F16(v) => unsafe {
+                std::slice::from_raw_parts_mut(v.as_mut_ptr() as *mut u8, v.len() * 2)
+            },
+            PixelBuffer::F32(v) => unsafe {
+                std::slice::from_raw_parts_mut(v.as_mut_ptr() as *mut u8, v.len() * 4)
+            },
+        }
+    }
+}
+
+/// An extra channel of the image. - JxlDecoderGetExtraChannelInfo()
+#[derive(Debug, Clone)]
+pub struct ExtraChannel {
+    pub info: JxlExtraChannelInfo,
+    pub name: String,
+}
+
+/// One decoded frame.
+#[derive(Debug, Clone)]
+pub struct DecodedFrame {
+    pub header: JxlFrameHeader,
+    pub name: String,
+    /// Interleaved pixels in `DecodedImage::pixel_format`.
+    pub pixels: PixelBuffer,
+    /// One buffer per entry of `DecodedImage::extra_channels`, empty unless requested.
+    pub extra_channels: Vec<PixelBuffer>,
+}
+
+impl DecodedFrame {
+    /// Width of this frame; differs from the image width only for non-coalesced layers.
+    pub fn width(&self, info: &JxlBasicInfo) -> u32 {
+        if self.header.layer_info.have_crop.into() {
+            self.header.layer_info.xsize
+        } else {
+            info.xsize
+        }
+    }
+
+    /// Height of this frame; differs from the image height only for non-coalesced layers.
+    pub fn height(&self, info: &JxlBasicInfo) -> u32 {
+        if self.header.layer_info.have_crop.into() {
+            self.header.layer_info.ysize
+        } else {
+            info.ysize
+        }
+    }
+}
+
+/// Everything `decode` produces for one image.
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    pub basic_info: JxlBasicInfo,
+    /// ICC profile describing the decoded pixels. - JxlDecoderGetColorAsICCProfile()
+    pub icc_profile: Option<Vec<u8>>,
+    /// Structured color profile of the decoded pixels, when one exists. - JxlDecoderGetColorAsEncodedProfile()
+    pub color_encoding: Option<JxlColorEncoding>,
+    /// Layout of `DecodedFrame::pixels`.
+    pub pixel_format: JxlPixelFormat,
+    pub extra_channels: Vec<ExtraChannel>,
+    pub frames: Vec<DecodedFrame>,
+}
+
+impl DecodedImage {
+    pub fn width(&self) -> u32 {
+        self.basic_info.xsize
+    }
+
+    pub fn height(&self) -> u32 {
+        self.basic_info.ysize
+    }
+}
+
+pub(crate) fn bytes_per_sample(data_type: JxlDataType) -> usize {
+    match data_type {
+        JxlDataType::Uint8 => 1,
+        JxlDataType::Uint16 | JxlDataType::Float16 => 2,
+        JxlDataType::Float => 4,
+    }
+}
+
+/// Picks the smallest sample type that holds the image without loss.
+fn default_data_type(info: &JxlBasicInfo) -> JxlDataType {
+    if info.exponent_bits_per_sample > 0 || info.bits_per_sample > 16 {
+        JxlDataType::Float
+    } else if info.bits_per_sample > 8 {
+        JxlDataType::Uint16
+    } else {
+        JxlDataType::Uint8
+    }
+}
+
+impl JxlDecoder {
+    /// Decodes a complete image held in memory.
+    ///
+    /// Resets the decoder first; an attached parallel runner is kept.
+    pub fn decode(&self, data: &[u8], options: &DecodeOptions) -> Result<DecodedImage, JxlError> {
+        let mut data = Some(data);
+        self.decode_with(options, || match data.take() {
+            Some(data) => {
+                self.set_input(data)?;
+                self.close_input();
+                Ok(())
+            }
+            None => Err(JxlError::NotEnoughInput),
+        })
+    }
+
+    /// Runs the decode loop, calling `need_input` on every `NeedMoreInput`.
+    ///
+    /// No input is set when this starts, so `need_input` provides the first chunk too.
+    pub(crate) fn decode_with<F>(
+        &self,
+        options: &DecodeOptions,
+        mut need_input: F,
+    ) -> Result<DecodedImage, JxlError>
+    where
+        F: FnMut() -> Result<(), JxlError>,
+    {
+        let mut reader = FrameReader::new(self, options)?;
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.next_frame(&mut need_input)? {
+            frames.push(frame);
+        }
+
+        Ok(DecodedImage {
+            basic_info: reader.basic_info.take().ok_or(JxlError::DecodingFailed)?,
+            icc_profile: reader.icc_profile.take(),
+            color_encoding: reader.color_encoding.take(),
+            pixel_format: mem::take(&mut reader.pixel_format),
+            extra_channels: mem::take(&mut reader.extra_channels),
+            frames,
+        })
+    }
+}
+
+/// Walks an image frame by frame; shared by `decode` and `frames`.
+pub(crate) struct FrameReader<'d> {
+    pub(crate) decoder: &'d JxlDecoder,
+    options: DecodeOptions,
+    pub(crate) basic_info: Option<JxlBasicInfo>,
+    pub(crate) pixel_format: JxlPixelFormat,
+    pub(crate) icc_profile: Option<Vec<u8>>,
+    pub(crate) color_encoding: Option<JxlColorEncoding>,
+    pub(crate) extra_channels: Vec<ExtraChannel>,
+    // libjxl writes into these buffers until the FullImage event
+    pending: Option<DecodedFrame>,
+}
+
+impl<'d> FrameReader<'d> {
+    /// Resets `decoder` and applies `options`; an attached parallel runner is kept.
+    pub(crate) fn new(decoder: &'d JxlDecoder, options: &DecodeOptions) -> Result<Self, JxlError> {
+        decoder.reset()?;
+        decoder.subscribe_events(
+            JxlDecoderStatus::BasicInfo
+                | JxlDecoderStatus::ColorEncoding
+                | JxlDecoderStatus::Frame
+                | JxlDecoderStatus::FullImage,
+        )?;
+        decoder.set_coalescing(options.coalescing)?;
+        decoder.set_keep_orientation(options.keep_orientation)?;
+        decoder.set_unpremultiply_alpha(options.unpremultiply_alpha)?;
+        decoder.set_render_spotcolors(options.render_spotcolors)?;
+        if let Some(target) = options.desired_intensity_target {
+            decoder.set_desired_intensity_target(target)?;
+        }
+
+        Ok(FrameReader {
+            decoder,
+            options: options.clone(),
+            basic_info: None,
+            pixel_format: JxlPixelFormat::default(),
+            icc_profile: None,
+            color_encoding: None,
+            extra_channels: Vec::new(),
+            pending: None,
+        })
+    }
+
+    /// Decodes up to the end of the next frame; `None` once the image is done.
+    pub(crate) fn next_frame<F>(
+        &mut self,
+        need_input: &mut F,
+    ) -> Result<Option<DecodedFrame>, JxlError>
+    where
+        F: FnMut() -> Result<(), JxlError>,
+    {
+        let decoder = self.decoder;
+        loop {
+            match decoder.process_input()? {
+                JxlDecoderStatus::BasicInfo => {
+                    let info = decoder.get_basic_info()?;
+                    let data_type = self
+                        .options
+                        .data_type
+                        .unwrap_or_else(|| default_data_type(&info));
+                    let num_channels = self.options.num_channels.unwrap_or(
+                        info.num_color_channels + if info.alpha_bits > 0 { 1 } else { 0 },
+                    );
+                    self.pixel_format = JxlPixelFormat {
+                        num_channels,
+                        data_type,
+                        endianness: JxlEndianness::NativeEndian,
+                        align: 0,
+                    };
+                    for index in 0..info.num_extra_channels as usize {
+                        self.extra_channels.push(ExtraChannel {
+                            info: decoder.get_extra_channel_info(index)?,
+                            name: decoder.get_extra_channel_name(index)?,
+                        });
+                    }
+                    self.basic_info = Some(info);
+                }
+                JxlDecoderStatus::ColorEncoding => {
+                    let target = JxlColorProfileTarget::Data;
+                    self.color_encoding = decoder.get_color_as_encoded_profile(target).ok();
+                    let mut size = 0;
+                    if decoder
+                        .get_icc_profile_size(target, Some(&mut size))
+                        .is_ok()
+                        && size > 0
+                    {
+                        let mut icc = vec![0u8; size];
+                        decoder.get_color_as_icc_profile(target, &mut icc, size)?;
+                        self.icc_profile = Some(icc);
+                    }
+                }
+                JxlDecoderStatus::Frame => {
+                    let header = decoder.get_frame_header()?;
+                    let name = if header.name_length > 0 {
+                        decoder.get_frame_name()?
+                    } else {
+                        String::new()
+                    };
+                    self.pending = Some(DecodedFrame {
+                        header,
+                        name,
+                        pixels: PixelBuffer::U8(Vec::new()),
+                        extra_channels: Vec::new(),
+                    });
+                }
+                JxlDecoderStatus::NeedImageOutBuffer => {
+                    let pixel_format = &self.pixel_format;
+                    let frame = self.pending.as_mut().ok_or(JxlError::DecodingFailed)?;
+                    let size = decoder.image_out_buffer_size(pixel_format)?;
+                    frame.pixels = PixelBuffer::with_byte_size(pixel_format.data_type, size);
+                    decoder.set_image_out_buffer(pixel_format, frame.pixels.as_bytes_mut())?;
+
+                    if self.options.extra_channels {
+                        let channel_format = JxlPixelFormat {
+                            num_channels: 1,
+                            ..pixel_format.clone()
+                        };
+                        frame.extra_channels.clear();
+                        for index in 0..self.extra_channels.len() as u32 {
+                            let size = decoder.extra_channel_buffer_size(&channel_format, index)?;
+                            frame
+                                .extra_channels
+                                .push(PixelBuffer::with_byte_size(channel_format.data_type, size));
+                        }
+                        for (index, buffer) in frame.extra_channels.iter_mut().enumerate() {
+                            decoder.set_extra_channel_buffer(
+                                &channel_format,
+                                buffer.as_bytes_mut(),
+                                index as u32,
+                            )?;
+                        }
+                    }
+                }
+                JxlDecoderStatus::FullImage => {
+                    if let Some(frame) = self.pending.take() {
+                        return Ok(Some(frame));
+                    }
+                }
+                JxlDecoderStatus::Success => return Ok(None),
+                JxlDecoderStatus::NeedMoreInput => need_input()?,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for FrameReader<'_> {
+    fn drop(&mut self) {
+        // 디코더가 아직 pending 버퍼를 가리키고 있으므로 해제 전에 되감음
+        if self.pending.is_some() {
+            self.decoder.rewind();
+        }
+    }
+}
+
+/// Decodes a complete image with a fresh decoder on `lib`.
+pub fn decode_to_image(
+    lib: &Arc<JxlLibrary>,
+    data: &[u8],
+    options: &DecodeOptions,
+) -> Result<DecodedImage, JxlError> {
+    JxlDecoder::with_library(lib.clone())?.decode(data, options)
+}
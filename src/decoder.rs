@@ -1,5 +1,6 @@
 use crate::decoder_enum::*;
 use crate::decoder_struct::*;
+use crate::ImageOutSlot;
 use crate::JxlBasicInfo;
 use crate::JxlBitDepth;
 use crate::JxlBlendInfo;
@@ -8,25 +9,31 @@ use crate::JxlColorEncoding;
 use crate::JxlError;
 use crate::JxlExtraChannelInfo;
 use crate::JxlFrameHeader;
+use crate::JxlLibrary;
 use crate::JxlPixelFormat;
+use crate::ParallelRunner;
 
-use libloading::{Library, Symbol};
-use std::ffi::{c_char, c_float, c_int, c_void};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, OsStr};
 use std::ptr;
+use std::sync::Arc;
 
 /// Represents a JPEG XL decoder.
 pub struct JxlDecoder {
-    lib: Library,
+    lib: Arc<JxlLibrary>,
     pub dec: *mut c_void,
+    // libjxl keeps calling into the runner until the decoder is destroyed
+    runner: Option<Arc<dyn ParallelRunner>>,
+    // JxlDecoderReset detaches the runner, so it is attached again from here
+    attached_runner: Option<(JxlParallelRunner, *mut c_void)>,
+    // libjxl calls the image-out closure until the frame is done or the decoder is reset
+    pub(crate) image_out: RefCell<Option<ImageOutSlot>>,
 }
 
 impl JxlDecoder {
     /// Gets the version of the JPEG XL decoder. - JxlDecoderVersion()
     pub fn version(&self) -> Result<(u32, u32, u32), JxlError> {
-        let get_version: Symbol<unsafe extern "C" fn() -> u32> =
-            unsafe { self.lib.get(b"JxlDecoderVersion") }.map_err(JxlError::SymbolLoadFailed)?;
-
-        let version = unsafe { get_version() };
+        let version = unsafe { (self.lib.fns.decoder_version)() };
         let major = version / 1_000_000;
         let minor = (version % 1_000_000) / 1_000;
         let patch = version % 1_000;
@@ -38,10 +45,7 @@ impl JxlDecoder {
     /// Checks if the passed buffer contains a valid JPEG XL signature. The passed buf of size size doesn’t need to be a full image, only the beginning of the file.
     /// 전달된 버퍼에 유효한 JPEG XL 서명이 포함되어 있는지 확인합니다. 전달된 buf의 크기 size는 전체 이미지일 필요가 없으며, 파일의 시작 부분만 있으면 됩니다.
     pub fn check_signature(&self, data: &[u8]) -> Result<JxlSignature, JxlError> {
-        let signature_check: Symbol<unsafe extern "C" fn(*const u8, usize) -> c_int> =
-            unsafe { self.lib.get(b"JxlSignatureCheck") }.map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { signature_check(data.as_ptr(), data.len()) };
+        let result = unsafe { (self.lib.fns.signature_check)(data.as_ptr(), data.len()) };
         match result {
             0 => Ok(JxlSignature::NotEnoughBytes),
             1 => Ok(JxlSignature::Invalid),
@@ -51,52 +55,54 @@ impl JxlDecoder {
         }
     }
 
-    /// Creates a new JPEG XL decoder. - JxlDecoderCreate()
-    pub fn new(dll_path: &str) -> Result<Self, JxlError> {
-        let lib = unsafe { Library::new(dll_path) }.map_err(JxlError::LibraryLoadFailed)?;
-
-        let create: Symbol<unsafe extern "C" fn(*const c_void) -> *mut c_void> =
-            unsafe { lib.get(b"JxlDecoderCreate") }.map_err(JxlError::SymbolLoadFailed)?;
+    /// Creates a new JPEG XL decoder, loading libjxl from `dll_path`. - JxlDecoderCreate()
+    pub fn new<P: AsRef<OsStr>>(dll_path: P) -> Result<Self, JxlError> {
+        Self::with_library(JxlLibrary::load(dll_path)?)
+    }
 
-        let dec = unsafe { create(ptr::null()) };
+    /// Creates a new JPEG XL decoder on an already loaded library. - JxlDecoderCreate()
+    pub fn with_library(lib: Arc<JxlLibrary>) -> Result<Self, JxlError> {
+        let dec = unsafe { (lib.fns.decoder_create)(ptr::null()) };
         if dec.is_null() {
             return Err(JxlError::DecoderCreationFailed);
         }
 
-        Ok(JxlDecoder { lib, dec })
+        Ok(JxlDecoder {
+            lib,
+            dec,
+            runner: None,
+            attached_runner: None,
+            image_out: RefCell::new(None),
+        })
     }
 
-    /// Resets the decoder. - JxlDecoderReset()
-    pub fn reset(&self) {
-        let reset: Symbol<unsafe extern "C" fn(*mut c_void)> =
-            unsafe { self.lib.get(b"JxlDecoderReset").unwrap() };
+    /// Returns the library this decoder was created from.
+    pub fn library(&self) -> &Arc<JxlLibrary> {
+        &self.lib
+    }
 
-        unsafe { reset(self.dec) };
+    /// Resets the decoder. - JxlDecoderReset()
+    /// libjxl detaches the parallel runner on reset; it is attached again.
+    pub fn reset(&self) -> Result<(), JxlError> {
+        unsafe { (self.lib.fns.decoder_reset)(self.dec) };
+        self.clear_image_out();
+        self.restore_parallel_runner()
     }
 
     /// Rewinds the decoder to the beginning of the input. - JxlDecoderRewind()
     pub fn rewind(&self) {
-        let rewind: Symbol<unsafe extern "C" fn(*mut c_void)> =
-            unsafe { self.lib.get(b"JxlDecoderRewind").unwrap() };
-
-        unsafe { rewind(self.dec) };
+        unsafe { (self.lib.fns.decoder_rewind)(self.dec) };
+        self.clear_image_out();
     }
 
     /// Skips a specified number of frames. - JxlDecoderSkipFrames()
     pub fn skip_frames(&self, amount: usize) {
-        let skip_frames: Symbol<unsafe extern "C" fn(*mut c_void, usize)> =
-            unsafe { self.lib.get(b"JxlDecoderSkipFrames").unwrap() };
-
-        unsafe { skip_frames(self.dec, amount) };
+        unsafe { (self.lib.fns.decoder_skip_frames)(self.dec, amount) };
     }
 
     /// Skips decoding the current frame. - JxlDecoderSkipCurrentFrame()
     pub fn skip_current_frame(&self) -> Result<(), JxlError> {
-        let skip_current_frame: Symbol<unsafe extern "C" fn(*mut c_void) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSkipCurrentFrame") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { skip_current_frame(self.dec) };
+        let result = unsafe { (self.lib.fns.decoder_skip_current_frame)(self.dec) };
         if result == 0 {
             Ok(())
         } else {
@@ -105,18 +111,51 @@ impl JxlDecoder {
     }
 
     /// Sets the parallel runner for multithreading. - JxlDecoderSetParallelRunner()
-    pub fn set_parallel_runner(
-        &self,
+    /// The decoder keeps `runner` alive until it is replaced or the decoder is dropped.
+    /// Must be called before the first `process_input`.
+    pub fn set_parallel_runner<R: ParallelRunner + 'static>(
+        &mut self,
+        runner: Arc<R>,
+    ) -> Result<(), JxlError> {
+        unsafe { self.set_parallel_runner_raw(Some(runner.runner_fn()), runner.runner_opaque())? };
+        self.runner = Some(runner);
+        Ok(())
+    }
+
+    /// Sets a raw parallel runner. - JxlDecoderSetParallelRunner()
+    /// `reset` attaches it again, like one set with `set_parallel_runner`.
+    ///
+    /// # Safety
+    ///
+    /// `parallel_runner_opaque` must stay valid for `parallel_runner` until the decoder is
+    /// dropped or another runner is set.
+    pub unsafe fn set_parallel_runner_raw(
+        &mut self,
         parallel_runner: Option<JxlParallelRunner>,
         parallel_runner_opaque: *mut c_void,
     ) -> Result<(), JxlError> {
-        let set_parallel_runner: Symbol<
-            unsafe extern "C" fn(*mut c_void, Option<JxlParallelRunner>, *mut c_void) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetParallelRunner") }
-            .map_err(JxlError::SymbolLoadFailed)?;
+        let result = (self.lib.fns.decoder_set_parallel_runner)(
+            self.dec,
+            parallel_runner,
+            parallel_runner_opaque,
+        );
+        if result == 0 {
+            self.runner = None;
+            self.attached_runner = parallel_runner.map(|f| (f, parallel_runner_opaque));
+            Ok(())
+        } else {
+            Err(JxlError::SetParallelRunnerFailed)
+        }
+    }
 
-        let result =
-            unsafe { set_parallel_runner(self.dec, parallel_runner, parallel_runner_opaque) };
+    /// Attaches the last runner again; `JxlDecoderReset` detaches it.
+    fn restore_parallel_runner(&self) -> Result<(), JxlError> {
+        let Some((runner_fn, opaque)) = self.attached_runner else {
+            return Ok(());
+        };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_parallel_runner)(self.dec, Some(runner_fn), opaque)
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -126,19 +165,12 @@ impl JxlDecoder {
 
     /// Gets the size hint for basic info. - JxlDecoderSizeHintBasicInfo()
     pub fn size_hint_basic_info(&self) -> usize {
-        let size_hint_basic_info: Symbol<unsafe extern "C" fn(*const c_void) -> usize> =
-            unsafe { self.lib.get(b"JxlDecoderSizeHintBasicInfo").unwrap() };
-
-        unsafe { size_hint_basic_info(self.dec) }
+        unsafe { (self.lib.fns.decoder_size_hint_basic_info)(self.dec) }
     }
 
     /// Subscribes to decoder events. - JxlDecoderSubscribeEvents()
     pub fn subscribe_events(&self, events: i32) -> Result<(), JxlError> {
-        let subscribe_events: Symbol<unsafe extern "C" fn(*mut c_void, c_int) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSubscribeEvents") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { subscribe_events(self.dec, events as i32) };
+        let result = unsafe { (self.lib.fns.decoder_subscribe_events)(self.dec, events) };
         if result == 0 {
             Ok(())
         } else {
@@ -148,11 +180,8 @@ impl JxlDecoder {
 
     /// Sets whether to keep the original image orientation. - JxlDecoderSetKeepOrientation()
     pub fn set_keep_orientation(&self, keep: bool) -> Result<(), JxlError> {
-        let set_keep_orientation: Symbol<unsafe extern "C" fn(*mut c_void, c_int) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetKeepOrientation") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_keep_orientation(self.dec, keep as c_int) };
+        let result =
+            unsafe { (self.lib.fns.decoder_set_keep_orientation)(self.dec, keep as c_int) };
         if result == 0 {
             Ok(())
         } else {
@@ -162,12 +191,12 @@ impl JxlDecoder {
 
     /// Sets the unpremultiply alpha option. - JxlDecoderSetUnpremultiplyAlpha()
     pub fn set_unpremultiply_alpha(&self, unpremultiply_alpha: bool) -> Result<(), JxlError> {
-        let set_unpremultiply_alpha: Symbol<unsafe extern "C" fn(*mut c_void, c_int) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetUnpremultiplyAlpha") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result =
-            unsafe { set_unpremultiply_alpha(self.dec, if unpremultiply_alpha { 1 } else { 0 }) };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_unpremultiply_alpha)(
+                self.dec,
+                if unpremultiply_alpha { 1 } else { 0 },
+            )
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -177,12 +206,12 @@ impl JxlDecoder {
 
     /// Sets the render spotcolors option. - JxlDecoderSetRenderSpotcolors()
     pub fn set_render_spotcolors(&self, render_spotcolors: bool) -> Result<(), JxlError> {
-        let set_render_spotcolors: Symbol<unsafe extern "C" fn(*mut c_void, c_int) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetRenderSpotcolors") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result =
-            unsafe { set_render_spotcolors(self.dec, if render_spotcolors { 1 } else { 0 }) };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_render_spotcolors)(
+                self.dec,
+                if render_spotcolors { 1 } else { 0 },
+            )
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -192,11 +221,9 @@ impl JxlDecoder {
 
     /// Sets the coalescing option for animated images. - JxlDecoderSetCoalescing()
     pub fn set_coalescing(&self, coalescing: bool) -> Result<(), JxlError> {
-        let set_coalescing: Symbol<unsafe extern "C" fn(*mut c_void, c_int) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetCoalescing") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result_bits = unsafe { set_coalescing(self.dec, if coalescing { 1 } else { 0 }) };
+        let result_bits = unsafe {
+            (self.lib.fns.decoder_set_coalescing)(self.dec, if coalescing { 1 } else { 0 })
+        };
         let result = JxlDecoderStatus::from_bits(result_bits)?;
         if result == JxlDecoderStatus::Success {
             Ok(())
@@ -207,30 +234,19 @@ impl JxlDecoder {
 
     /// Processes the input data. - JxlDecoderProcessInput()
     pub fn process_input(&self) -> Result<JxlDecoderStatus, JxlError> {
-        let process_input: Symbol<unsafe extern "C" fn(*mut c_void) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderProcessInput") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { process_input(self.dec) };
-        match result {
-            0 => Ok(JxlDecoderStatus::Success),
-            1 => Err(JxlError::DecodingFailed),
-            2 => Ok(JxlDecoderStatus::NeedMoreInput),
-            64 => Ok(JxlDecoderStatus::BasicInfo),
-            128 => Ok(JxlDecoderStatus::ColorEncoding),
-            256 => Ok(JxlDecoderStatus::PreviewImage),
-            512 => Ok(JxlDecoderStatus::Frame),
-            1024 => Ok(JxlDecoderStatus::FullImage),
-            _ => Err(JxlError::InvalidInput(result.to_string())),
+        let result = unsafe { (self.lib.fns.decoder_process_input)(self.dec) };
+        let status = JxlDecoderStatus::from_bits(result)?;
+        self.finish_image_out(status);
+        match status {
+            JxlDecoderStatus::Error => Err(JxlError::DecodingFailed),
+            status => Ok(status),
         }
     }
 
     /// Sets the input data for the decoder. - JxlDecoderSetInput()
     pub fn set_input(&self, data: &[u8]) -> Result<(), JxlError> {
-        let set_input: Symbol<unsafe extern "C" fn(*mut c_void, *const u8, usize) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetInput") }.map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_input(self.dec, data.as_ptr(), data.len()) };
+        let result =
+            unsafe { (self.lib.fns.decoder_set_input)(self.dec, data.as_ptr(), data.len()) };
         if result == 0 {
             Ok(())
         } else {
@@ -240,29 +256,18 @@ impl JxlDecoder {
 
     /// Releases input which was provided with JxlDecoderSetInput. - JxlDecoderReleaseInput()
     pub fn release_input(&self) -> usize {
-        let release_input: Symbol<unsafe extern "C" fn(*mut c_void) -> usize> =
-            unsafe { self.lib.get(b"JxlDecoderReleaseInput").unwrap() };
-
-        unsafe { release_input(self.dec) }
+        unsafe { (self.lib.fns.decoder_release_input)(self.dec) }
     }
 
     /// Closes the input, indicating no more input will be set. - JxlDecoderCloseInput()
     pub fn close_input(&self) {
-        let close_input: Symbol<unsafe extern "C" fn(*mut c_void)> =
-            unsafe { self.lib.get(b"JxlDecoderCloseInput").unwrap() };
-
-        unsafe { close_input(self.dec) };
+        unsafe { (self.lib.fns.decoder_close_input)(self.dec) };
     }
 
     /// Gets the basic information about the image. - JxlDecoderGetBasicInfo()
     pub fn get_basic_info(&self) -> Result<JxlBasicInfo, JxlError> {
-        let get_basic_info: Symbol<
-            unsafe extern "C" fn(*const c_void, *mut JxlBasicInfo) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetBasicInfo") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut info = JxlBasicInfo::default();
-        let result = unsafe { get_basic_info(self.dec, &mut info) };
+        let result = unsafe { (self.lib.fns.decoder_get_basic_info)(self.dec, &mut info) };
         if result == 0 {
             Ok(info)
         } else {
@@ -272,13 +277,10 @@ impl JxlDecoder {
 
     /// Gets the extra channel information. - JxlDecoderGetExtraChannelInfo()
     pub fn get_extra_channel_info(&self, index: usize) -> Result<JxlExtraChannelInfo, JxlError> {
-        let get_extra_channel_info: Symbol<
-            unsafe extern "C" fn(*const c_void, usize, *mut JxlExtraChannelInfo) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetExtraChannelInfo") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut extra_channel_info = JxlExtraChannelInfo::default();
-        let result = unsafe { get_extra_channel_info(self.dec, index, &mut extra_channel_info) };
+        let result = unsafe {
+            (self.lib.fns.decoder_get_extra_channel_info)(self.dec, index, &mut extra_channel_info)
+        };
         if result == 0 {
             Ok(extra_channel_info)
         } else {
@@ -288,20 +290,17 @@ impl JxlDecoder {
 
     /// Gets the name of an extra channel. - JxlDecoderGetExtraChannelName()
     pub fn get_extra_channel_name(&self, index: usize) -> Result<String, JxlError> {
-        let get_extra_channel_name: Symbol<
-            unsafe extern "C" fn(*const c_void, usize, *mut c_char, usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetExtraChannelName") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let size: usize = 0;
-        let result = unsafe { get_extra_channel_name(self.dec, index, ptr::null_mut(), size) };
-        if result != 0 {
-            return Err(JxlError::ExtraChannelError(result.to_string()));
-        }
+        // 이름 길이는 널 문자를 제외한 값
+        let size = self.get_extra_channel_info(index)?.name_length as usize + 1;
 
         let mut buffer = vec![0u8; size];
         let result = unsafe {
-            get_extra_channel_name(self.dec, index, buffer.as_mut_ptr() as *mut c_char, size)
+            (self.lib.fns.decoder_get_extra_channel_name)(
+                self.dec,
+                index,
+                buffer.as_mut_ptr() as *mut c_char,
+                size,
+            )
         };
         if result == 0 {
             Ok(String::from_utf8_lossy(&buffer[..size - 1]).into_owned())
@@ -320,17 +319,14 @@ impl JxlDecoder {
         &self,
         target: JxlColorProfileTarget,
     ) -> Result<JxlColorEncoding, JxlError> {
-        let get_color_as_encoded_profile: Symbol<
-            unsafe extern "C" fn(
-                *const c_void,
-                JxlColorProfileTarget,
-                *mut JxlColorEncoding,
-            ) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetColorAsEncodedProfile") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut color_encoding = JxlColorEncoding::default();
-        let result = unsafe { get_color_as_encoded_profile(self.dec, target, &mut color_encoding) };
+        let result = unsafe {
+            (self.lib.fns.decoder_get_color_as_encoded_profile)(
+                self.dec,
+                target,
+                &mut color_encoding,
+            )
+        };
         if result == 0 {
             Ok(color_encoding)
         } else {
@@ -343,7 +339,7 @@ impl JxlDecoder {
     /// # Arguments
     ///
     /// * `target` - Whether to get the original color profile from the metadata
-    ///              or the color profile of the decoded pixels.
+    ///   or the color profile of the decoded pixels.
     /// * `size` - Optional pointer to output the size into. If None, only checks the return status.
     ///
     /// # Returns
@@ -356,17 +352,8 @@ impl JxlDecoder {
         target: JxlColorProfileTarget,
         size: Option<&mut usize>,
     ) -> Result<(), JxlError> {
-        let get_icc_profile_size: Symbol<
-            unsafe extern "C" fn(
-                *const c_void,
-                JxlColorProfileTarget,
-                *mut usize,
-            ) -> JxlDecoderStatus,
-        > = unsafe { self.lib.get(b"JxlDecoderGetICCProfileSize") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let status = unsafe {
-            get_icc_profile_size(
+            (self.lib.fns.decoder_get_icc_profile_size)(
                 self.dec,
                 target,
                 size.map_or(std::ptr::null_mut(), |s| s as *mut usize),
@@ -385,7 +372,7 @@ impl JxlDecoder {
     /// # Arguments
     ///
     /// * `target` - Whether to get the original color profile from the metadata
-    ///              or the color profile of the decoded pixels
+    ///   or the color profile of the decoded pixels
     /// * `icc_profile` - Buffer to copy the ICC profile into
     /// * `size` - Size of the icc_profile buffer in bytes
     ///
@@ -399,17 +386,14 @@ impl JxlDecoder {
         icc_profile: &mut [u8],
         size: usize,
     ) -> Result<(), JxlError> {
-        let get_color_as_icc: Symbol<
-            unsafe extern "C" fn(
-                *const c_void,
-                JxlColorProfileTarget,
-                *mut u8,
-                usize,
-            ) -> JxlDecoderStatus,
-        > = unsafe { self.lib.get(b"JxlDecoderGetColorAsICCProfile") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let status = unsafe { get_color_as_icc(self.dec, target, icc_profile.as_mut_ptr(), size) };
+        let status = unsafe {
+            (self.lib.fns.decoder_get_color_as_icc_profile)(
+                self.dec,
+                target,
+                icc_profile.as_mut_ptr(),
+                size,
+            )
+        };
 
         match status {
             JxlDecoderStatus::Success => Ok(()),
@@ -425,12 +409,8 @@ impl JxlDecoder {
     ) -> Result<(), JxlError> {
         use crate::decoder_enum::JxlDecoderStatus;
 
-        let set_preferred_color_profile: Symbol<
-            unsafe extern "C" fn(*mut c_void, *const JxlColorEncoding) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetPreferredColorProfile") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_preferred_color_profile(self.dec, color_encoding) };
+        let result =
+            unsafe { (self.lib.fns.decoder_set_preferred_color_profile)(self.dec, color_encoding) };
         // 0 is JXL_DEC_SUCCESS
 
         let result = JxlDecoderStatus::from_bits(result)?;
@@ -447,12 +427,9 @@ impl JxlDecoder {
         &self,
         desired_intensity_target: f32,
     ) -> Result<(), JxlError> {
-        let set_desired_intensity_target: Symbol<
-            unsafe extern "C" fn(*mut c_void, c_float) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetDesiredIntensityTarget") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_desired_intensity_target(self.dec, desired_intensity_target) };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_desired_intensity_target)(self.dec, desired_intensity_target)
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -466,11 +443,6 @@ impl JxlDecoder {
         color_encoding: Option<&JxlColorEncoding>,
         icc_data: Option<&[u8]>,
     ) -> Result<(), JxlError> {
-        let set_output_color_profile: Symbol<
-            unsafe extern "C" fn(*mut c_void, *const JxlColorEncoding, *const u8, usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetOutputColorProfile") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let (color_encoding_ptr, icc_data_ptr, icc_size) = match (color_encoding, icc_data) {
             (Some(ce), None) => (ce as *const JxlColorEncoding, std::ptr::null(), 0),
             (None, Some(icc)) => (std::ptr::null(), icc.as_ptr(), icc.len()),
@@ -482,7 +454,12 @@ impl JxlDecoder {
         };
 
         let result = unsafe {
-            set_output_color_profile(self.dec, color_encoding_ptr, icc_data_ptr, icc_size)
+            (self.lib.fns.decoder_set_output_color_profile)(
+                self.dec,
+                color_encoding_ptr,
+                icc_data_ptr,
+                icc_size,
+            )
         };
         if result == 0 {
             Ok(())
@@ -492,11 +469,8 @@ impl JxlDecoder {
     }
 
     /// Sets the color management system (CMS) to use for color conversions. - JxlDecoderSetCms()
-    pub fn set_cms(&self, cms: &JxlCmsInterface) -> Result<(), JxlError> {
-        let set_cms: Symbol<unsafe extern "C" fn(*mut c_void, *const JxlCmsInterface) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetCms") }.map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_cms(self.dec, cms) };
+    pub fn set_cms(&self, cms: JxlCmsInterface) -> Result<(), JxlError> {
+        let result = unsafe { (self.lib.fns.decoder_set_cms)(self.dec, cms) };
         if result == 0 {
             Ok(())
         } else {
@@ -506,13 +480,9 @@ impl JxlDecoder {
 
     /// Gets the size needed for the preview image output buffer. - JxlDecoderPreviewOutBufferSize()
     pub fn preview_out_buffer_size(&self, format: &JxlPixelFormat) -> Result<usize, JxlError> {
-        let preview_out_buffer_size: Symbol<
-            unsafe extern "C" fn(*const c_void, *const JxlPixelFormat, *mut usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderPreviewOutBufferSize") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut size = 0;
-        let result = unsafe { preview_out_buffer_size(self.dec, format, &mut size) };
+        let result =
+            unsafe { (self.lib.fns.decoder_preview_out_buffer_size)(self.dec, format, &mut size) };
         if result == 0 {
             Ok(size)
         } else {
@@ -526,13 +496,8 @@ impl JxlDecoder {
         format: &JxlPixelFormat,
         buffer: &mut [u8],
     ) -> Result<(), JxlError> {
-        let set_preview_out_buffer: Symbol<
-            unsafe extern "C" fn(*mut c_void, *const JxlPixelFormat, *mut c_void, usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetPreviewOutBuffer") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let result = unsafe {
-            set_preview_out_buffer(
+            (self.lib.fns.decoder_set_preview_out_buffer)(
                 self.dec,
                 format,
                 buffer.as_mut_ptr() as *mut c_void,
@@ -548,13 +513,9 @@ impl JxlDecoder {
 
     /// Gets the frame header for the current frame. - JxlDecoderGetFrameHeader()
     pub fn get_frame_header(&self) -> Result<JxlFrameHeader, JxlError> {
-        let get_frame_header: Symbol<
-            unsafe extern "C" fn(*const c_void, *mut JxlFrameHeader) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetFrameHeader") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut frame_header = JxlFrameHeader::default();
-        let result = unsafe { get_frame_header(self.dec, &mut frame_header) };
+        let result =
+            unsafe { (self.lib.fns.decoder_get_frame_header)(self.dec, &mut frame_header) };
         if result == 0 {
             Ok(frame_header)
         } else {
@@ -564,14 +525,14 @@ impl JxlDecoder {
 
     /// Gets the name of the current frame. - JxlDecoderGetFrameName()
     pub fn get_frame_name(&self) -> Result<String, JxlError> {
-        let get_frame_name: Symbol<
-            unsafe extern "C" fn(*const c_void, *mut c_char, usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetFrameName") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let mut buffer = vec![0u8; 256]; // Assuming a reasonable max length for frame names
-        let result =
-            unsafe { get_frame_name(self.dec, buffer.as_mut_ptr() as *mut c_char, buffer.len()) };
+        let mut buffer = vec![0u8; self.get_frame_header()?.name_length as usize + 1];
+        let result = unsafe {
+            (self.lib.fns.decoder_get_frame_name)(
+                self.dec,
+                buffer.as_mut_ptr() as *mut c_char,
+                buffer.len(),
+            )
+        };
         if result == 0 {
             Ok(String::from_utf8_lossy(&buffer)
                 .trim_end_matches('\0')
@@ -583,13 +544,10 @@ impl JxlDecoder {
 
     /// Gets the blend info for an extra channel in the current frame. - JxlDecoderGetExtraChannelBlendInfo()
     pub fn get_extra_channel_blend_info(&self, index: usize) -> Result<JxlBlendInfo, JxlError> {
-        let get_extra_channel_blend_info: Symbol<
-            unsafe extern "C" fn(*const c_void, usize, *mut JxlBlendInfo) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderGetExtraChannelBlendInfo") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut blend_info = JxlBlendInfo::default();
-        let result = unsafe { get_extra_channel_blend_info(self.dec, index, &mut blend_info) };
+        let result = unsafe {
+            (self.lib.fns.decoder_get_extra_channel_blend_info)(self.dec, index, &mut blend_info)
+        };
         if result == 0 {
             Ok(blend_info)
         } else {
@@ -599,13 +557,9 @@ impl JxlDecoder {
 
     /// Gets the output buffer size needed for the decoded image. - JxlDecoderImageOutBufferSize()
     pub fn image_out_buffer_size(&self, format: &JxlPixelFormat) -> Result<usize, JxlError> {
-        let image_out_buffer_size: Symbol<
-            unsafe extern "C" fn(*const c_void, *const JxlPixelFormat, *mut usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderImageOutBufferSize") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut size = 0;
-        let result = unsafe { image_out_buffer_size(self.dec, format, &mut size) };
+        let result =
+            unsafe { (self.lib.fns.decoder_image_out_buffer_size)(self.dec, format, &mut size) };
         if result == 0 {
             Ok(size)
         } else {
@@ -619,13 +573,8 @@ impl JxlDecoder {
         format: &JxlPixelFormat,
         buffer: &mut [u8],
     ) -> Result<(), JxlError> {
-        let set_image_out_buffer: Symbol<
-            unsafe extern "C" fn(*mut c_void, *const JxlPixelFormat, *mut c_void, usize) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetImageOutBuffer") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let result = unsafe {
-            set_image_out_buffer(
+            (self.lib.fns.decoder_set_image_out_buffer)(
                 self.dec,
                 format,
                 buffer.as_mut_ptr() as *mut c_void,
@@ -640,169 +589,16 @@ impl JxlDecoder {
         }
     }
 
-    /// Sets a callback for progressive image output. - JxlDecoderSetImageOutCallback()
-    pub fn set_image_out_callback<F>(
-        &self,
-        format: &JxlPixelFormat,
-        callback: F,
-    ) -> Result<(), JxlError>
-    where
-        F: FnMut(*mut c_void, usize, usize, usize, *const c_void) + 'static,
-    {
-        let set_image_out_callback: Symbol<
-            unsafe extern "C" fn(
-                *mut c_void,
-                *const JxlPixelFormat,
-                unsafe extern "C" fn(*mut c_void, usize, usize, usize, *const c_void),
-                *mut c_void,
-            ) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetImageOutCallback") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let callback_ptr = Box::into_raw(Box::new(callback)) as *mut c_void;
-
-        unsafe extern "C" fn callback_wrapper<F>(
-            opaque: *mut c_void,
-            x: usize,
-            y: usize,
-            num_pixels: usize,
-            pixels: *const c_void,
-        ) where
-            F: FnMut(*mut c_void, usize, usize, usize, *const c_void),
-        {
-            let callback = &mut *(opaque as *mut F);
-            callback(opaque, x, y, num_pixels, pixels);
-        }
-
-        let result = unsafe {
-            set_image_out_callback(self.dec, format, callback_wrapper::<F>, callback_ptr)
-        };
-        if result == 0 {
-            Ok(())
-        } else {
-            unsafe {
-                let _ = Box::from_raw(callback_ptr as *mut F);
-            }; // Clean up the box if there's an error
-            Err(JxlError::OutputBufferError)
-        }
-    }
-
-    /// Sets a multi-threaded image output callback. - JxlDecoderSetMultithreadedImageOutCallback()
-    pub fn set_multithreaded_image_out_callback<F, G, H>(
-        &self,
-        format: &JxlPixelFormat,
-        init_callback: F,
-        run_callback: G,
-        destroy_callback: Option<H>,
-        _init_opaque: *mut c_void,
-    ) -> Result<(), JxlError>
-    where
-        F: FnMut(*mut c_void, usize, usize) -> *mut c_void + 'static,
-        G: FnMut(*mut c_void, usize, usize, usize, usize, *const c_void) + 'static,
-        H: FnMut(*mut c_void) + 'static + Copy,
-    {
-        struct CallbackWrapper<F, G, H> {
-            init: F,
-            run: G,
-            destroy: Option<H>,
-        }
-
-        let callbacks = Box::new(CallbackWrapper {
-            init: init_callback,
-            run: run_callback,
-            destroy: destroy_callback,
-        });
-        let callbacks_ptr = Box::into_raw(callbacks) as *mut c_void;
-
-        unsafe extern "C" fn init_wrapper<F, G, H>(
-            init_opaque: *mut c_void,
-            num_threads: usize,
-            num_pixels_per_thread: usize,
-        ) -> *mut c_void
-        where
-            F: FnMut(*mut c_void, usize, usize) -> *mut c_void,
-        {
-            let callbacks = &mut *(init_opaque as *mut CallbackWrapper<F, G, H>);
-            (callbacks.init)(init_opaque, num_threads, num_pixels_per_thread)
-        }
-
-        unsafe extern "C" fn run_wrapper<F, G, H>(
-            run_opaque: *mut c_void,
-            thread_id: usize,
-            x: usize,
-            y: usize,
-            num_pixels: usize,
-            pixels: *const c_void,
-        ) where
-            G: FnMut(*mut c_void, usize, usize, usize, usize, *const c_void),
-        {
-            let callbacks = &mut *(run_opaque as *mut CallbackWrapper<F, G, H>);
-            (callbacks.run)(run_opaque, thread_id, x, y, num_pixels, pixels);
-        }
-
-        unsafe extern "C" fn destroy_wrapper<F, G, H>(run_opaque: *mut c_void)
-        where
-            H: FnMut(*mut c_void),
-        {
-            let mut callbacks = Box::from_raw(run_opaque as *mut CallbackWrapper<F, G, H>);
-            if let Some(ref mut destroy_callback) = callbacks.destroy {
-                destroy_callback(run_opaque);
-            }
-        }
-
-        let set_multithreaded_image_out_callback: Symbol<
-            unsafe extern "C" fn(
-                *mut c_void,
-                *const JxlPixelFormat,
-                Option<unsafe extern "C" fn(*mut c_void, usize, usize) -> *mut c_void>,
-                Option<unsafe extern "C" fn(*mut c_void, usize, usize, usize, usize, *const c_void)>,
-                Option<unsafe extern "C" fn(*mut c_void)>,
-                *mut c_void,
-            ) -> c_int,
-        > = unsafe {
-            self.lib
-                .get(b"JxlDecoderSetMultithreadedImageOutCallback")
-                .map_err(JxlError::SymbolLoadFailed)?
-        };
-
-        let result = unsafe {
-            set_multithreaded_image_out_callback(
-                self.dec,
-                format,
-                Some(init_wrapper::<F, G, H>),
-                Some(run_wrapper::<F, G, H>),
-                if destroy_callback.is_some() {
-                    Some(destroy_wrapper::<F, G, H>)
-                } else {
-                    None
-                },
-                callbacks_ptr,
-            )
-        };
-
-        if result == 0 {
-            Ok(())
-        } else {
-            unsafe {
-                let _ = Box::from_raw(callbacks_ptr as *mut CallbackWrapper<F, G, H>);
-            };
-            Err(JxlError::OutputBufferError)
-        }
-    }
-
     /// Gets the size needed for an extra channel buffer. - JxlDecoderExtraChannelBufferSize()
     pub fn extra_channel_buffer_size(
         &self,
         format: &JxlPixelFormat,
         index: u32,
     ) -> Result<usize, JxlError> {
-        let extra_channel_buffer_size: Symbol<
-            unsafe extern "C" fn(*const c_void, *const JxlPixelFormat, *mut usize, u32) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderExtraChannelBufferSize") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut size = 0;
-        let result = unsafe { extra_channel_buffer_size(self.dec, format, &mut size, index) };
+        let result = unsafe {
+            (self.lib.fns.decoder_extra_channel_buffer_size)(self.dec, format, &mut size, index)
+        };
         if result == 0 {
             Ok(size)
         } else {
@@ -817,19 +613,8 @@ impl JxlDecoder {
         buffer: &mut [u8],
         index: u32,
     ) -> Result<(), JxlError> {
-        let set_extra_channel_buffer: Symbol<
-            unsafe extern "C" fn(
-                *mut c_void,
-                *const JxlPixelFormat,
-                *mut c_void,
-                usize,
-                u32,
-            ) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetExtraChannelBuffer") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
         let result = unsafe {
-            set_extra_channel_buffer(
+            (self.lib.fns.decoder_set_extra_channel_buffer)(
                 self.dec,
                 format,
                 buffer.as_mut_ptr() as *mut c_void,
@@ -846,11 +631,9 @@ impl JxlDecoder {
 
     /// Sets the buffer for JPEG reconstruction. - JxlDecoderSetJPEGBuffer()
     pub fn set_jpeg_buffer(&self, buffer: &mut [u8]) -> Result<(), JxlError> {
-        let set_jpeg_buffer: Symbol<unsafe extern "C" fn(*mut c_void, *mut u8, usize) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetJPEGBuffer") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_jpeg_buffer(self.dec, buffer.as_mut_ptr(), buffer.len()) };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_jpeg_buffer)(self.dec, buffer.as_mut_ptr(), buffer.len())
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -860,19 +643,14 @@ impl JxlDecoder {
 
     /// Releases the JPEG buffer. - JxlDecoderReleaseJPEGBuffer()
     pub fn release_jpeg_buffer(&self) -> usize {
-        let release_jpeg_buffer: Symbol<unsafe extern "C" fn(*mut c_void) -> usize> =
-            unsafe { self.lib.get(b"JxlDecoderReleaseJPEGBuffer").unwrap() };
-
-        unsafe { release_jpeg_buffer(self.dec) }
+        unsafe { (self.lib.fns.decoder_release_jpeg_buffer)(self.dec) }
     }
 
     /// Sets the buffer for box output. - JxlDecoderSetBoxBuffer()
     pub fn set_box_buffer(&self, buffer: &mut [u8]) -> Result<(), JxlError> {
-        let set_box_buffer: Symbol<unsafe extern "C" fn(*mut c_void, *mut u8, usize) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetBoxBuffer") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_box_buffer(self.dec, buffer.as_mut_ptr(), buffer.len()) };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_box_buffer)(self.dec, buffer.as_mut_ptr(), buffer.len())
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -882,19 +660,14 @@ impl JxlDecoder {
 
     /// Releases the box buffer. - JxlDecoderReleaseBoxBuffer()
     pub fn release_box_buffer(&self) -> usize {
-        let release_box_buffer: Symbol<unsafe extern "C" fn(*mut c_void) -> usize> =
-            unsafe { self.lib.get(b"JxlDecoderReleaseBoxBuffer").unwrap() };
-
-        unsafe { release_box_buffer(self.dec) }
+        unsafe { (self.lib.fns.decoder_release_box_buffer)(self.dec) }
     }
 
     /// Sets whether to decompress boxes. - JxlDecoderSetDecompressBoxes()
     pub fn set_decompress_boxes(&self, decompress: bool) -> Result<(), JxlError> {
-        let set_decompress_boxes: Symbol<unsafe extern "C" fn(*mut c_void, c_int) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderSetDecompressBoxes") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_decompress_boxes(self.dec, if decompress { 1 } else { 0 }) };
+        let result = unsafe {
+            (self.lib.fns.decoder_set_decompress_boxes)(self.dec, if decompress { 1 } else { 0 })
+        };
         if result == 0 {
             Ok(())
         } else {
@@ -902,13 +675,13 @@ impl JxlDecoder {
         }
     }
 
-    /// Gets the type of the current box. - JxlDecoderGetBoxType()
-    pub fn get_box_type(&self) -> Result<[u8; 4], JxlError> {
-        let get_box_type: Symbol<unsafe extern "C" fn(*const c_void, *mut [u8; 4]) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderGetBoxType") }.map_err(JxlError::SymbolLoadFailed)?;
-
+    /// Gets the type of the current box; with `decompressed`, the inner type of a `brob` box.
+    /// - JxlDecoderGetBoxType()
+    pub fn get_box_type(&self, decompressed: bool) -> Result<[u8; 4], JxlError> {
         let mut box_type = [0u8; 4];
-        let result = unsafe { get_box_type(self.dec, &mut box_type) };
+        let result = unsafe {
+            (self.lib.fns.decoder_get_box_type)(self.dec, &mut box_type, decompressed.into())
+        };
         if result == 0 {
             Ok(box_type)
         } else {
@@ -918,12 +691,8 @@ impl JxlDecoder {
 
     /// Gets the raw size of the current box. - JxlDecoderGetBoxSizeRaw()
     pub fn get_box_size_raw(&self) -> Result<u64, JxlError> {
-        let get_box_size_raw: Symbol<unsafe extern "C" fn(*const c_void, *mut u64) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderGetBoxSizeRaw") }
-                .map_err(JxlError::SymbolLoadFailed)?;
-
         let mut size = 0;
-        let result = unsafe { get_box_size_raw(self.dec, &mut size) };
+        let result = unsafe { (self.lib.fns.decoder_get_box_size_raw)(self.dec, &mut size) };
         if result == 0 {
             Ok(size)
         } else {
@@ -933,9 +702,7 @@ impl JxlDecoder {
 
     /// Gets the size of the contents of the current box. - JxlDecoderGetBoxSizeContents()
     pub fn get_box_size_contents(&self) -> Result<u64, JxlError> {
-        let get_box_size_contents: Symbol<unsafe extern "C" fn(*const c_void, *mut u64) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderGetBoxSizeContents") }
-                .map_err(JxlError::SymbolLoadFailed)?;
+        let get_box_size_contents = self.lib.fns.decoder_get_box_size_contents()?;
 
         let mut size = 0;
         let result = unsafe { get_box_size_contents(self.dec, &mut size) };
@@ -948,12 +715,7 @@ impl JxlDecoder {
 
     /// Sets the level of progressive detail to decode. - JxlDecoderSetProgressiveDetail()
     pub fn set_progressive_detail(&self, detail: JxlProgressiveDetail) -> Result<(), JxlError> {
-        let set_progressive_detail: Symbol<
-            unsafe extern "C" fn(*mut c_void, JxlProgressiveDetail) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetProgressiveDetail") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_progressive_detail(self.dec, detail) };
+        let result = unsafe { (self.lib.fns.decoder_set_progressive_detail)(self.dec, detail) };
         if result == 0 {
             Ok(())
         } else {
@@ -963,21 +725,12 @@ impl JxlDecoder {
 
     /// Gets the intended downsampling ratio for the current progressive step. - JxlDecoderGetIntendedDownsamplingRatio()
     pub fn get_intended_downsampling_ratio(&self) -> usize {
-        let get_intended_downsampling_ratio: Symbol<unsafe extern "C" fn(*mut c_void) -> usize> = unsafe {
-            self.lib
-                .get(b"JxlDecoderGetIntendedDownsamplingRatio")
-                .unwrap()
-        };
-
-        unsafe { get_intended_downsampling_ratio(self.dec) }
+        unsafe { (self.lib.fns.decoder_get_intended_downsampling_ratio)(self.dec) }
     }
 
     /// Flushes the decoder, returning any partial image data. - JxlDecoderFlushImage()
     pub fn flush_image(&self) -> Result<(), JxlError> {
-        let flush_image: Symbol<unsafe extern "C" fn(*mut c_void) -> c_int> =
-            unsafe { self.lib.get(b"JxlDecoderFlushImage") }.map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { flush_image(self.dec) };
+        let result = unsafe { (self.lib.fns.decoder_flush_image)(self.dec) };
         if result == 0 {
             Ok(())
         } else {
@@ -990,12 +743,7 @@ impl JxlDecoder {
     /// decorder.set_image_out_buffer 또는 decorder.set_image_out_callback 이후에 호출할 수 있습니다.
     /// float 픽셀 데이터 유형의 경우 기본 JXL_BIT_DEPTH_FROM_PIXEL_FORMAT 설정만 지원됩니다.
     pub fn set_image_out_bit_depth(&self, bit_depth: &JxlBitDepth) -> Result<(), JxlError> {
-        let set_image_out_bit_depth: Symbol<
-            unsafe extern "C" fn(*mut c_void, *const JxlBitDepth) -> c_int,
-        > = unsafe { self.lib.get(b"JxlDecoderSetImageOutBitDepth") }
-            .map_err(JxlError::SymbolLoadFailed)?;
-
-        let result = unsafe { set_image_out_bit_depth(self.dec, bit_depth) };
+        let result = unsafe { (self.lib.fns.decoder_set_image_out_bit_depth)(self.dec, bit_depth) };
         if result == 0 {
             Ok(())
         } else {
@@ -1008,8 +756,6 @@ impl JxlDecoder {
 impl Drop for JxlDecoder {
     /// Destroys the decoder. - JxlDecoderDestroy()
     fn drop(&mut self) {
-        let destroy: Symbol<unsafe extern "C" fn(*mut c_void)> =
-            unsafe { self.lib.get(b"JxlDecoderDestroy").unwrap() };
-        unsafe { destroy(self.dec) };
+        unsafe { (self.lib.fns.decoder_destroy)(self.dec) };
     }
 }
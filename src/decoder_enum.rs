@@ -76,6 +76,14 @@ impl std::ops::BitOr for JxlDecoderStatus {
         self.bits() | rhs.bits()
     }
 }
+
+impl std::ops::BitOr<JxlDecoderStatus> for i32 {
+    type Output = i32;
+
+    fn bitor(self, rhs: JxlDecoderStatus) -> Self::Output {
+        self | rhs.bits()
+    }
+}
 /// Represents progressive detail levels.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -90,6 +98,7 @@ pub enum JxlProgressiveDetail {
 }
 
 /// Represents the color profile target.
+#[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum JxlColorProfileTarget {
     /// JXL_COLOR_PROFILE_TARGET_ORIGINAL
@@ -1,7 +1,51 @@
 use std::ffi::{c_int, c_void};
 
-/// Type for parallel runner function.
-pub type JxlParallelRunner = unsafe extern "C" fn(*mut c_void, *mut c_void) -> c_int;
+/// Called once per run with the thread count, before any work item. - JxlParallelRunInit
+pub type JxlParallelRunInit =
+    unsafe extern "C" fn(jpegxl_opaque: *mut c_void, num_threads: usize) -> c_int;
+
+/// Runs one work item on the given thread. - JxlParallelRunFunction
+pub type JxlParallelRunFunction =
+    unsafe extern "C" fn(jpegxl_opaque: *mut c_void, value: u32, thread_id: usize);
+
+/// Runs `func` for every value in `start_range..end_range`, after calling `init` once. - JxlParallelRunner
+pub type JxlParallelRunner = unsafe extern "C" fn(
+    runner_opaque: *mut c_void,
+    jpegxl_opaque: *mut c_void,
+    init: JxlParallelRunInit,
+    func: JxlParallelRunFunction,
+    start_range: u32,
+    end_range: u32,
+) -> c_int;
+
+/// Image-out callback. - JxlImageOutCallback
+pub type JxlImageOutCallback = unsafe extern "C" fn(
+    opaque: *mut c_void,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+);
+
+/// Initializes per-thread state for the multithreaded image-out callback. - JxlImageOutInitCallback
+pub type JxlImageOutInitCallback = unsafe extern "C" fn(
+    init_opaque: *mut c_void,
+    num_threads: usize,
+    num_pixels_per_thread: usize,
+) -> *mut c_void;
+
+/// Multithreaded image-out callback. - JxlImageOutRunCallback
+pub type JxlImageOutRunCallback = unsafe extern "C" fn(
+    run_opaque: *mut c_void,
+    thread_id: usize,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+);
+
+/// Destroys the state returned by the init callback. - JxlImageOutDestroyCallback
+pub type JxlImageOutDestroyCallback = unsafe extern "C" fn(run_opaque: *mut c_void);
 
 /// Represents the size of a box in the JPEG XL container.
 #[repr(C)]
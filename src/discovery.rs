@@ -0,0 +1,206 @@
+use crate::JxlError;
+use crate::JxlLibrary;
+use crate::JxlThreadsLibrary;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Environment variable consulted first by `JxlLibrary::discover`.
+///
+/// Holds one or more entries separated like `PATH`. Each entry is either the library file
+/// itself or a directory to search for the platform library names.
+pub const JXL_DYNLINK_PATH: &str = "JXL_DYNLINK_PATH";
+
+/// File names of a libjxl component (`"jxl"`, `"jxl_threads"`, ...) on the current platform,
+/// most specific first.
+pub(crate) fn platform_library_names(stem: &str) -> Vec<String> {
+    // 지원하는 libjxl 버전 (최신 순)
+    const VERSIONS: [&str; 3] = ["0.11", "0.10", "0.9"];
+
+    let mut names = Vec::new();
+    if cfg!(windows) {
+        names.push(format!("{stem}.dll"));
+        names.push(format!("lib{stem}.dll"));
+    } else if cfg!(target_os = "macos") {
+        for version in VERSIONS {
+            names.push(format!("lib{stem}.{version}.dylib"));
+        }
+        names.push(format!("lib{stem}.dylib"));
+    } else {
+        for version in VERSIONS {
+            names.push(format!("lib{stem}.so.{version}"));
+        }
+        names.push(format!("lib{stem}.so"));
+    }
+    names
+}
+
+/// Directories where package managers install libjxl.
+fn standard_library_dirs() -> Vec<PathBuf> {
+    let dirs: &[&str] = if cfg!(windows) {
+        &[]
+    } else if cfg!(target_os = "macos") {
+        &["/opt/homebrew/lib", "/usr/local/lib", "/opt/local/lib"]
+    } else {
+        &[
+            "/usr/local/lib",
+            "/usr/local/lib64",
+            "/usr/lib",
+            "/usr/lib64",
+            "/usr/lib/x86_64-linux-gnu",
+            "/usr/lib/aarch64-linux-gnu",
+        ]
+    };
+    dirs.iter().map(PathBuf::from).collect()
+}
+
+/// Builds the ordered candidate list for a libjxl component.
+///
+/// Order: `JXL_DYNLINK_PATH` entries, the executable's directory (and its `dlls`
+/// subdirectory), `./dlls`, bare names resolved by the system loader, then the standard
+/// install directories. A file entry in `JXL_DYNLINK_PATH` names libjxl itself, so other
+/// components are looked up next to it.
+pub(crate) fn library_candidates(stem: &str) -> Vec<PathBuf> {
+    let names = platform_library_names(stem);
+    let mut dirs = Vec::new();
+    let mut candidates = Vec::new();
+
+    if let Some(value) = env::var_os(JXL_DYNLINK_PATH) {
+        for entry in env::split_paths(&value) {
+            if entry.as_os_str().is_empty() {
+                continue;
+            }
+            if entry.is_file() {
+                if stem == "jxl" {
+                    candidates.push(entry);
+                } else if let Some(parent) = entry.parent() {
+                    dirs.push(parent.to_path_buf());
+                }
+            } else {
+                dirs.push(entry);
+            }
+        }
+    }
+
+    if let Some(exe_dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(exe_dir.join("dlls"));
+        dirs.push(exe_dir);
+    }
+    dirs.push(PathBuf::from("dlls"));
+
+    for dir in &dirs {
+        candidates.extend(names.iter().map(|name| dir.join(name)));
+    }
+    // 경로 없이 이름만 넘기면 LD_LIBRARY_PATH / ldconfig 캐시 / PATH 를 시스템 로더가 검색
+    candidates.extend(names.iter().map(PathBuf::from));
+    for dir in standard_library_dirs() {
+        candidates.extend(names.iter().map(|name| dir.join(name)));
+    }
+
+    let mut unique = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if !unique.contains(&candidate) {
+            unique.push(candidate);
+        }
+    }
+    unique
+}
+
+/// Tries `load` on each candidate in order and returns the first success.
+pub(crate) fn load_first<T>(
+    candidates: Vec<PathBuf>,
+    mut load: impl FnMut(&Path) -> Result<T, JxlError>,
+) -> Result<T, JxlError> {
+    let mut tried = Vec::with_capacity(candidates.len());
+
+    for candidate in candidates {
+        match load(&candidate) {
+            Ok(loaded) => return Ok(loaded),
+            Err(e) => tried.push((candidate, e)),
+        }
+    }
+
+    Err(JxlError::LibraryNotFound(tried))
+}
+
+impl JxlLibrary {
+    /// Searches the usual locations for libjxl and loads the first usable one.
+    ///
+    /// See `JxlLibrary::candidate_paths` for the search order. On failure every attempted
+    /// path is returned in `JxlError::LibraryNotFound` together with the reason it failed.
+    pub fn discover() -> Result<Arc<Self>, JxlError> {
+        load_first(library_candidates("jxl"), |path| JxlLibrary::load(path))
+    }
+
+    /// Lists the paths `discover` would try, in order.
+    pub fn candidate_paths() -> Vec<PathBuf> {
+        library_candidates("jxl")
+    }
+}
+
+impl JxlThreadsLibrary {
+    /// Searches for jxl_threads the same way `JxlLibrary::discover` searches for libjxl.
+    pub fn discover() -> Result<Arc<Self>, JxlError> {
+        load_first(library_candidates("jxl_threads"), |path| {
+            JxlThreadsLibrary::load(path)
+        })
+    }
+
+    /// Looks for jxl_threads next to an already loaded libjxl first, then discovers it.
+    pub fn discover_near(lib: &JxlLibrary) -> Result<Arc<Self>, JxlError> {
+        let mut candidates = Vec::new();
+        if let Some(dir) = lib
+            .path()
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            candidates.extend(
+                platform_library_names("jxl_threads")
+                    .iter()
+                    .map(|name| dir.join(name)),
+            );
+        }
+        for candidate in library_candidates("jxl_threads") {
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        load_first(candidates, |path| JxlThreadsLibrary::load(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_cover_platform_names() {
+        let candidates = library_candidates("jxl");
+        for name in platform_library_names("jxl") {
+            assert!(candidates.contains(&PathBuf::from(&name)), "{name} missing");
+        }
+        let mut sorted = candidates.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), candidates.len());
+    }
+
+    #[test]
+    fn test_not_found_lists_every_attempt() {
+        let candidates = vec![
+            PathBuf::from("/nonexistent/dir/libjxl.so"),
+            PathBuf::from("libjxl_dynlink_does_not_exist.so"),
+        ];
+        match load_first(candidates.clone(), |path| JxlLibrary::load(path)) {
+            Err(JxlError::LibraryNotFound(tried)) => {
+                let paths: Vec<_> = tried.into_iter().map(|(path, _)| path).collect();
+                assert_eq!(paths, candidates);
+            }
+            other => panic!("expected LibraryNotFound, got {other:?}"),
+        }
+    }
+}
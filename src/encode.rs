@@ -0,0 +1,813 @@
+use crate::ColorTransform;
+use crate::FrameSettings;
+use crate::GroupOrder;
+use crate::JxlAnimationHeader;
+use crate::JxlBasicInfo;
+use crate::JxlBool;
+use crate::JxlColorEncoding;
+use crate::JxlDataType;
+use crate::JxlEncoder;
+use crate::JxlEncoderStatus;
+use crate::JxlEndianness;
+use crate::JxlError;
+use crate::JxlLibrary;
+use crate::JxlPixelFormat;
+use crate::JxlStreamEncoder;
+use crate::ModularGroupSize;
+use crate::ModularPredictor;
+use crate::Resampling;
+use crate::Toggle;
+
+use image::{DynamicImage, ExtendedColorType};
+use std::borrow::Cow;
+use std::io::Write;
+use std::sync::Arc;
+
+const INITIAL_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Options for `encode_dynamic_image` and `JxlImageEncoder`, covering every
+/// `JxlEncoderFrameSettingId`.
+///
+/// Build them with `EncodeOptions::builder()`, which checks the ranges, or set the fields
+/// directly and let `validate` catch mistakes. Every field defaults to what libjxl would pick
+/// on its own, except `distance`, which defaults to lossless. Only fields that differ from
+/// the default are passed to libjxl, so presets work on older libjxl versions as long as they
+/// leave newer options alone.
+///
+/// With the `serde` feature the options can be stored as presets, e.g. in a config file;
+/// missing fields take their default.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EncodeOptions {
+    /// Butteraugli distance, 0 to 25; `0.0` (the default) encodes losslessly, `1.0` is
+    /// visually lossless.
+    pub distance: f32,
+    /// Encoder effort, 1 (fastest) to 10 (slowest). Defaults to 7.
+    pub effort: u32,
+    /// ICC profile of the pixels. Without one, integer pixels are tagged as sRGB and float
+    /// pixels as linear sRGB.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub icc_profile: Option<Vec<u8>>,
+    /// Decoding speed tier, 0 (best quality, the default) to 4 (fastest decoding).
+    pub decoding_speed: u32,
+    /// Downsampling of the color channels.
+    pub resampling: Resampling,
+    /// Downsampling of the extra channels.
+    pub extra_channel_resampling: Resampling,
+    /// The input is already downsampled by `resampling`. Defaults to `false`.
+    pub already_downsampled: bool,
+    /// Adds noise matching this ISO film speed; `0.0` (the default) adds none.
+    pub photon_noise_iso: f32,
+    /// Adaptive noise generation.
+    pub noise: Toggle,
+    /// Dot detection.
+    pub dots: Toggle,
+    /// Patch detection.
+    pub patches: Toggle,
+    /// Edge preserving filter level, 0 to 3.
+    pub epf: Option<u32>,
+    /// Gaborish filter.
+    pub gaborish: Toggle,
+    /// Modular mode (`On`) or VarDCT (`Off`).
+    pub modular: Toggle,
+    /// Keep the color of invisible pixels.
+    pub keep_invisible: Toggle,
+    /// Order in which groups are stored.
+    pub group_order: GroupOrder,
+    /// Center x of `GroupOrder::CenterFirst`; `None` is the image center.
+    pub group_order_center_x: Option<u32>,
+    /// Center y of `GroupOrder::CenterFirst`; `None` is the image center.
+    pub group_order_center_y: Option<u32>,
+    /// Progressive (squeeze) modular encoding.
+    pub responsive: Toggle,
+    /// Spectral progression of VarDCT AC.
+    pub progressive_ac: Toggle,
+    /// Quantization progression of VarDCT AC.
+    pub qprogressive_ac: Toggle,
+    /// Progressive DC levels, 0 to 2.
+    pub progressive_dc: Option<u32>,
+    /// Percentage of colors for a global channel palette, 0 to 100.
+    pub channel_colors_global_percent: Option<f32>,
+    /// Percentage of colors for a per-group channel palette, 0 to 100.
+    pub channel_colors_group_percent: Option<f32>,
+    /// Maximum colors of a palette; `Some(0)` disables palettes.
+    pub palette_colors: Option<u32>,
+    /// Delta palette (lossy).
+    pub lossy_palette: Toggle,
+    /// Color transform.
+    pub color_transform: ColorTransform,
+    /// Reversible color transform of modular mode, 0 to 41.
+    pub modular_color_space: Option<u32>,
+    /// Modular group size.
+    pub modular_group_size: ModularGroupSize,
+    /// Modular predictor.
+    pub modular_predictor: ModularPredictor,
+    /// Percentage of pixels used to learn MA trees, 0 to 100.
+    pub modular_ma_tree_learning_percent: Option<f32>,
+    /// Previous channels used as MA tree properties, 0 to 11.
+    pub modular_nb_prev_channels: Option<u32>,
+    /// Chroma from luma when recompressing JPEG.
+    pub jpeg_recon_cfl: Toggle,
+    /// List the frame in the frame index box. Defaults to `false`.
+    pub index_box: bool,
+    /// Brotli effort for compressed boxes, 0 to 11.
+    pub brotli_effort: Option<u32>,
+    /// Brotli-compress metadata boxes copied from JPEG.
+    pub jpeg_compress_boxes: Toggle,
+    /// Streaming buffering strategy, 0 to 3.
+    pub buffering: Option<u32>,
+    /// Keep Exif when recompressing JPEG.
+    pub jpeg_keep_exif: Toggle,
+    /// Keep XMP when recompressing JPEG.
+    pub jpeg_keep_xmp: Toggle,
+    /// Keep JUMBF when recompressing JPEG.
+    pub jpeg_keep_jumbf: Toggle,
+    /// Run heuristics on the full image even when streaming. Defaults to `false`.
+    pub use_full_image_heuristics: bool,
+    /// Disable perceptual optimizations, e.g. for non-photographic data. Defaults to `false`.
+    pub disable_perceptual_heuristics: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            distance: 0.0,
+            effort: 7,
+            icc_profile: None,
+            decoding_speed: 0,
+            resampling: Resampling::Default,
+            extra_channel_resampling: Resampling::Default,
+            already_downsampled: false,
+            photon_noise_iso: 0.0,
+            noise: Toggle::Default,
+            dots: Toggle::Default,
+            patches: Toggle::Default,
+            epf: None,
+            gaborish: Toggle::Default,
+            modular: Toggle::Default,
+            keep_invisible: Toggle::Default,
+            group_order: GroupOrder::Default,
+            group_order_center_x: None,
+            group_order_center_y: None,
+            responsive: Toggle::Default,
+            progressive_ac: Toggle::Default,
+            qprogressive_ac: Toggle::Default,
+            progressive_dc: None,
+            channel_colors_global_percent: None,
+            channel_colors_group_percent: None,
+            palette_colors: None,
+            lossy_palette: Toggle::Default,
+            color_transform: ColorTransform::Default,
+            modular_color_space: None,
+            modular_group_size: ModularGroupSize::Default,
+            modular_predictor: ModularPredictor::Default,
+            modular_ma_tree_learning_percent: None,
+            modular_nb_prev_channels: None,
+            jpeg_recon_cfl: Toggle::Default,
+            index_box: false,
+            brotli_effort: None,
+            jpeg_compress_boxes: Toggle::Default,
+            buffering: None,
+            jpeg_keep_exif: Toggle::Default,
+            jpeg_keep_xmp: Toggle::Default,
+            jpeg_keep_jumbf: Toggle::Default,
+            use_full_image_heuristics: false,
+            disable_perceptual_heuristics: false,
+        }
+    }
+}
+
+impl EncodeOptions {
+    /// Lossless options with the default effort.
+    pub fn lossless() -> Self {
+        Self::default()
+    }
+
+    /// Lossy options at the given butteraugli distance.
+    pub fn lossy(distance: f32) -> Self {
+        EncodeOptions {
+            distance,
+            ..Self::default()
+        }
+    }
+
+    /// Starts a builder from the default options.
+    pub fn builder() -> EncodeOptionsBuilder {
+        EncodeOptionsBuilder {
+            options: Self::default(),
+        }
+    }
+
+    /// Returns `true` if these options encode losslessly.
+    pub fn is_lossless(&self) -> bool {
+        self.distance == 0.0
+    }
+
+    /// Checks every value against the range libjxl accepts.
+    pub fn validate(&self) -> Result<(), JxlError> {
+        check_float("distance", Some(self.distance), 0.0, 25.0)?;
+        check("effort", Some(self.effort), 1, 10)?;
+        check("decoding_speed", Some(self.decoding_speed), 0, 4)?;
+        if self.photon_noise_iso.is_nan() || self.photon_noise_iso < 0.0 {
+            return Err(JxlError::InvalidInput(format!(
+                "photon_noise_iso must not be negative, got {}",
+                self.photon_noise_iso
+            )));
+        }
+        check("epf", self.epf, 0, 3)?;
+        check("progressive_dc", self.progressive_dc, 0, 2)?;
+        check_float(
+            "channel_colors_global_percent",
+            self.channel_colors_global_percent,
+            0.0,
+            100.0,
+        )?;
+        check_float(
+            "channel_colors_group_percent",
+            self.channel_colors_group_percent,
+            0.0,
+            100.0,
+        )?;
+        check("modular_color_space", self.modular_color_space, 0, 41)?;
+        check_float(
+            "modular_ma_tree_learning_percent",
+            self.modular_ma_tree_learning_percent,
+            0.0,
+            100.0,
+        )?;
+        check(
+            "modular_nb_prev_channels",
+            self.modular_nb_prev_channels,
+            0,
+            11,
+        )?;
+        check("brotli_effort", self.brotli_effort, 0, 11)?;
+        check("buffering", self.buffering, 0, 3)?;
+        // libjxl 은 i64 로 받지만 음수는 기본값 의미이므로 i32 범위로 제한
+        let max = i32::MAX as u32;
+        check("group_order_center_x", self.group_order_center_x, 0, max)?;
+        check("group_order_center_y", self.group_order_center_y, 0, max)?;
+        check("palette_colors", self.palette_colors, 0, max)
+    }
+
+    /// Validates the options and sets every non-default one on `frame_settings`.
+    ///
+    /// `icc_profile` is not a frame setting and is left to the caller.
+    pub fn apply_to(&self, frame_settings: &FrameSettings) -> Result<(), JxlError> {
+        self.validate()?;
+        let default = Self::default();
+        let fs = frame_settings;
+
+        if self.effort != default.effort {
+            fs.set_effort(self.effort)?;
+        }
+        if self.is_lossless() {
+            fs.set_lossless(true)?;
+        } else {
+            fs.set_distance(self.distance)?;
+        }
+        if self.decoding_speed != default.decoding_speed {
+            fs.set_decoding_speed(self.decoding_speed)?;
+        }
+        if self.resampling != default.resampling {
+            fs.set_resampling(self.resampling)?;
+        }
+        if self.extra_channel_resampling != default.extra_channel_resampling {
+            fs.set_extra_channel_resampling(self.extra_channel_resampling)?;
+        }
+        if self.already_downsampled != default.already_downsampled {
+            fs.set_already_downsampled(self.already_downsampled)?;
+        }
+        if self.photon_noise_iso != default.photon_noise_iso {
+            fs.set_photon_noise_iso(self.photon_noise_iso)?;
+        }
+        if self.noise != default.noise {
+            fs.set_noise(self.noise)?;
+        }
+        if self.dots != default.dots {
+            fs.set_dots(self.dots)?;
+        }
+        if self.patches != default.patches {
+            fs.set_patches(self.patches)?;
+        }
+        if self.epf != default.epf {
+            fs.set_epf(self.epf)?;
+        }
+        if self.gaborish != default.gaborish {
+            fs.set_gaborish(self.gaborish)?;
+        }
+        if self.modular != default.modular {
+            fs.set_modular(self.modular)?;
+        }
+        if self.keep_invisible != default.keep_invisible {
+            fs.set_keep_invisible(self.keep_invisible)?;
+        }
+        if self.group_order != default.group_order {
+            fs.set_group_order(self.group_order)?;
+        }
+        if self.group_order_center_x != default.group_order_center_x {
+            fs.set_group_order_center_x(self.group_order_center_x)?;
+        }
+        if self.group_order_center_y != default.group_order_center_y {
+            fs.set_group_order_center_y(self.group_order_center_y)?;
+        }
+        if self.responsive != default.responsive {
+            fs.set_responsive(self.responsive)?;
+        }
+        if self.progressive_ac != default.progressive_ac {
+            fs.set_progressive_ac(self.progressive_ac)?;
+        }
+        if self.qprogressive_ac != default.qprogressive_ac {
+            fs.set_qprogressive_ac(self.qprogressive_ac)?;
+        }
+        if self.progressive_dc != default.progressive_dc {
+            fs.set_progressive_dc(self.progressive_dc)?;
+        }
+        if self.channel_colors_global_percent != default.channel_colors_global_percent {
+            fs.set_channel_colors_global_percent(self.channel_colors_global_percent)?;
+        }
+        if self.channel_colors_group_percent != default.channel_colors_group_percent {
+            fs.set_channel_colors_group_percent(self.channel_colors_group_percent)?;
+        }
+        if self.palette_colors != default.palette_colors {
+            fs.set_palette_colors(self.palette_colors)?;
+        }
+        if self.lossy_palette != default.lossy_palette {
+            fs.set_lossy_palette(self.lossy_palette)?;
+        }
+        if self.color_transform != default.color_transform {
+            fs.set_color_transform(self.color_transform)?;
+        }
+        if self.modular_color_space != default.modular_color_space {
+            fs.set_modular_color_space(self.modular_color_space)?;
+        }
+        if self.modular_group_size != default.modular_group_size {
+            fs.set_modular_group_size(self.modular_group_size)?;
+        }
+        if self.modular_predictor != default.modular_predictor {
+            fs.set_modular_predictor(self.modular_predictor)?;
+        }
+        if self.modular_ma_tree_learning_percent != default.modular_ma_tree_learning_percent {
+            fs.set_modular_ma_tree_learning_percent(self.modular_ma_tree_learning_percent)?;
+        }
+        if self.modular_nb_prev_channels != default.modular_nb_prev_channels {
+            fs.set_modular_nb_prev_channels(self.modular_nb_prev_channels)?;
+        }
+        if self.jpeg_recon_cfl != default.jpeg_recon_cfl {
+            fs.set_jpeg_recon_cfl(self.jpeg_recon_cfl)?;
+        }
+        if self.index_box != default.index_box {
+            fs.set_index_box(self.index_box)?;
+        }
+        if self.brotli_effort != default.brotli_effort {
+            fs.set_brotli_effort(self.brotli_effort)?;
+        }
+        if self.jpeg_compress_boxes != default.jpeg_compress_boxes {
+            fs.set_jpeg_compress_boxes(self.jpeg_compress_boxes)?;
+        }
+        if self.buffering != default.buffering {
+            fs.set_buffering(self.buffering)?;
+        }
+        if self.jpeg_keep_exif != default.jpeg_keep_exif {
+            fs.set_jpeg_keep_exif(self.jpeg_keep_exif)?;
+        }
+        if self.jpeg_keep_xmp != default.jpeg_keep_xmp {
+            fs.set_jpeg_keep_xmp(self.jpeg_keep_xmp)?;
+        }
+        if self.jpeg_keep_jumbf != default.jpeg_keep_jumbf {
+            fs.set_jpeg_keep_jumbf(self.jpeg_keep_jumbf)?;
+        }
+        if self.use_full_image_heuristics != default.use_full_image_heuristics {
+            fs.set_use_full_image_heuristics(self.use_full_image_heuristics)?;
+        }
+        if self.disable_perceptual_heuristics != default.disable_perceptual_heuristics {
+            fs.set_disable_perceptual_heuristics(self.disable_perceptual_heuristics)?;
+        }
+        Ok(())
+    }
+}
+
+fn check(name: &str, value: Option<u32>, min: u32, max: u32) -> Result<(), JxlError> {
+    match value {
+        Some(v) if !(min..=max).contains(&v) => Err(JxlError::InvalidInput(format!(
+            "{name} must be {min} to {max}, got {v}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn check_float(name: &str, value: Option<f32>, min: f32, max: f32) -> Result<(), JxlError> {
+    match value {
+        // NaN 도 여기서 걸러짐
+        Some(v) if !(min..=max).contains(&v) => Err(JxlError::InvalidInput(format!(
+            "{name} must be {min} to {max}, got {v}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Builder for `EncodeOptions`; `build` checks every range.
+///
+/// ```no_run
+/// # use jxl_dynlink::*;
+/// let options = EncodeOptions::builder()
+///     .distance(1.0)
+///     .effort(9)
+///     .progressive_ac(Toggle::On)
+///     .epf(Some(2))
+///     .build()?;
+/// # Ok::<(), JxlError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptionsBuilder {
+    options: EncodeOptions,
+}
+
+macro_rules! builder_setters {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        impl EncodeOptionsBuilder {
+            $(
+                #[doc = concat!("Sets `EncodeOptions::", stringify!($name), "`.")]
+                pub fn $name(mut self, $name: $ty) -> Self {
+                    self.options.$name = $name;
+                    self
+                }
+            )*
+        }
+    };
+}
+
+builder_setters! {
+    distance: f32,
+    effort: u32,
+    icc_profile: Option<Vec<u8>>,
+    decoding_speed: u32,
+    resampling: Resampling,
+    extra_channel_resampling: Resampling,
+    already_downsampled: bool,
+    photon_noise_iso: f32,
+    noise: Toggle,
+    dots: Toggle,
+    patches: Toggle,
+    epf: Option<u32>,
+    gaborish: Toggle,
+    modular: Toggle,
+    keep_invisible: Toggle,
+    group_order: GroupOrder,
+    group_order_center_x: Option<u32>,
+    group_order_center_y: Option<u32>,
+    responsive: Toggle,
+    progressive_ac: Toggle,
+    qprogressive_ac: Toggle,
+    progressive_dc: Option<u32>,
+    channel_colors_global_percent: Option<f32>,
+    channel_colors_group_percent: Option<f32>,
+    palette_colors: Option<u32>,
+    lossy_palette: Toggle,
+    color_transform: ColorTransform,
+    modular_color_space: Option<u32>,
+    modular_group_size: ModularGroupSize,
+    modular_predictor: ModularPredictor,
+    modular_ma_tree_learning_percent: Option<f32>,
+    modular_nb_prev_channels: Option<u32>,
+    jpeg_recon_cfl: Toggle,
+    index_box: bool,
+    brotli_effort: Option<u32>,
+    jpeg_compress_boxes: Toggle,
+    buffering: Option<u32>,
+    jpeg_keep_exif: Toggle,
+    jpeg_keep_xmp: Toggle,
+    jpeg_keep_jumbf: Toggle,
+    use_full_image_heuristics: bool,
+    disable_perceptual_heuristics: bool,
+}
+
+impl EncodeOptionsBuilder {
+    /// Returns the options, or `JxlError::InvalidInput` naming the first value out of range.
+    pub fn build(self) -> Result<EncodeOptions, JxlError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+/// Encodes a `DynamicImage` of any color type into a JPEG XL codestream.
+///
+/// 8/16-bit images are stored as integers and `Rgb32F`/`Rgba32F` as 32-bit float, so images
+/// loaded from TIFF, WebP, BMP, EXR, ... all go through the same path.
+pub fn encode_dynamic_image(
+    lib: &Arc<JxlLibrary>,
+    image: &DynamicImage,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, JxlError> {
+    let (pixels, color_type) = dynamic_image_pixels(image);
+    encode_pixels(
+        lib,
+        &pixels,
+        image.width(),
+        image.height(),
+        color_type,
+        options,
+    )
+}
+
+/// Encodes a `DynamicImage` like `encode_dynamic_image`, streaming the codestream into
+/// `writer` instead of collecting it in memory. Returns the writer.
+///
+/// Uses `JxlStreamEncoder`, so it needs libjxl 0.10 or newer.
+pub fn encode_dynamic_image_to<W: Write>(
+    lib: &Arc<JxlLibrary>,
+    image: &DynamicImage,
+    options: &EncodeOptions,
+    writer: W,
+) -> Result<W, JxlError> {
+    let (pixels, color_type) = dynamic_image_pixels(image);
+    let encoder = JxlEncoder::with_library(lib.clone(), None)?;
+    let mut stream = JxlStreamEncoder::new_unseekable(encoder, writer)?;
+    add_pixels(
+        &mut stream.encoder,
+        &pixels,
+        image.width(),
+        image.height(),
+        color_type,
+        options,
+    )?;
+    stream.finish()
+}
+
+/// Returns the native-endian samples of `image` and their color type.
+fn dynamic_image_pixels(image: &DynamicImage) -> (Cow<'_, [u8]>, ExtendedColorType) {
+    let color_type = ExtendedColorType::from(image.color());
+    if PixelLayout::from_color_type(color_type).is_ok() {
+        return (Cow::Borrowed(image.as_bytes()), color_type);
+    }
+
+    // DynamicImage 는 non_exhaustive 이므로 새 타입은 손실 없는 형식으로 변환
+    if image.color().has_alpha() {
+        let pixels = DynamicImage::from(image.to_rgba32f());
+        (
+            Cow::Owned(pixels.as_bytes().to_vec()),
+            ExtendedColorType::Rgba32F,
+        )
+    } else {
+        let pixels = DynamicImage::from(image.to_rgb32f());
+        (
+            Cow::Owned(pixels.as_bytes().to_vec()),
+            ExtendedColorType::Rgb32F,
+        )
+    }
+}
+
+/// How an `image` color type maps onto libjxl.
+#[derive(Debug, Clone)]
+pub(crate) struct PixelLayout {
+    pub(crate) format: JxlPixelFormat,
+    pub(crate) num_color_channels: u32,
+    pub(crate) has_alpha: bool,
+    pub(crate) bits_per_sample: u32,
+    pub(crate) exponent_bits_per_sample: u32,
+}
+
+impl PixelLayout {
+    pub(crate) fn from_color_type(color_type: ExtendedColorType) -> Result<Self, JxlError> {
+        let (num_channels, data_type) = match color_type {
+            ExtendedColorType::L8 => (1, JxlDataType::Uint8),
+            ExtendedColorType::La8 => (2, JxlDataType::Uint8),
+            ExtendedColorType::Rgb8 => (3, JxlDataType::Uint8),
+            ExtendedColorType::Rgba8 => (4, JxlDataType::Uint8),
+            ExtendedColorType::L16 => (1, JxlDataType::Uint16),
+            ExtendedColorType::La16 => (2, JxlDataType::Uint16),
+            ExtendedColorType::Rgb16 => (3, JxlDataType::Uint16),
+            ExtendedColorType::Rgba16 => (4, JxlDataType::Uint16),
+            ExtendedColorType::Rgb32F => (3, JxlDataType::Float),
+            ExtendedColorType::Rgba32F => (4, JxlDataType::Float),
+            other => {
+                return Err(JxlError::UnsupportedOperation(format!(
+                    "Unsupported color type: {other:?}"
+                )))
+            }
+        };
+        let (bits_per_sample, exponent_bits_per_sample) = match data_type {
+            JxlDataType::Uint8 => (8, 0),
+            JxlDataType::Uint16 => (16, 0),
+            JxlDataType::Float16 => (16, 5),
+            JxlDataType::Float => (32, 8),
+        };
+
+        Ok(PixelLayout {
+            format: JxlPixelFormat {
+                num_channels,
+                data_type,
+                endianness: JxlEndianness::NativeEndian,
+                align: 0,
+            },
+            num_color_channels: if num_channels <= 2 { 1 } else { 3 },
+            has_alpha: num_channels == 2 || num_channels == 4,
+            bits_per_sample,
+            exponent_bits_per_sample,
+        })
+    }
+
+    /// Checks that `pixels` holds exactly `width` x `height` pixels.
+    pub(crate) fn check_len(&self, pixels: &[u8], width: u32, height: u32) -> Result<(), JxlError> {
+        let expected = width as usize
+            * height as usize
+            * self.format.num_channels as usize
+            * (self.bits_per_sample as usize / 8);
+        if pixels.len() != expected {
+            return Err(JxlError::InvalidInput(format!(
+                "Expected {expected} bytes of pixel data, got {}",
+                pixels.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fills the image-level fields of `info` for a `width` x `height` image.
+    pub(crate) fn apply_to_basic_info(&self, info: &mut JxlBasicInfo, width: u32, height: u32) {
+        info.xsize = width;
+        info.ysize = height;
+        info.num_color_channels = self.num_color_channels;
+        info.bits_per_sample = self.bits_per_sample;
+        info.exponent_bits_per_sample = self.exponent_bits_per_sample;
+        if self.has_alpha {
+            info.num_extra_channels = 1;
+            info.alpha_bits = self.bits_per_sample;
+            info.alpha_exponent_bits = self.exponent_bits_per_sample;
+        } else {
+            info.num_extra_channels = 0;
+            info.alpha_bits = 0;
+            info.alpha_exponent_bits = 0;
+        }
+    }
+}
+
+/// Encodes one frame of interleaved native-endian pixels.
+///
+/// `pixels` must be exactly `width * height` samples of `color_type`.
+pub(crate) fn encode_pixels(
+    lib: &Arc<JxlLibrary>,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color_type: ExtendedColorType,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, JxlError> {
+    let mut encoder = JxlEncoder::with_library(lib.clone(), None)?;
+    add_pixels(&mut encoder, pixels, width, height, color_type, options)?;
+    encoder.close_input()?;
+
+    collect_output(&mut encoder)
+}
+
+/// Sets up `encoder` for a single-frame image and adds the frame; the input is left open.
+pub(crate) fn add_pixels(
+    encoder: &mut JxlEncoder,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color_type: ExtendedColorType,
+    options: &EncodeOptions,
+) -> Result<(), JxlError> {
+    let layout = PixelLayout::from_color_type(color_type)?;
+    layout.check_len(pixels, width, height)?;
+    set_image_info(encoder, &layout, width, height, options, None)?;
+
+    let frame_settings = encoder.create_frame_settings()?;
+    options.apply_to(&frame_settings)?;
+
+    frame_settings.add_image_frame(&layout.format, pixels)
+}
+
+/// Sets the basic info and color profile for a `width` x `height` image of `layout`.
+pub(crate) fn set_image_info(
+    encoder: &mut JxlEncoder,
+    layout: &PixelLayout,
+    width: u32,
+    height: u32,
+    options: &EncodeOptions,
+    animation: Option<&JxlAnimationHeader>,
+) -> Result<(), JxlError> {
+    options.validate()?;
+    let lossless = options.is_lossless();
+
+    let mut basic_info = JxlBasicInfo::default();
+    encoder.init_basic_info(&mut basic_info)?;
+    layout.apply_to_basic_info(&mut basic_info, width, height);
+    // 무손실 인코딩은 원본 색 공간 유지가 필요
+    basic_info.uses_original_profile = JxlBool::from(lossless);
+    if let Some(animation) = animation {
+        basic_info.have_animation = JxlBool::True;
+        basic_info.animation = *animation;
+    }
+    encoder.set_basic_info(&basic_info)?;
+
+    match &options.icc_profile {
+        Some(icc_profile) => encoder.set_icc_profile(icc_profile),
+        None => {
+            let mut color_encoding = JxlColorEncoding::default();
+            let is_gray = layout.num_color_channels == 1;
+            if layout.format.data_type == JxlDataType::Float {
+                encoder.color_encoding_set_to_linear_srgb(&mut color_encoding, is_gray)?;
+            } else {
+                encoder.color_encoding_set_to_srgb(&mut color_encoding, is_gray)?;
+            }
+            encoder.set_color_encoding(&color_encoding)
+        }
+    }
+}
+
+/// Drains `JxlEncoderProcessOutput` into a `Vec`, growing the chunk size as needed.
+pub(crate) fn collect_output(encoder: &mut JxlEncoder) -> Result<Vec<u8>, JxlError> {
+    let mut compressed = Vec::new();
+    let mut buffer = vec![0u8; INITIAL_BUFFER_SIZE];
+
+    loop {
+        let mut next_out = buffer.as_mut_ptr();
+        let mut avail_out = buffer.len();
+
+        let status = encoder.process_output(&mut next_out, &mut avail_out)?;
+        let bytes_written = buffer.len() - avail_out;
+        compressed.extend_from_slice(&buffer[..bytes_written]);
+
+        match status {
+            JxlEncoderStatus::Success => break,
+            JxlEncoderStatus::NeedMoreOutput => buffer.resize(buffer.len() * 2, 0),
+            _ => {
+                return Err(JxlError::from_encoder_status(
+                    status,
+                    encoder.get_error().ok(),
+                ))
+            }
+        }
+    }
+
+    Ok(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_checks_ranges() {
+        let options = EncodeOptions::builder()
+            .distance(1.5)
+            .effort(9)
+            .epf(Some(3))
+            .modular_predictor(ModularPredictor::Weighted)
+            .build()
+            .unwrap();
+        assert_eq!(options.effort, 9);
+        assert!(!options.is_lossless());
+
+        assert!(EncodeOptions::builder().effort(0).build().is_err());
+        assert!(EncodeOptions::builder().distance(25.5).build().is_err());
+        assert!(EncodeOptions::builder().distance(f32::NAN).build().is_err());
+        assert!(EncodeOptions::builder().epf(Some(4)).build().is_err());
+        assert!(EncodeOptions::builder()
+            .channel_colors_group_percent(Some(101.0))
+            .build()
+            .is_err());
+        assert!(EncodeOptions::builder()
+            .photon_noise_iso(-1.0)
+            .build()
+            .is_err());
+
+        let err = EncodeOptions::builder().buffering(Some(4)).build();
+        assert!(matches!(err, Err(JxlError::InvalidInput(m)) if m.contains("buffering")));
+    }
+
+    #[test]
+    fn test_default_is_valid() {
+        assert!(EncodeOptions::default().validate().is_ok());
+        assert_eq!(
+            EncodeOptions::builder().build().unwrap(),
+            EncodeOptions::default()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_preset() {
+        let options = EncodeOptions::builder()
+            .distance(1.0)
+            .progressive_ac(Toggle::On)
+            .group_order(GroupOrder::CenterFirst)
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains(r#""group_order":"center_first""#));
+        assert_eq!(
+            serde_json::from_str::<EncodeOptions>(&json).unwrap(),
+            options
+        );
+
+        // 빠진 필드는 기본값
+        let preset: EncodeOptions =
+            serde_json::from_str(r#"{"effort": 3, "modular": "on"}"#).unwrap();
+        assert_eq!(preset.effort, 3);
+        assert_eq!(preset.modular, Toggle::On);
+        assert!(preset.is_lossless());
+    }
+}
@@ -65,7 +65,7 @@ pub enum JxlEncoderFrameSettingId {
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum JxlEncoderStatsKey {
     HeaderBits,
     TocBits,
@@ -95,3 +95,35 @@ pub enum JxlEncoderStatsKey {
     NumButteraugliIters,
     NumStats,
 }
+
+impl JxlEncoderStatsKey {
+    /// Every key except `NumStats`, in order.
+    pub const ALL: [JxlEncoderStatsKey; 26] = [
+        JxlEncoderStatsKey::HeaderBits,
+        JxlEncoderStatsKey::TocBits,
+        JxlEncoderStatsKey::DictionaryBits,
+        JxlEncoderStatsKey::SplinesBits,
+        JxlEncoderStatsKey::NoiseBits,
+        JxlEncoderStatsKey::QuantBits,
+        JxlEncoderStatsKey::ModularTreeBits,
+        JxlEncoderStatsKey::ModularGlobalBits,
+        JxlEncoderStatsKey::DcBits,
+        JxlEncoderStatsKey::ModularDcGroupBits,
+        JxlEncoderStatsKey::ControlFieldsBits,
+        JxlEncoderStatsKey::CoefOrderBits,
+        JxlEncoderStatsKey::AcHistogramBits,
+        JxlEncoderStatsKey::AcBits,
+        JxlEncoderStatsKey::ModularAcGroupBits,
+        JxlEncoderStatsKey::NumSmallBlocks,
+        JxlEncoderStatsKey::NumDct4x8Blocks,
+        JxlEncoderStatsKey::NumAfvBlocks,
+        JxlEncoderStatsKey::NumDct8Blocks,
+        JxlEncoderStatsKey::NumDct8x32Blocks,
+        JxlEncoderStatsKey::NumDct16Blocks,
+        JxlEncoderStatsKey::NumDct16x32Blocks,
+        JxlEncoderStatsKey::NumDct32Blocks,
+        JxlEncoderStatsKey::NumDct32x64Blocks,
+        JxlEncoderStatsKey::NumDct64Blocks,
+        JxlEncoderStatsKey::NumButteraugliIters,
+    ];
+}
@@ -0,0 +1,109 @@
+use crate::encoder_enum::*;
+use crate::FrameSettings;
+use crate::JxlError;
+use crate::JxlLibrary;
+
+use std::ffi::c_void;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Statistics gathered while encoding, e.g. bits per section and DCT block counts.
+/// - JxlEncoderStats
+///
+/// Attach it to frames with `FrameSettings::collect_stats`; the counts are filled in as the
+/// frames are encoded and can be read once `process_output` is done. Stats of several encodes
+/// can be summed with `merge`.
+pub struct EncoderStats {
+    handle: Rc<StatsHandle>,
+    get: unsafe extern "C" fn(*const c_void, JxlEncoderStatsKey) -> usize,
+}
+
+/// Owns the `JxlEncoderStats*`; shared with every encoder writing into it.
+pub(crate) struct StatsHandle {
+    lib: Arc<JxlLibrary>,
+    stats: *mut c_void,
+}
+
+impl EncoderStats {
+    /// Creates empty stats. - JxlEncoderStatsCreate()
+    /// Fails with `JxlError::SymbolNotFound` on a libjxl built without the stats API.
+    pub fn new(lib: &Arc<JxlLibrary>) -> Result<Self, JxlError> {
+        let get = lib.fns.encoder_stats_get()?;
+        let stats = unsafe { lib.fns.encoder_stats_create()?() };
+        if stats.is_null() {
+            return Err(JxlError::StatsCreationFailed);
+        }
+        Ok(EncoderStats {
+            handle: Rc::new(StatsHandle {
+                lib: lib.clone(),
+                stats,
+            }),
+            get,
+        })
+    }
+
+    /// Returns the value of one statistic. - JxlEncoderStatsGet()
+    pub fn get(&self, key: JxlEncoderStatsKey) -> usize {
+        unsafe { (self.get)(self.handle.stats, key) }
+    }
+
+    /// Returns every statistic with its key.
+    pub fn to_vec(&self) -> Vec<(JxlEncoderStatsKey, usize)> {
+        JxlEncoderStatsKey::ALL
+            .iter()
+            .map(|&key| (key, self.get(key)))
+            .collect()
+    }
+
+    /// Adds the values of `other` to these stats. - JxlEncoderStatsMerge()
+    pub fn merge(&mut self, other: &EncoderStats) -> Result<(), JxlError> {
+        let merge = self.handle.lib.fns.encoder_stats_merge()?;
+        unsafe { merge(self.handle.stats, other.handle.stats) };
+        Ok(())
+    }
+}
+
+impl FrameSettings<'_> {
+    /// Collects statistics of the frames encoded with these settings into `stats`.
+    /// - JxlEncoderCollectStats()
+    ///
+    /// The encoder keeps `stats` alive until it is reset or dropped.
+    pub fn collect_stats(&self, stats: &EncoderStats) -> Result<(), JxlError> {
+        let encoder = self.encoder();
+        let collect = encoder.lib.fns.encoder_collect_stats()?;
+        let status = unsafe { collect(self.as_ptr(), stats.handle.stats) };
+        match status {
+            JxlEncoderStatus::Success => {
+                encoder.stats.borrow_mut().push(stats.handle.clone());
+                Ok(())
+            }
+            _ => Err(JxlError::from_encoder_status(
+                status,
+                encoder.get_error().ok(),
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for EncoderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.to_vec()).finish()
+    }
+}
+
+impl fmt::Debug for StatsHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsHandle")
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl Drop for StatsHandle {
+    fn drop(&mut self) {
+        if let Ok(destroy) = self.lib.fns.encoder_stats_destroy() {
+            unsafe { destroy(self.stats) };
+        }
+    }
+}
@@ -7,12 +7,17 @@ pub enum JxlError {
     // 기존 에러들...
     LibraryLoadFailed(libloading::Error),
     SymbolLoadFailed(libloading::Error),
+    /// No candidate could be loaded; holds every path tried and why it failed.
+    LibraryNotFound(Vec<(std::path::PathBuf, JxlError)>),
+    /// The loaded libjxl is too old to export this symbol.
+    SymbolNotFound(&'static str),
     EncoderCreationFailed,
     NullPointer,
     InvalidInput(String),
     EncodingFailed(JxlEncoderStatus),
     NotEnoughOutput,
     SetParallelRunnerFailed,
+    ParallelRunnerCreationFailed,
     ColorProfileError,
     FrameError(String),
     ExtraChannelError(String),
@@ -22,14 +27,21 @@ pub enum JxlError {
     PixelFormatError,
     ProgressiveError,
     JpegReconstructionError,
+    /// A reconstructed JPEG did not hash to the expected value.
+    JpegVerificationFailed(String),
     BoxBufferError,
     ExtraChannelBufferError,
     PreviewBufferError,
     FrameIndexBoxError,
     EncoderFrameSettingsFailed,
+    StatsCreationFailed,
     DecoderCreationFailed,
     DecodingFailed,
     NotEnoughInput,
+    /// Reading input or writing output failed.
+    Io(std::io::Error),
+    /// Writing a PNG, JPEG or TIFF failed.
+    ExportFailed(String),
 
     // ICC 프로필 관련 에러들
     ICCProfileEncodeFailed(String),
@@ -52,6 +64,12 @@ pub enum JxlError {
     InvalidDecoderStatus(i32),
 }
 
+impl From<std::io::Error> for JxlError {
+    fn from(e: std::io::Error) -> Self {
+        JxlError::Io(e)
+    }
+}
+
 impl JxlError {
     pub fn from_encoder_status(
         status: JxlEncoderStatus,
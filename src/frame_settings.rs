@@ -0,0 +1,578 @@
+use crate::common::*;
+use crate::encoder_enum::*;
+use crate::encoder_struct::*;
+use crate::metadata::*;
+use crate::JxlEncoder;
+use crate::JxlError;
+
+use std::ffi::{c_void, CString};
+use std::fmt;
+use std::ptr;
+
+/// Settings for the frames added with them. - JxlEncoderFrameSettings
+///
+/// Borrows the encoder that created it, so it cannot outlive the encoder, be used with a
+/// different encoder, or survive `JxlEncoder::reset`.
+///
+/// ```compile_fail,E0502
+/// # use jxl_dynlink::*;
+/// # fn f(lib: std::sync::Arc<JxlLibrary>) -> Result<(), JxlError> {
+/// let mut encoder = JxlEncoder::with_library(lib, None)?;
+/// let settings = encoder.create_frame_settings()?;
+/// encoder.reset()?;
+/// settings.set_effort(3)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameSettings<'enc> {
+    encoder: &'enc JxlEncoder,
+    settings: *mut c_void,
+}
+
+/// A setting that is either forced on or off, or left to the encoder (`-1` in libjxl).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Toggle {
+    #[default]
+    Default = -1,
+    Off = 0,
+    On = 1,
+}
+
+impl From<bool> for Toggle {
+    fn from(b: bool) -> Self {
+        if b {
+            Toggle::On
+        } else {
+            Toggle::Off
+        }
+    }
+}
+
+/// Downsampling factor. - JXL_ENC_FRAME_SETTING_RESAMPLING
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Resampling {
+    #[default]
+    Default = -1,
+    None = 1,
+    X2 = 2,
+    X4 = 4,
+    X8 = 8,
+}
+
+/// Order in which groups are stored. - JXL_ENC_FRAME_SETTING_GROUP_ORDER
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GroupOrder {
+    #[default]
+    Default = -1,
+    ScanLine = 0,
+    CenterFirst = 1,
+}
+
+/// Color transform of the frame. - JXL_ENC_FRAME_SETTING_COLOR_TRANSFORM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ColorTransform {
+    #[default]
+    Default = -1,
+    Xyb = 0,
+    None = 1,
+    YCbCr = 2,
+}
+
+/// Modular group size. - JXL_ENC_FRAME_SETTING_MODULAR_GROUP_SIZE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ModularGroupSize {
+    #[default]
+    Default = -1,
+    Size128 = 0,
+    Size256 = 1,
+    Size512 = 2,
+    Size1024 = 3,
+}
+
+/// Modular predictor. - JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ModularPredictor {
+    #[default]
+    Default = -1,
+    Zero = 0,
+    Left = 1,
+    Top = 2,
+    Average0 = 3,
+    Select = 4,
+    Gradient = 5,
+    Weighted = 6,
+    TopRight = 7,
+    TopLeft = 8,
+    LeftLeft = 9,
+    Average1 = 10,
+    Average2 = 11,
+    Average3 = 12,
+    TopTopAverage = 13,
+    /// Mix of `Gradient` and `Weighted`.
+    GradientWeighted = 14,
+    /// Mix of every predictor.
+    All = 15,
+}
+
+/// `None` lets the encoder choose (`-1` in libjxl).
+fn or_default(value: Option<u32>) -> i64 {
+    value.map_or(-1, i64::from)
+}
+
+fn or_default_float(value: Option<f32>) -> f32 {
+    value.unwrap_or(-1.0)
+}
+
+impl JxlEncoder {
+    /// 새로운 인코더 옵션 세트를 생성하며, 모든 값은 기본값으로 설정됩니다. - JxlEncoderFrameSettingsCreate()
+    /// 반환된 설정은 인코더에 연결되어 있으며, JxlEncoderDestroy()가 호출될 때 인코더에 의해 할당 해제됩니다.
+    /// 기존 설정을 기반으로 새로운 설정을 만들려면 `FrameSettings::try_clone`을 사용하세요.
+    pub fn create_frame_settings(&self) -> Result<FrameSettings<'_>, JxlError> {
+        FrameSettings::create(self, ptr::null())
+    }
+}
+
+impl<'enc> FrameSettings<'enc> {
+    fn create(encoder: &'enc JxlEncoder, source: *const c_void) -> Result<Self, JxlError> {
+        let settings =
+            unsafe { (encoder.lib.fns.encoder_frame_settings_create)(encoder.enc, source) };
+        if settings.is_null() {
+            Err(JxlError::EncoderFrameSettingsFailed)
+        } else {
+            Ok(FrameSettings { encoder, settings })
+        }
+    }
+
+    /// Creates new settings with every value copied from these. - JxlEncoderFrameSettingsCreate()
+    pub fn try_clone(&self) -> Result<FrameSettings<'enc>, JxlError> {
+        Self::create(self.encoder, self.settings)
+    }
+
+    /// Returns the encoder these settings belong to.
+    pub fn encoder(&self) -> &'enc JxlEncoder {
+        self.encoder
+    }
+
+    /// Returns the raw `JxlEncoderFrameSettings*`, valid while the encoder is alive.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.settings
+    }
+
+    fn check(&self, status: JxlEncoderStatus) -> Result<(), JxlError> {
+        match status {
+            JxlEncoderStatus::Success => Ok(()),
+            _ => Err(JxlError::from_encoder_status(
+                status,
+                self.encoder.get_error().ok(),
+            )),
+        }
+    }
+
+    /// Sets an integer option by id. - JxlEncoderFrameSettingsSetOption()
+    ///
+    /// The typed setters below cover every id; this is for ids newer than this crate.
+    pub fn set_option(&self, option: JxlEncoderFrameSettingId, value: i64) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe { (fns.encoder_frame_settings_set_option)(self.settings, option, value) })
+    }
+
+    /// Sets a float option by id. - JxlEncoderFrameSettingsSetFloatOption()
+    pub fn set_float_option(
+        &self,
+        option: JxlEncoderFrameSettingId,
+        value: f32,
+    ) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe {
+            (fns.encoder_frame_settings_set_float_option)(self.settings, option, value)
+        })
+    }
+
+    ///무손실 인코딩을 활성화합니다. - JxlEncoderSetFrameLossless()
+    /// 이것은 그 자체로 다른 옵션들과 같은 단순한 옵션이 아닙니다.
+    /// 대신 활성화되면 기존의 여러 옵션들(예: 거리, 모듈러 모드, 색상 변환 등)을 재정의하여 비트 단위의 무손실 인코딩을 가능하게 합니다.
+    /// 비활성화된 경우, 이러한 옵션들은 재정의되지 않습니다.
+    /// 하지만 이 옵션들이 수동으로 무손실 작동 조합으로 설정될 수 있기 때문에, 이 함수를 JXL_FALSE로 설정하여 사용하는 것이 반드시 손실 인코딩을 보장하지는 않습니다.
+    /// 다만, 기본 옵션 세트는 손실 인코딩입니다.
+    /// 무손실이 되려면 basic info 의 `uses_original_profile` 도 켜져 있어야 합니다.
+    pub fn set_lossless(&self, lossless: bool) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe { (fns.encoder_set_frame_lossless)(self.settings, lossless.into()) })
+    }
+
+    /// 손실 압축을 위한 거리 레벨을 설정합니다: 목표 최대 butteraugli 거리, 낮을수록 더 높은 품질을 의미합니다. - JxlEncoderSetFrameDistance()
+    /// 범위: 0 .. 25. 0.0 = 수학적으로 무손실 (그러나 진정한 무손실을 위해서는 JxlEncoderSetFrameLossless를 대신 사용하세요.
+    /// 거리를 0으로 설정하는 것만으로는 무손실의 유일한 요구사항이 아닙니다).
+    /// 1.0 = 시각적으로 무손실. 권장 범위: 0.5 .. 3.0. 기본값: 1.0.
+    pub fn set_distance(&self, distance: f32) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe { (fns.encoder_set_frame_distance)(self.settings, distance) })
+    }
+
+    /// Sets the distance of one extra channel; `-1` follows the color distance. - JxlEncoderSetExtraChannelDistance()
+    pub fn set_extra_channel_distance(&self, index: usize, distance: f32) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe {
+            (fns.encoder_set_extra_channel_distance)(self.settings, index, distance)
+        })
+    }
+
+    /// Sets the frame header (duration, name length, layer info). - JxlEncoderSetFrameHeader()
+    pub fn set_header(&self, frame_header: &JxlFrameHeader) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe { (fns.encoder_set_frame_header)(self.settings, frame_header) })
+    }
+
+    /// Sets how extra channel `index` is blended. - JxlEncoderSetExtraChannelBlendInfo()
+    pub fn set_extra_channel_blend_info(
+        &self,
+        index: usize,
+        blend_info: &JxlBlendInfo,
+    ) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe {
+            (fns.encoder_set_extra_channel_blend_info)(self.settings, index, blend_info)
+        })
+    }
+
+    /// Sets the name of the frame. - JxlEncoderSetFrameName()
+    pub fn set_name(&self, frame_name: &str) -> Result<(), JxlError> {
+        let c_frame_name =
+            CString::new(frame_name).map_err(|_| JxlError::InvalidInput(frame_name.to_string()))?;
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe { (fns.encoder_set_frame_name)(self.settings, c_frame_name.as_ptr()) })
+    }
+
+    /// Sets how input samples map to the stored bit depth. - JxlEncoderSetFrameBitDepth()
+    pub fn set_bit_depth(&self, bit_depth: &JxlBitDepth) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe { (fns.encoder_set_frame_bit_depth)(self.settings, bit_depth) })
+    }
+
+    ///다음 프레임을 인코딩하기 위해 JPEG 인코딩된 바이트를 추가합니다. - JxlEncoderAddJPEGFrame()
+    ///JxlEncoderSetBasicInfo가 아직 호출되지 않았다면, JxlEncoderAddJPEGFrame을 호출하면 추가된 JPEG 프레임의 파라미터로 암시적으로 호출됩니다.
+    ///JxlEncoderSetColorEncoding 또는 JxlEncoderSetICCProfile이 아직 호출되지 않았다면, JxlEncoderAddJPEGFrame을 호출하면 추가된 JPEG 프레임의 파라미터로 암시적으로 호출됩니다.
+    ///JxlEncoderStoreJPEGMetadata를 사용하여 JPEG 재구성 메타데이터를 저장하도록 인코더가 설정되어 있고 단일 JPEG 프레임이 추가된 경우, JPEG 코드스트림을 무손실로 재구성할 수 있게 됩니다.
+    ///이것이 마지막 프레임인 경우, 다음 JxlEncoderProcessOutput 호출 전에 JxlEncoderCloseInput 또는 JxlEncoderCloseFrames를 호출해야 합니다.
+    ///참고로, 이 함수는 무손실 압축을 위해 JPEG 프레임을 추가하는 데에만 사용할 수 있습니다. 손실 압축으로 인코딩하려면 JPEG을 수동으로 디코딩하고 JxlEncoderAddImageFrame을 사용하여 픽셀 버퍼를 추가해야 합니다.
+    ///
+    ///
+    /// 요약!!!! jpg 파일을 jxl로 인코딩할 때, add_jpeg_frame만 호출하면 JxlEncoderSetBasicInfo, JxlEncoderSetICCProfile, JxlEncoderSetColorEncoding을 호출하지 않아도 된다
+    pub fn add_jpeg_frame(&self, buffer: &[u8]) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe {
+            (fns.encoder_add_jpeg_frame)(self.settings, buffer.as_ptr(), buffer.len())
+        })
+    }
+
+    /// Adds a frame of interleaved pixels. - JxlEncoderAddImageFrame()
+    pub fn add_image_frame(
+        &self,
+        pixel_format: &JxlPixelFormat,
+        buffer: &[u8],
+    ) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe {
+            (fns.encoder_add_image_frame)(
+                self.settings,
+                pixel_format,
+                buffer.as_ptr(),
+                buffer.len(),
+            )
+        })
+    }
+
+    /// Sets the pixels of extra channel `index` for the next frame. - JxlEncoderSetExtraChannelBuffer()
+    pub fn set_extra_channel_buffer(
+        &self,
+        pixel_format: &JxlPixelFormat,
+        buffer: &[u8],
+        index: u32,
+    ) -> Result<(), JxlError> {
+        let fns = &self.encoder.lib.fns;
+        self.check(unsafe {
+            (fns.encoder_set_extra_channel_buffer)(
+                self.settings,
+                pixel_format,
+                buffer.as_ptr(),
+                buffer.len(),
+                index,
+            )
+        })
+    }
+
+    /// Adds a frame from raw chunked-input callbacks. - JxlEncoderAddChunkedFrame()
+    ///
+    /// # Safety
+    ///
+    /// The callbacks in `chunked_frame_input` must follow the `JxlChunkedFrameInputSource`
+    /// contract for `opaque` until this returns. Prefer `add_chunked_frame_from`.
+    pub unsafe fn add_chunked_frame(
+        &self,
+        is_last_frame: bool,
+        chunked_frame_input: JxlChunkedFrameInputSource,
+    ) -> Result<(), JxlError> {
+        let add_chunked_frame_fn = self.encoder.lib.fns.encoder_add_chunked_frame()?;
+        self.check(add_chunked_frame_fn(
+            self.settings,
+            is_last_frame.into(),
+            chunked_frame_input,
+        ))
+    }
+
+    /// Encoder effort, 1 (fastest) to 10 (slowest). Default 7. - JXL_ENC_FRAME_SETTING_EFFORT
+    pub fn set_effort(&self, effort: u32) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Effort, effort.into())
+    }
+
+    /// Decoding speed tier, 0 (best quality) to 4 (fastest decoding). Default 0. - JXL_ENC_FRAME_SETTING_DECODING_SPEED
+    pub fn set_decoding_speed(&self, tier: u32) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::DecodingSpeed, tier.into())
+    }
+
+    /// Downsampling of the color channels. - JXL_ENC_FRAME_SETTING_RESAMPLING
+    pub fn set_resampling(&self, resampling: Resampling) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Resampling, resampling as i64)
+    }
+
+    /// Downsampling of the extra channels. - JXL_ENC_FRAME_SETTING_EXTRA_CHANNEL_RESAMPLING
+    pub fn set_extra_channel_resampling(&self, resampling: Resampling) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::ExtraChannelResampling,
+            resampling as i64,
+        )
+    }
+
+    /// Input is already downsampled by the resampling factor. - JXL_ENC_FRAME_SETTING_ALREADY_DOWNSAMPLED
+    pub fn set_already_downsampled(&self, already_downsampled: bool) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::AlreadyDownsampled,
+            already_downsampled.into(),
+        )
+    }
+
+    /// Adds noise matching the given ISO film speed; `0.0` disables it. - JXL_ENC_FRAME_SETTING_PHOTON_NOISE
+    pub fn set_photon_noise_iso(&self, iso: f32) -> Result<(), JxlError> {
+        self.set_float_option(JxlEncoderFrameSettingId::PhotonNoise, iso)
+    }
+
+    /// Adaptive noise generation. - JXL_ENC_FRAME_SETTING_NOISE
+    pub fn set_noise(&self, noise: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Noise, noise as i64)
+    }
+
+    /// Dot detection. - JXL_ENC_FRAME_SETTING_DOTS
+    pub fn set_dots(&self, dots: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Dots, dots as i64)
+    }
+
+    /// Patch detection. - JXL_ENC_FRAME_SETTING_PATCHES
+    pub fn set_patches(&self, patches: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Patches, patches as i64)
+    }
+
+    /// Edge preserving filter level, 0 to 3. - JXL_ENC_FRAME_SETTING_EPF
+    pub fn set_epf(&self, level: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Epf, or_default(level))
+    }
+
+    /// Gaborish filter. - JXL_ENC_FRAME_SETTING_GABORISH
+    pub fn set_gaborish(&self, gaborish: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Gaborish, gaborish as i64)
+    }
+
+    /// Modular mode (`On`) or VarDCT (`Off`). - JXL_ENC_FRAME_SETTING_MODULAR
+    pub fn set_modular(&self, modular: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Modular, modular as i64)
+    }
+
+    /// Keep color of invisible pixels. - JXL_ENC_FRAME_SETTING_KEEP_INVISIBLE
+    pub fn set_keep_invisible(&self, keep_invisible: Toggle) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::KeepInvisible,
+            keep_invisible as i64,
+        )
+    }
+
+    /// Group order. - JXL_ENC_FRAME_SETTING_GROUP_ORDER
+    pub fn set_group_order(&self, order: GroupOrder) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::GroupOrder, order as i64)
+    }
+
+    /// Center x of `GroupOrder::CenterFirst`; `None` is the image center. - JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_X
+    pub fn set_group_order_center_x(&self, x: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::GroupOrderCenterX, or_default(x))
+    }
+
+    /// Center y of `GroupOrder::CenterFirst`; `None` is the image center. - JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_Y
+    pub fn set_group_order_center_y(&self, y: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::GroupOrderCenterY, or_default(y))
+    }
+
+    /// Progressive (squeeze) modular encoding. - JXL_ENC_FRAME_SETTING_RESPONSIVE
+    pub fn set_responsive(&self, responsive: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Responsive, responsive as i64)
+    }
+
+    /// Spectral progression of VarDCT AC. - JXL_ENC_FRAME_SETTING_PROGRESSIVE_AC
+    pub fn set_progressive_ac(&self, progressive_ac: Toggle) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::ProgressiveAc,
+            progressive_ac as i64,
+        )
+    }
+
+    /// Quantization progression of VarDCT AC. - JXL_ENC_FRAME_SETTING_QPROGRESSIVE_AC
+    pub fn set_qprogressive_ac(&self, qprogressive_ac: Toggle) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::QprogressiveAc,
+            qprogressive_ac as i64,
+        )
+    }
+
+    /// Progressive DC levels, 0 to 2. - JXL_ENC_FRAME_SETTING_PROGRESSIVE_DC
+    pub fn set_progressive_dc(&self, levels: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::ProgressiveDc, or_default(levels))
+    }
+
+    /// Percentage of colors for a global channel palette, 0 to 100. - JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GLOBAL_PERCENT
+    pub fn set_channel_colors_global_percent(&self, percent: Option<f32>) -> Result<(), JxlError> {
+        self.set_float_option(
+            JxlEncoderFrameSettingId::ChannelColorsGlobalPercent,
+            or_default_float(percent),
+        )
+    }
+
+    /// Percentage of colors for a per-group channel palette, 0 to 100. - JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GROUP_PERCENT
+    pub fn set_channel_colors_group_percent(&self, percent: Option<f32>) -> Result<(), JxlError> {
+        self.set_float_option(
+            JxlEncoderFrameSettingId::ChannelColorsGroupPercent,
+            or_default_float(percent),
+        )
+    }
+
+    /// Maximum colors of a palette; `Some(0)` disables palettes. - JXL_ENC_FRAME_SETTING_PALETTE_COLORS
+    pub fn set_palette_colors(&self, colors: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::PaletteColors, or_default(colors))
+    }
+
+    /// Delta palette (lossy). - JXL_ENC_FRAME_SETTING_LOSSY_PALETTE
+    pub fn set_lossy_palette(&self, lossy_palette: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::LossyPalette, lossy_palette as i64)
+    }
+
+    /// Color transform. - JXL_ENC_FRAME_SETTING_COLOR_TRANSFORM
+    pub fn set_color_transform(&self, transform: ColorTransform) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::ColorTransform, transform as i64)
+    }
+
+    /// Reversible color transform of modular mode, 0 to 41. - JXL_ENC_FRAME_SETTING_MODULAR_COLOR_SPACE
+    pub fn set_modular_color_space(&self, rct: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::ModularColorSpace, or_default(rct))
+    }
+
+    /// Modular group size. - JXL_ENC_FRAME_SETTING_MODULAR_GROUP_SIZE
+    pub fn set_modular_group_size(&self, size: ModularGroupSize) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::ModularGroupSize, size as i64)
+    }
+
+    /// Modular predictor. - JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR
+    pub fn set_modular_predictor(&self, predictor: ModularPredictor) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::ModularPredictor, predictor as i64)
+    }
+
+    /// Percentage of pixels used to learn MA trees, 0 to 100. - JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT
+    pub fn set_modular_ma_tree_learning_percent(
+        &self,
+        percent: Option<f32>,
+    ) -> Result<(), JxlError> {
+        self.set_float_option(
+            JxlEncoderFrameSettingId::ModularMaTreeLearningPercent,
+            or_default_float(percent),
+        )
+    }
+
+    /// Previous channels used as MA tree properties, 0 to 11. - JXL_ENC_FRAME_SETTING_MODULAR_NB_PREV_CHANNELS
+    pub fn set_modular_nb_prev_channels(&self, channels: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::ModularNbPrevChannels,
+            or_default(channels),
+        )
+    }
+
+    /// Chroma from luma when recompressing JPEG. - JXL_ENC_FRAME_SETTING_JPEG_RECON_CFL
+    pub fn set_jpeg_recon_cfl(&self, cfl: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::JpegReconCfl, cfl as i64)
+    }
+
+    /// Lists the frame in the frame index box. - JXL_ENC_FRAME_INDEX_BOX
+    pub fn set_index_box(&self, index_box: bool) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::IndexBox, index_box.into())
+    }
+
+    /// Brotli effort for compressed boxes, 0 to 11. - JXL_ENC_FRAME_SETTING_BROTLI_EFFORT
+    pub fn set_brotli_effort(&self, effort: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::BrotliEffort, or_default(effort))
+    }
+
+    /// Brotli-compress metadata boxes copied from JPEG. - JXL_ENC_FRAME_SETTING_JPEG_COMPRESS_BOXES
+    pub fn set_jpeg_compress_boxes(&self, compress: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::JpegCompressBoxes, compress as i64)
+    }
+
+    /// Streaming buffering strategy, 0 to 3. - JXL_ENC_FRAME_SETTING_BUFFERING
+    pub fn set_buffering(&self, buffering: Option<u32>) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::Buffering, or_default(buffering))
+    }
+
+    /// Keep Exif when recompressing JPEG. - JXL_ENC_FRAME_SETTING_JPEG_KEEP_EXIF
+    pub fn set_jpeg_keep_exif(&self, keep: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::JpegKeepExif, keep as i64)
+    }
+
+    /// Keep XMP when recompressing JPEG. - JXL_ENC_FRAME_SETTING_JPEG_KEEP_XMP
+    pub fn set_jpeg_keep_xmp(&self, keep: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::JpegKeepXmp, keep as i64)
+    }
+
+    /// Keep JUMBF when recompressing JPEG. - JXL_ENC_FRAME_SETTING_JPEG_KEEP_JUMBF
+    pub fn set_jpeg_keep_jumbf(&self, keep: Toggle) -> Result<(), JxlError> {
+        self.set_option(JxlEncoderFrameSettingId::JpegKeepJumbf, keep as i64)
+    }
+
+    /// Run heuristics on the full image even when streaming. - JXL_ENC_FRAME_SETTING_USE_FULL_IMAGE_HEURISTICS
+    pub fn set_use_full_image_heuristics(&self, full_image: bool) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::UseFullImageHeuristics,
+            full_image.into(),
+        )
+    }
+
+    /// Disable perceptual optimizations, e.g. for non-photographic data. - JXL_ENC_FRAME_SETTING_DISABLE_PERCEPTUAL_HEURISTICS
+    pub fn set_disable_perceptual_heuristics(&self, disable: bool) -> Result<(), JxlError> {
+        self.set_option(
+            JxlEncoderFrameSettingId::DisablePerceptualHeuristics,
+            disable.into(),
+        )
+    }
+}
+
+impl fmt::Debug for FrameSettings<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameSettings")
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
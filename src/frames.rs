@@ -0,0 +1,227 @@
+use crate::decode::FrameReader;
+use crate::DecodeOptions;
+use crate::DecodedFrame;
+use crate::ExtraChannel;
+use crate::JxlAnimationHeader;
+use crate::JxlBasicInfo;
+use crate::JxlDecoder;
+use crate::JxlError;
+use crate::JxlFrameHeader;
+use crate::JxlPixelFormat;
+use crate::PixelBuffer;
+
+use std::iter::FusedIterator;
+use std::time::Duration;
+
+/// One frame yielded by `JxlDecoder::frames`.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub header: JxlFrameHeader,
+    pub name: String,
+    /// Interleaved pixels in `Frames::pixel_format`.
+    pub pixels: PixelBuffer,
+    /// One buffer per entry of `Frames::extra_channels`, empty unless requested.
+    pub extra_channels: Vec<PixelBuffer>,
+    /// How long the frame is shown. Zero for still images and for layers that are only
+    /// blended into the next frame.
+    pub duration: Duration,
+}
+
+impl Frame {
+    fn from_decoded(frame: DecodedFrame, info: &JxlBasicInfo) -> Self {
+        let duration = if info.have_animation.into() {
+            info.animation.ticks_to_duration(frame.header.duration)
+        } else {
+            Duration::ZERO
+        };
+        Frame {
+            header: frame.header,
+            name: frame.name,
+            pixels: frame.pixels,
+            extra_channels: frame.extra_channels,
+            duration,
+        }
+    }
+
+    /// Width of this frame; differs from the image width only for non-coalesced layers.
+    pub fn width(&self, info: &JxlBasicInfo) -> u32 {
+        if self.header.layer_info.have_crop.into() {
+            self.header.layer_info.xsize
+        } else {
+            info.xsize
+        }
+    }
+
+    /// Height of this frame; differs from the image height only for non-coalesced layers.
+    pub fn height(&self, info: &JxlBasicInfo) -> u32 {
+        if self.header.layer_info.have_crop.into() {
+            self.header.layer_info.ysize
+        } else {
+            info.ysize
+        }
+    }
+}
+
+impl JxlAnimationHeader {
+    /// Converts a frame duration in ticks to time; zero if the tick rate is unset.
+    pub fn ticks_to_duration(&self, ticks: u32) -> Duration {
+        if self.tps_numerator == 0 {
+            return Duration::ZERO;
+        }
+        let nanos = u128::from(ticks) * u128::from(self.tps_denominator) * 1_000_000_000
+            / u128::from(self.tps_numerator);
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// Converts time to the nearest number of ticks, at least one for a non-zero time.
+    pub fn duration_to_ticks(&self, duration: Duration) -> u32 {
+        if self.tps_denominator == 0 || duration.is_zero() {
+            return 0;
+        }
+        let ticks = (duration.as_nanos() * u128::from(self.tps_numerator)
+            + u128::from(self.tps_denominator) * 500_000_000)
+            / (u128::from(self.tps_denominator) * 1_000_000_000);
+        u32::try_from(ticks).unwrap_or(u32::MAX).max(1)
+    }
+}
+
+/// Iterator over the frames of an image, decoded one at a time. Created by
+/// `JxlDecoder::frames`.
+///
+/// With `DecodeOptions::coalescing` (the default) every frame is a full canvas with
+/// blending applied. Without it, each layer is yielded as stored, with its crop in
+/// `header.layer_info` and zero-duration layers included.
+pub struct Frames<'a> {
+    reader: FrameReader<'a>,
+    input: Option<&'a [u8]>,
+    done: bool,
+}
+
+impl JxlDecoder {
+    /// Decodes `data` frame by frame instead of all at once.
+    ///
+    /// Resets the decoder first; an attached parallel runner is kept.
+    pub fn frames<'a>(
+        &'a self,
+        data: &'a [u8],
+        options: &DecodeOptions,
+    ) -> Result<Frames<'a>, JxlError> {
+        Ok(Frames {
+            reader: FrameReader::new(self, options)?,
+            input: Some(data),
+            done: false,
+        })
+    }
+}
+
+impl Frames<'_> {
+    /// Returns the basic info, available once the first frame has been yielded.
+    pub fn basic_info(&self) -> Option<&JxlBasicInfo> {
+        self.reader.basic_info.as_ref()
+    }
+
+    /// Returns the layout of `Frame::pixels`.
+    pub fn pixel_format(&self) -> &JxlPixelFormat {
+        &self.reader.pixel_format
+    }
+
+    /// Returns the ICC profile of the decoded pixels, if there is one.
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.reader.icc_profile.as_deref()
+    }
+
+    /// Returns the extra channels of the image.
+    pub fn extra_channels(&self) -> &[ExtraChannel] {
+        &self.reader.extra_channels
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<Frame, JxlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let decoder = self.reader.decoder;
+        let input = &mut self.input;
+        let result = self.reader.next_frame(&mut || match input.take() {
+            Some(data) => {
+                decoder.set_input(data)?;
+                decoder.close_input();
+                Ok(())
+            }
+            None => Err(JxlError::NotEnoughInput),
+        });
+
+        match result {
+            Ok(Some(frame)) => match &self.reader.basic_info {
+                Some(info) => Some(Ok(Frame::from_decoded(frame, info))),
+                None => {
+                    self.done = true;
+                    Some(Err(JxlError::DecodingFailed))
+                }
+            },
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl FusedIterator for Frames<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JxlBool;
+
+    #[test]
+    fn test_ticks_to_duration() {
+        let animation = JxlAnimationHeader {
+            tps_numerator: 100,
+            tps_denominator: 1,
+            num_loops: 0,
+            have_timecodes: JxlBool::False,
+        };
+        assert_eq!(animation.ticks_to_duration(7), Duration::from_millis(70));
+
+        let ntsc = JxlAnimationHeader {
+            tps_numerator: 30000,
+            tps_denominator: 1001,
+            ..animation
+        };
+        assert_eq!(ntsc.ticks_to_duration(1), Duration::from_nanos(33_366_666));
+
+        let unset = JxlAnimationHeader {
+            tps_numerator: 0,
+            ..animation
+        };
+        assert_eq!(unset.ticks_to_duration(5), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_duration_to_ticks() {
+        let animation = JxlAnimationHeader {
+            tps_numerator: 100,
+            tps_denominator: 1,
+            num_loops: 0,
+            have_timecodes: JxlBool::False,
+        };
+        assert_eq!(animation.duration_to_ticks(Duration::from_millis(70)), 7);
+        assert_eq!(animation.duration_to_ticks(Duration::from_millis(1)), 1);
+        assert_eq!(animation.duration_to_ticks(Duration::ZERO), 0);
+
+        let ntsc = JxlAnimationHeader {
+            tps_numerator: 30000,
+            tps_denominator: 1001,
+            ..animation
+        };
+        assert_eq!(ntsc.duration_to_ticks(ntsc.ticks_to_duration(3)), 3);
+    }
+}
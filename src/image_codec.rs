@@ -0,0 +1,294 @@
+use crate::encode::{encode_pixels, PixelLayout};
+use crate::DecodeOptions;
+use crate::EncodeOptions;
+use crate::JxlBasicInfo;
+use crate::JxlColorProfileTarget;
+use crate::JxlDataType;
+use crate::JxlDecoder;
+use crate::JxlDecoderStatus;
+use crate::JxlError;
+use crate::JxlLibrary;
+use crate::JXL_CODESTREAM_SIGNATURE;
+use crate::JXL_CONTAINER_SIGNATURE;
+
+use image::error::{DecodingError, EncodingError, ImageFormatHint};
+use image::hooks::{
+    decoding_hook_registered, register_decoding_hook, register_format_detection_hook,
+};
+use image::{ColorType, ExtendedColorType, ImageDecoder, ImageEncoder, ImageError, ImageResult};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+const JXL_EXTENSION: &str = "jxl";
+
+fn format_hint() -> ImageFormatHint {
+    ImageFormatHint::Name("JPEG XL".to_string())
+}
+
+fn decoding_error(e: JxlError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(format_hint(), format!("{e:?}")))
+}
+
+fn encoding_error(e: JxlError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(format_hint(), format!("{e:?}")))
+}
+
+/// `image::ImageDecoder` for JPEG XL.
+///
+/// The whole input is read up front; only the header is decoded until `read_image`.
+pub struct JxlImageDecoder<R> {
+    decoder: JxlDecoder,
+    data: Vec<u8>,
+    basic_info: JxlBasicInfo,
+    icc_profile: Option<Vec<u8>>,
+    color_type: ColorType,
+    _reader: PhantomData<R>,
+}
+
+impl<R: Read> JxlImageDecoder<R> {
+    /// Creates a decoder using `JxlLibrary::discover`.
+    pub fn new(reader: R) -> ImageResult<Self> {
+        Self::with_library(reader, JxlLibrary::discover().map_err(decoding_error)?)
+    }
+
+    /// Creates a decoder on an already loaded library.
+    pub fn with_library(mut reader: R, lib: Arc<JxlLibrary>) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let decoder = JxlDecoder::with_library(lib).map_err(decoding_error)?;
+        let (basic_info, icc_profile) = read_header(&decoder, &data).map_err(decoding_error)?;
+        let color_type = color_type_for(&basic_info);
+
+        Ok(JxlImageDecoder {
+            decoder,
+            data,
+            basic_info,
+            icc_profile,
+            color_type,
+            _reader: PhantomData,
+        })
+    }
+
+    /// Returns the basic info of the image.
+    pub fn basic_info(&self) -> &JxlBasicInfo {
+        &self.basic_info
+    }
+}
+
+/// Decodes just far enough to know the size, layout and color profile.
+fn read_header(
+    decoder: &JxlDecoder,
+    data: &[u8],
+) -> Result<(JxlBasicInfo, Option<Vec<u8>>), JxlError> {
+    decoder.subscribe_events(JxlDecoderStatus::BasicInfo | JxlDecoderStatus::ColorEncoding)?;
+    decoder.set_input(data)?;
+    decoder.close_input();
+
+    let mut basic_info = None;
+    loop {
+        match decoder.process_input()? {
+            JxlDecoderStatus::BasicInfo => basic_info = Some(decoder.get_basic_info()?),
+            JxlDecoderStatus::ColorEncoding => {
+                let target = JxlColorProfileTarget::Data;
+                let mut size = 0;
+                let icc_profile = match decoder.get_icc_profile_size(target, Some(&mut size)) {
+                    Ok(()) if size > 0 => {
+                        let mut icc = vec![0u8; size];
+                        decoder.get_color_as_icc_profile(target, &mut icc, size)?;
+                        Some(icc)
+                    }
+                    _ => None,
+                };
+                let basic_info = basic_info.ok_or(JxlError::DecodingFailed)?;
+                return Ok((basic_info, icc_profile));
+            }
+            JxlDecoderStatus::NeedMoreInput => return Err(JxlError::NotEnoughInput),
+            JxlDecoderStatus::Success => return Err(JxlError::DecodingFailed),
+            _ => {}
+        }
+    }
+}
+
+/// Picks the `image` color type closest to the stored image.
+///
+/// `image` has no grayscale float types, so float grayscale is widened to RGB.
+pub(crate) fn color_type_for(info: &JxlBasicInfo) -> ColorType {
+    let gray = info.num_color_channels == 1;
+    let alpha = info.alpha_bits > 0;
+    if info.exponent_bits_per_sample > 0 || info.bits_per_sample > 16 {
+        return if alpha {
+            ColorType::Rgba32F
+        } else {
+            ColorType::Rgb32F
+        };
+    }
+    let wide = info.bits_per_sample > 8;
+    match (gray, alpha, wide) {
+        (true, false, false) => ColorType::L8,
+        (true, true, false) => ColorType::La8,
+        (false, false, false) => ColorType::Rgb8,
+        (false, true, false) => ColorType::Rgba8,
+        (true, false, true) => ColorType::L16,
+        (true, true, true) => ColorType::La16,
+        (false, false, true) => ColorType::Rgb16,
+        (false, true, true) => ColorType::Rgba16,
+    }
+}
+
+/// Options that decode straight into the layout of `color_type`.
+pub(crate) fn decode_options_for(color_type: ColorType) -> DecodeOptions {
+    let data_type = match color_type {
+        ColorType::Rgb32F | ColorType::Rgba32F => JxlDataType::Float,
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => {
+            JxlDataType::Uint16
+        }
+        _ => JxlDataType::Uint8,
+    };
+    DecodeOptions {
+        data_type: Some(data_type),
+        num_channels: Some(u32::from(color_type.channel_count())),
+        extra_channels: false,
+        ..DecodeOptions::default()
+    }
+}
+
+impl<R: Read> ImageDecoder for JxlImageDecoder<R> {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.basic_info.xsize, self.basic_info.ysize)
+    }
+
+    fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    fn icc_profile(&mut self) -> ImageResult<Option<Vec<u8>>> {
+        Ok(self.icc_profile.clone())
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        assert_eq!(u64::try_from(buf.len()), Ok(self.total_bytes()));
+
+        let options = decode_options_for(self.color_type);
+        let image = self
+            .decoder
+            .decode(&self.data, &options)
+            .map_err(decoding_error)?;
+        let frame = image
+            .frames
+            .first()
+            .ok_or_else(|| decoding_error(JxlError::DecodingFailed))?;
+
+        let pixels = frame.pixels.as_bytes();
+        if pixels.len() != buf.len() {
+            return Err(decoding_error(JxlError::OutputBufferError));
+        }
+        buf.copy_from_slice(pixels);
+        Ok(())
+    }
+
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+/// `image::ImageEncoder` for JPEG XL.
+///
+/// Lossless by default; use `with_distance` for lossy output.
+pub struct JxlImageEncoder<W> {
+    writer: W,
+    lib: Arc<JxlLibrary>,
+    options: EncodeOptions,
+}
+
+impl<W: Write> JxlImageEncoder<W> {
+    /// Creates a lossless encoder using `JxlLibrary::discover`.
+    pub fn new(writer: W) -> ImageResult<Self> {
+        Ok(Self::with_library(
+            writer,
+            JxlLibrary::discover().map_err(encoding_error)?,
+        ))
+    }
+
+    /// Creates a lossless encoder on an already loaded library.
+    pub fn with_library(writer: W, lib: Arc<JxlLibrary>) -> Self {
+        JxlImageEncoder {
+            writer,
+            lib,
+            options: EncodeOptions::default(),
+        }
+    }
+
+    /// Replaces all encoding options. An ICC profile set later by `image` takes precedence.
+    pub fn with_options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the butteraugli distance; `0.0` is lossless, `1.0` visually lossless.
+    pub fn with_distance(mut self, distance: f32) -> Self {
+        self.options.distance = distance;
+        self
+    }
+
+    /// Sets the encoder effort, 1 (fastest) to 10 (slowest).
+    pub fn with_effort(mut self, effort: u32) -> Self {
+        self.options.effort = effort;
+        self
+    }
+}
+
+impl<W: Write> ImageEncoder for JxlImageEncoder<W> {
+    fn write_image(
+        mut self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ExtendedColorType,
+    ) -> ImageResult<()> {
+        PixelLayout::from_color_type(color_type).map_err(|_| {
+            ImageError::Unsupported(image::error::UnsupportedError::from_format_and_kind(
+                format_hint(),
+                image::error::UnsupportedErrorKind::Color(color_type),
+            ))
+        })?;
+        let encoded = encode_pixels(&self.lib, buf, width, height, color_type, &self.options)
+            .map_err(encoding_error)?;
+        self.writer.write_all(&encoded)?;
+        Ok(())
+    }
+
+    fn set_icc_profile(
+        &mut self,
+        icc_profile: Vec<u8>,
+    ) -> Result<(), image::error::UnsupportedError> {
+        self.options.icc_profile = Some(icc_profile);
+        Ok(())
+    }
+}
+
+/// Registers JPEG XL with `image::ImageReader`, so `image::open("a.jxl")` and
+/// `ImageReader::with_guessed_format` decode through `lib`.
+///
+/// Returns `false` if a `jxl` decoding hook was already registered. `image` has no encoding
+/// hooks; write with `DynamicImage::write_with_encoder(JxlImageEncoder::new(..)?)`.
+pub fn register_image_hooks(lib: Arc<JxlLibrary>) -> bool {
+    if decoding_hook_registered(JXL_EXTENSION.as_ref()) {
+        return false;
+    }
+    let registered = register_decoding_hook(
+        JXL_EXTENSION.into(),
+        Box::new(move |reader| {
+            Ok(Box::new(JxlImageDecoder::with_library(
+                reader,
+                lib.clone(),
+            )?))
+        }),
+    );
+    if registered {
+        register_format_detection_hook(JXL_EXTENSION.into(), &JXL_CODESTREAM_SIGNATURE, None);
+        register_format_detection_hook(JXL_EXTENSION.into(), &JXL_CONTAINER_SIGNATURE, None);
+    }
+    registered
+}
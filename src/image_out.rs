@@ -0,0 +1,567 @@
+use crate::JxlDataType;
+use crate::JxlDecoder;
+use crate::JxlDecoderStatus;
+use crate::JxlEndianness;
+use crate::JxlError;
+use crate::JxlPixelFormat;
+
+use std::any::{type_name, Any};
+use std::ffi::{c_int, c_void};
+use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+type PanicPayload = Box<dyn Any + Send>;
+
+/// A sample type the image-out callbacks hand out rows of.
+///
+/// # Safety
+///
+/// `accepts` may only return `true` for data types whose samples have the size and bit
+/// validity of `Self`.
+pub unsafe trait JxlSample: Copy + Send + Sync + 'static {
+    /// Returns `true` if samples of `data_type` can be read as `Self`.
+    fn accepts(data_type: JxlDataType) -> bool;
+}
+
+unsafe impl JxlSample for u8 {
+    fn accepts(data_type: JxlDataType) -> bool {
+        data_type == JxlDataType::Uint8
+    }
+}
+
+/// `Float16` samples are handed out as their raw bits.
+unsafe impl JxlSample for u16 {
+    fn accepts(data_type: JxlDataType) -> bool {
+        matches!(data_type, JxlDataType::Uint16 | JxlDataType::Float16)
+    }
+}
+
+unsafe impl JxlSample for f32 {
+    fn accepts(data_type: JxlDataType) -> bool {
+        data_type == JxlDataType::Float
+    }
+}
+
+/// Closure state registered with libjxl; dropping it frees the closure.
+pub(crate) trait ImageOutState {
+    /// Takes the payload of a panic raised inside the callback, if any.
+    fn take_panic(&self) -> Option<PanicPayload>;
+}
+
+/// The image-out callback held by a decoder.
+pub(crate) struct ImageOutSlot {
+    state: Arc<dyn ImageOutState>,
+    /// Registered for the frame being decoded; cleared once the frame is done.
+    active: bool,
+}
+
+/// State of `set_image_out_callback`. libjxl may call it from several threads at once when
+/// a parallel runner is set, so the `FnMut` sits behind a mutex.
+struct RowCallback<T, F> {
+    inner: Mutex<RowInner<T, F>>,
+}
+
+struct RowInner<T, F> {
+    callback: F,
+    num_channels: usize,
+    scratch: Vec<T>,
+    panic: Option<PanicPayload>,
+}
+
+impl<T, F> ImageOutState for RowCallback<T, F> {
+    fn take_panic(&self) -> Option<PanicPayload> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.panic.take()
+    }
+}
+
+/// State of `set_multithreaded_image_out_callback`, shared by every thread.
+struct ThreadedCallback<T, S, I, R> {
+    init: I,
+    run: R,
+    num_channels: usize,
+    panicked: AtomicBool,
+    panic: Mutex<Option<PanicPayload>>,
+    _marker: PhantomData<fn() -> (T, S)>,
+}
+
+/// Per-frame state created by the init callback; one entry per thread id.
+///
+/// Holds its own reference to the callback, since libjxl may destroy the states only after
+/// the next frame's callback replaced this one in the decoder.
+struct ThreadStates<T, S, I, R> {
+    callback: Arc<ThreadedCallback<T, S, I, R>>,
+    threads: Vec<Mutex<ThreadState<T, S>>>,
+}
+
+struct ThreadState<T, S> {
+    state: S,
+    scratch: Vec<T>,
+}
+
+impl<T, S, I, R> ThreadedCallback<T, S, I, R> {
+    /// Runs `f`, keeping the first panic and skipping every call after it.
+    fn guard<V>(&self, f: impl FnOnce() -> V) -> Option<V> {
+        if self.panicked.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.catch(f)
+    }
+
+    /// Runs `f` even after a panic, keeping the first one.
+    fn catch<V>(&self, f: impl FnOnce() -> V) -> Option<V> {
+        // 패닉이 C 코드로 넘어가지 않도록 잡아 둠
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(value) => Some(value),
+            Err(payload) => {
+                if !self.panicked.swap(true, Ordering::Relaxed) {
+                    *self.panic.lock().unwrap_or_else(|e| e.into_inner()) = Some(payload);
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<T, S, I, R> ImageOutState for ThreadedCallback<T, S, I, R> {
+    fn take_panic(&self) -> Option<PanicPayload> {
+        self.panic.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl JxlDecoder {
+    /// Sets a callback that receives the decoded image row by row instead of an output
+    /// buffer. - JxlDecoderSetImageOutCallback()
+    ///
+    /// The callback gets `x`, `y` and `num_pixels * num_channels` samples of the format.
+    /// `T` must match `format.data_type` (`u8`, `u16` for `Uint16`/`Float16`, `f32`) and
+    /// multi-byte samples must be native-endian. Call it on `NeedImageOutBuffer`, once per frame;
+    /// a second registration for the same frame is rejected. The closure is freed when the
+    /// next frame's callback is set, or on `reset`, `rewind` or drop.
+    ///
+    /// A panic inside the callback stops further calls and is resumed from `process_input`.
+    pub fn set_image_out_callback<T, F>(
+        &self,
+        format: &JxlPixelFormat,
+        callback: F,
+    ) -> Result<(), JxlError>
+    where
+        T: JxlSample,
+        F: FnMut(usize, usize, &[T]) + Send + 'static,
+    {
+        let num_channels = check_format::<T>(format)?;
+        let state = Arc::new(RowCallback {
+            inner: Mutex::new(RowInner {
+                callback,
+                num_channels,
+                scratch: Vec::<T>::new(),
+                panic: None,
+            }),
+        });
+        let opaque = Arc::as_ptr(&state) as *mut c_void;
+
+        self.register_image_out(state, || unsafe {
+            (self.library().fns.decoder_set_image_out_callback)(
+                self.dec,
+                format,
+                row_callback::<T, F>,
+                opaque,
+            )
+        })
+    }
+
+    /// Sets a callback that libjxl calls from several threads at once, each with its own
+    /// state. - JxlDecoderSetMultithreadedImageOutCallback()
+    ///
+    /// For every frame, `init` is called once per thread with the most pixels a single `run`
+    /// call gets, and returns that thread's state. `run` then receives the state of the calling
+    /// thread, `x`, `y` and `num_pixels * num_channels` samples, and may run concurrently
+    /// for different threads. The states are dropped when libjxl is done with the frame.
+    /// `T` and the registration rules are as for `set_image_out_callback`.
+    ///
+    /// A panic inside `init` or `run` stops further calls and is resumed from `process_input`.
+    pub fn set_multithreaded_image_out_callback<T, S, I, R>(
+        &self,
+        format: &JxlPixelFormat,
+        init: I,
+        run: R,
+    ) -> Result<(), JxlError>
+    where
+        T: JxlSample,
+        S: Send + 'static,
+        I: Fn(usize) -> S + Send + Sync + 'static,
+        R: Fn(&mut S, usize, usize, &[T]) + Send + Sync + 'static,
+    {
+        let num_channels = check_format::<T>(format)?;
+        let state = Arc::new(ThreadedCallback {
+            init,
+            run,
+            num_channels,
+            panicked: AtomicBool::new(false),
+            panic: Mutex::new(None),
+            _marker: PhantomData::<fn() -> (T, S)>,
+        });
+        let opaque = Arc::as_ptr(&state) as *mut c_void;
+
+        self.register_image_out(state, || unsafe {
+            (self
+                .library()
+                .fns
+                .decoder_set_multithreaded_image_out_callback)(
+                self.dec,
+                format,
+                Some(init_threads::<T, S, I, R>),
+                Some(run_thread::<T, S, I, R>),
+                Some(destroy_threads::<T, S, I, R>),
+                opaque,
+            )
+        })
+    }
+
+    /// Hands `state` to libjxl through `set` and keeps it alive while libjxl may use it.
+    pub(crate) fn register_image_out(
+        &self,
+        state: Arc<dyn ImageOutState>,
+        set: impl FnOnce() -> c_int,
+    ) -> Result<(), JxlError> {
+        let mut slot = self.image_out.borrow_mut();
+        if slot.as_ref().is_some_and(|slot| slot.active) {
+            return Err(JxlError::UnsupportedOperation(
+                "An image-out callback is already set for this frame".to_string(),
+            ));
+        }
+        if set() != 0 {
+            return Err(JxlError::OutputBufferError);
+        }
+        // 이전 프레임의 콜백은 더 이상 쓰이지 않으므로 여기서 해제
+        *slot = Some(ImageOutSlot {
+            state,
+            active: true,
+        });
+        Ok(())
+    }
+
+    /// Resumes a panic raised inside the image-out callback and notes when its frame is done.
+    pub(crate) fn finish_image_out(&self, status: JxlDecoderStatus) {
+        let mut slot = self.image_out.borrow_mut();
+        let Some(current) = slot.as_mut() else {
+            return;
+        };
+        if let Some(payload) = current.state.take_panic() {
+            current.active = false;
+            drop(slot);
+            panic::resume_unwind(payload);
+        }
+        if matches!(
+            status,
+            JxlDecoderStatus::FullImage | JxlDecoderStatus::Success | JxlDecoderStatus::Error
+        ) {
+            current.active = false;
+        }
+    }
+
+    /// Frees the image-out callback after libjxl forgot it on reset or rewind.
+    pub(crate) fn clear_image_out(&self) {
+        self.image_out.borrow_mut().take();
+    }
+}
+
+/// Checks that rows of `format` can be handed out as `T`; returns the channel count.
+fn check_format<T: JxlSample>(format: &JxlPixelFormat) -> Result<usize, JxlError> {
+    if !T::accepts(format.data_type) {
+        return Err(JxlError::InvalidInput(format!(
+            "{:?} samples cannot be read as {}",
+            format.data_type,
+            type_name::<T>()
+        )));
+    }
+    let foreign_endian = match format.endianness {
+        JxlEndianness::NativeEndian => false,
+        JxlEndianness::LittleEndian => cfg!(target_endian = "big"),
+        JxlEndianness::BigEndian => cfg!(target_endian = "little"),
+    };
+    if foreign_endian && mem::size_of::<T>() > 1 {
+        return Err(JxlError::InvalidInput(format!(
+            "{:?} samples cannot be read as native {}",
+            format.endianness,
+            type_name::<T>()
+        )));
+    }
+    match format.num_channels {
+        1..=4 => Ok(format.num_channels as usize),
+        n => Err(JxlError::InvalidInput(format!(
+            "Expected 1 to 4 channels, got {n}"
+        ))),
+    }
+}
+
+/// Views `len` samples at `pixels`, copying them into `scratch` if the pointer is misaligned.
+///
+/// # Safety
+///
+/// `pixels` must point to `len` readable samples of `T`.
+pub(crate) unsafe fn samples<T: JxlSample>(
+    pixels: *const c_void,
+    len: usize,
+    scratch: &mut Vec<T>,
+) -> &[T] {
+    let pixels = pixels as *const T;
+    if len == 0 {
+        return &[];
+    }
+    if pixels.align_offset(mem::align_of::<T>()) == 0 {
+        return slice::from_raw_parts(pixels, len);
+    }
+    scratch.clear();
+    scratch.extend((0..len).map(|i| ptr::read_unaligned(pixels.add(i))));
+    scratch
+}
+
+unsafe extern "C" fn row_callback<T, F>(
+    opaque: *mut c_void,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+) where
+    T: JxlSample,
+    F: FnMut(usize, usize, &[T]),
+{
+    let state = &*(opaque as *const RowCallback<T, F>);
+    let mut inner = state.inner.lock().unwrap_or_else(|e| e.into_inner());
+    if inner.panic.is_some() {
+        return;
+    }
+    let inner = &mut *inner;
+    let len = num_pixels * inner.num_channels;
+    // 패닉이 C 코드로 넘어가지 않도록 잡아 둠
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let row = samples(pixels, len, &mut inner.scratch);
+        (inner.callback)(x, y, row);
+    }));
+    if let Err(payload) = result {
+        inner.panic = Some(payload);
+    }
+}
+
+unsafe extern "C" fn init_threads<T, S, I, R>(
+    init_opaque: *mut c_void,
+    num_threads: usize,
+    num_pixels_per_thread: usize,
+) -> *mut c_void
+where
+    I: Fn(usize) -> S,
+{
+    // 디코더가 슬롯을 잡고 있는 동안 불리므로 참조 수를 늘려도 안전함
+    let callback = init_opaque as *const ThreadedCallback<T, S, I, R>;
+    Arc::increment_strong_count(callback);
+    let callback = Arc::from_raw(callback);
+    let threads = callback.guard(|| {
+        (0..num_threads)
+            .map(|_| {
+                Mutex::new(ThreadState {
+                    state: (callback.init)(num_pixels_per_thread),
+                    scratch: Vec::new(),
+                })
+            })
+            .collect()
+    });
+    match threads {
+        Some(threads) => Box::into_raw(Box::new(ThreadStates { callback, threads })) as *mut c_void,
+        // null 을 돌려주면 libjxl 이 디코딩을 중단함
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn run_thread<T, S, I, R>(
+    run_opaque: *mut c_void,
+    thread_id: usize,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+) where
+    T: JxlSample,
+    R: Fn(&mut S, usize, usize, &[T]),
+{
+    let states = &*(run_opaque as *const ThreadStates<T, S, I, R>);
+    let callback = &states.callback;
+    let Some(thread) = states.threads.get(thread_id) else {
+        return;
+    };
+    // 같은 thread_id 는 동시에 불리지 않으므로 잠금 경합은 없음
+    let mut thread = thread.lock().unwrap_or_else(|e| e.into_inner());
+    let thread = &mut *thread;
+    let len = num_pixels * callback.num_channels;
+    callback.guard(|| {
+        let row = samples(pixels, len, &mut thread.scratch);
+        (callback.run)(&mut thread.state, x, y, row);
+    });
+}
+
+unsafe extern "C" fn destroy_threads<T, S, I, R>(run_opaque: *mut c_void) {
+    let states = Box::from_raw(run_opaque as *mut ThreadStates<T, S, I, R>);
+    let callback = states.callback.clone();
+    // 상태의 Drop 이 패닉해도 잡히도록 클로저 안에서 해제
+    callback.catch(|| drop(states));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(num_channels: u32, data_type: JxlDataType) -> JxlPixelFormat {
+        JxlPixelFormat {
+            num_channels,
+            data_type,
+            endianness: JxlEndianness::NativeEndian,
+            align: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_format() {
+        assert_eq!(
+            check_format::<u8>(&format(3, JxlDataType::Uint8)).unwrap(),
+            3
+        );
+        assert_eq!(
+            check_format::<u16>(&format(4, JxlDataType::Float16)).unwrap(),
+            4
+        );
+        assert!(check_format::<f32>(&format(3, JxlDataType::Uint8)).is_err());
+        assert!(check_format::<u8>(&format(0, JxlDataType::Uint8)).is_err());
+
+        let mut foreign = format(1, JxlDataType::Uint16);
+        foreign.endianness = if cfg!(target_endian = "little") {
+            JxlEndianness::BigEndian
+        } else {
+            JxlEndianness::LittleEndian
+        };
+        assert!(check_format::<u16>(&foreign).is_err());
+        foreign.data_type = JxlDataType::Uint8;
+        assert!(check_format::<u8>(&foreign).is_ok());
+    }
+
+    unsafe fn call<T: JxlSample, F: FnMut(usize, usize, &[T])>(
+        state: &RowCallback<T, F>,
+        x: usize,
+        y: usize,
+        num_pixels: usize,
+        pixels: *const u8,
+    ) {
+        let opaque = state as *const RowCallback<T, F> as *mut c_void;
+        row_callback::<T, F>(opaque, x, y, num_pixels, pixels as *const c_void);
+    }
+
+    #[test]
+    fn test_row_callback_types_and_catches_panics() {
+        let rows = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let seen = rows.clone();
+        let state = RowCallback {
+            inner: Mutex::new(RowInner {
+                callback: move |x: usize, y: usize, row: &[u16]| {
+                    assert!(y < 2, "bad row");
+                    seen.lock().unwrap().push((x, y, row.to_vec()));
+                },
+                num_channels: 2,
+                scratch: Vec::<u16>::new(),
+                panic: None,
+            }),
+        };
+        let pixels: Vec<u16> = vec![1, 2, 3, 4, 5];
+
+        unsafe {
+            let bytes = pixels.as_ptr() as *const u8;
+            call(&state, 3, 1, 2, bytes);
+            // 정렬되지 않은 포인터도 복사해서 넘김
+            call(&state, 0, 0, 1, bytes.add(1));
+            call(&state, 0, 5, 1, bytes);
+            call(&state, 0, 0, 1, bytes);
+        }
+
+        let rows = rows.lock().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], (3, 1, vec![1, 2, 3, 4]));
+        assert_eq!(rows[1].2.len(), 2);
+        assert!(state.take_panic().is_some());
+    }
+
+    unsafe fn call_init<T, S, I: Fn(usize) -> S, R>(
+        callback: &Arc<ThreadedCallback<T, S, I, R>>,
+        num_threads: usize,
+        num_pixels_per_thread: usize,
+    ) -> *mut c_void {
+        let opaque = Arc::as_ptr(callback) as *mut c_void;
+        init_threads::<T, S, I, R>(opaque, num_threads, num_pixels_per_thread)
+    }
+
+    unsafe fn call_run<T: JxlSample, S, I, R: Fn(&mut S, usize, usize, &[T])>(
+        _callback: &Arc<ThreadedCallback<T, S, I, R>>,
+        states: *mut c_void,
+        thread_id: usize,
+        pixels: *const T,
+    ) {
+        run_thread::<T, S, I, R>(states, thread_id, 0, 0, 1, pixels as *const c_void);
+    }
+
+    unsafe fn call_destroy<T, S, I, R>(
+        _callback: &Arc<ThreadedCallback<T, S, I, R>>,
+        states: *mut c_void,
+    ) {
+        destroy_threads::<T, S, I, R>(states);
+    }
+
+    #[test]
+    fn test_threaded_callback_states() {
+        struct Staging {
+            rows: std::sync::Arc<Mutex<Vec<(usize, usize, f32)>>>,
+            buffer: Vec<f32>,
+        }
+        impl Drop for Staging {
+            fn drop(&mut self) {
+                let mut rows = self.rows.lock().unwrap();
+                rows.extend(self.buffer.chunks(3).map(|p| (0, 0, p[0])));
+            }
+        }
+
+        let flushed = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let init_rows = flushed.clone();
+        let callback = Arc::new(ThreadedCallback {
+            init: move |num_pixels: usize| Staging {
+                rows: init_rows.clone(),
+                buffer: Vec::with_capacity(num_pixels * 3),
+            },
+            run: |staging: &mut Staging, _x: usize, _y: usize, row: &[f32]| {
+                staging.buffer.extend_from_slice(row);
+            },
+            num_channels: 3,
+            panicked: AtomicBool::new(false),
+            panic: Mutex::new(None),
+            _marker: PhantomData::<fn() -> (f32, Staging)>,
+        });
+        let pixels: Vec<f32> = vec![1.0, 0.0, 0.0, 2.0, 0.0, 0.0];
+
+        unsafe {
+            let states = call_init(&callback, 2, 1);
+            assert!(!states.is_null());
+            assert_eq!(Arc::strong_count(&callback), 2);
+            call_run(&callback, states, 1, pixels.as_ptr().add(3));
+            call_run(&callback, states, 0, pixels.as_ptr());
+            call_run(&callback, states, 7, pixels.as_ptr());
+            assert!(flushed.lock().unwrap().is_empty());
+            call_destroy(&callback, states);
+        }
+        assert_eq!(Arc::strong_count(&callback), 1);
+
+        let mut values: Vec<f32> = flushed.lock().unwrap().iter().map(|r| r.2).collect();
+        values.sort_by(f32::total_cmp);
+        assert_eq!(values, [1.0, 2.0]);
+        assert!(callback.take_panic().is_none());
+    }
+}
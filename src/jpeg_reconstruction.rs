@@ -0,0 +1,113 @@
+use crate::jpg_to_lossless_jxl_with_library;
+use crate::JxlDecoder;
+use crate::JxlDecoderStatus;
+use crate::JxlError;
+use crate::JxlLibrary;
+
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Initial size of the JPEG reconstruction buffer; doubled whenever libjxl runs out.
+const JPEG_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Effort used to re-encode the JPEG in `verify_jpeg_reconstruction`. It doesn't change the
+/// reconstruction data, only how long encoding takes.
+const VERIFY_EFFORT: i64 = 3;
+
+/// A JPEG recovered by `verify_jpeg_reconstruction` and proven to round-trip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JpegVerification {
+    /// The reconstructed JPEG.
+    pub jpeg: Vec<u8>,
+    /// SHA-256 of `jpeg`.
+    pub sha256: [u8; 32],
+}
+
+impl JxlDecoder {
+    /// Recovers the original JPEG from a file transcoded with `store_jpeg_metadata`.
+    ///
+    /// Returns `None` when the file has no JPEG reconstruction data (`jbrd` box). The output
+    /// buffer grows until the JPEG fits. Resets the decoder first; an attached parallel runner
+    /// is kept.
+    /// - JxlDecoderSetJPEGBuffer() / JxlDecoderReleaseJPEGBuffer()
+    pub fn reconstruct_jpeg(&self, data: &[u8]) -> Result<Option<Vec<u8>>, JxlError> {
+        self.reset()?;
+        self.subscribe_events(JxlDecoderStatus::JpegReconstruction | JxlDecoderStatus::FullImage)?;
+        self.set_input(data)?;
+        self.close_input();
+
+        let mut jpeg = Vec::new();
+        let result = loop {
+            let status = match self.process_input() {
+                Ok(status) => status,
+                Err(e) => break Err(e),
+            };
+            match status {
+                JxlDecoderStatus::JpegReconstruction => {
+                    jpeg.resize(JPEG_BUFFER_SIZE.max(data.len() * 2), 0);
+                    if let Err(e) = self.set_jpeg_buffer(&mut jpeg) {
+                        break Err(e);
+                    }
+                }
+                JxlDecoderStatus::JpegNeedMoreOutput => {
+                    let written = jpeg.len() - self.release_jpeg_buffer();
+                    jpeg.resize(jpeg.len() * 2, 0);
+                    if let Err(e) = self.set_jpeg_buffer(&mut jpeg[written..]) {
+                        break Err(e);
+                    }
+                }
+                JxlDecoderStatus::FullImage if !jpeg.is_empty() => {
+                    let written = jpeg.len() - self.release_jpeg_buffer();
+                    jpeg.truncate(written);
+                    break Ok(Some(jpeg));
+                }
+                // 재구성 데이터가 없으면 픽셀 버퍼를 요구함
+                JxlDecoderStatus::NeedImageOutBuffer | JxlDecoderStatus::Success => break Ok(None),
+                JxlDecoderStatus::NeedMoreInput => break Err(JxlError::NotEnoughInput),
+                _ => {}
+            }
+        };
+        // 버퍼가 해제되기 전에 디코더가 더 이상 가리키지 않게 함
+        self.reset().and(result)
+    }
+}
+
+/// Recovers the original JPEG with a fresh decoder on `lib`; see `JxlDecoder::reconstruct_jpeg`.
+pub fn reconstruct_jpeg(lib: &Arc<JxlLibrary>, data: &[u8]) -> Result<Option<Vec<u8>>, JxlError> {
+    JxlDecoder::with_library(lib.clone())?.reconstruct_jpeg(data)
+}
+
+/// Recovers the JPEG and proves it round-trips before the original is thrown away.
+///
+/// The reconstructed JPEG is re-encoded on the same `lib` with
+/// `jpg_to_lossless_jxl_with_library` and reconstructed again; both must have the same
+/// SHA-256. With `expected_sha256` (the hash of the original JPEG), the reconstruction must
+/// also match it. Returns `None` without reconstruction data and
+/// `JpegVerificationFailed` on any mismatch.
+pub fn verify_jpeg_reconstruction(
+    lib: &Arc<JxlLibrary>,
+    data: &[u8],
+    expected_sha256: Option<&[u8; 32]>,
+) -> Result<Option<JpegVerification>, JxlError> {
+    let decoder = JxlDecoder::with_library(lib.clone())?;
+    let Some(jpeg) = decoder.reconstruct_jpeg(data)? else {
+        return Ok(None);
+    };
+    let sha256: [u8; 32] = Sha256::digest(&jpeg).into();
+    if expected_sha256.is_some_and(|expected| *expected != sha256) {
+        return Err(JxlError::JpegVerificationFailed(
+            "Reconstructed JPEG does not match the expected hash".to_string(),
+        ));
+    }
+
+    let reencoded = jpg_to_lossless_jxl_with_library(lib, &jpeg, VERIFY_EFFORT)?;
+    let round_trip = decoder.reconstruct_jpeg(&reencoded)?.ok_or_else(|| {
+        JxlError::JpegVerificationFailed("Re-encoded file has no reconstruction data".to_string())
+    })?;
+    if <[u8; 32]>::from(Sha256::digest(&round_trip)) != sha256 {
+        return Err(JxlError::JpegVerificationFailed(
+            "JPEG changed after re-encoding".to_string(),
+        ));
+    }
+    Ok(Some(JpegVerification { jpeg, sha256 }))
+}
@@ -1,24 +1,65 @@
+mod animation_encoder;
+mod boxes;
+mod chunked_frame;
 mod common;
+mod container;
+mod container_editor;
+mod decode;
 mod decoder;
 mod decoder_enum;
 mod decoder_struct;
+mod discovery;
+mod encode;
 mod encoder;
 mod encoder_enum;
+mod encoder_stats;
 mod encoder_struct;
 mod error;
+mod frame_settings;
+mod frames;
+mod image_codec;
+mod image_out;
+mod jpeg_reconstruction;
+mod library;
+mod rust_runner;
+mod stream_decoder;
+mod stream_encoder;
+mod thread_runner;
+mod transcode;
 
 mod color;
 mod metadata;
+mod metadata_boxes;
 mod metadata_func;
 
+pub use animation_encoder::*;
+pub use boxes::*;
+pub use chunked_frame::*;
 pub use common::*;
+pub use container::*;
+pub use container_editor::*;
+pub use decode::*;
 pub use decoder::*;
 pub use decoder_enum::*;
 pub use decoder_struct::*;
+pub use discovery::*;
+pub use encode::*;
 pub use encoder::*;
 pub use encoder_enum::*;
+pub use encoder_stats::*;
 pub use encoder_struct::*;
 pub use error::*;
+pub use frame_settings::*;
+pub use frames::*;
+pub use image_codec::*;
+pub use image_out::*;
+pub use jpeg_reconstruction::*;
+pub use library::*;
+pub use rust_runner::*;
+pub use stream_decoder::*;
+pub use stream_encoder::*;
+pub use thread_runner::*;
+pub use transcode::*;
 
 pub use color::*;
 pub use metadata::*;
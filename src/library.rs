@@ -0,0 +1,401 @@
+use crate::common::*;
+use crate::decoder_enum::*;
+use crate::decoder_struct::*;
+use crate::encoder_enum::*;
+use crate::encoder_struct::*;
+use crate::metadata::*;
+use crate::JxlCmsInterface;
+use crate::JxlColorEncoding;
+use crate::JxlError;
+use crate::JxlParallelRunFunction;
+use crate::JxlParallelRunInit;
+use crate::JxlParallelRunner;
+
+use libloading::{Library, Symbol};
+use std::ffi::{c_char, c_float, c_int, c_void, OsStr};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Declares a function table resolved from a libjxl component.
+///
+/// `required` symbols must be exported by every supported libjxl (0.9 and newer) and make
+/// loading fail when missing. `optional` symbols were added in later releases; they are
+/// resolved when present and reported as `JxlError::SymbolNotFound` when called otherwise.
+macro_rules! jxl_functions {
+    (
+        struct $name:ident;
+        required {
+            $($req:ident: $req_sym:literal => fn($($req_arg:ty),* $(,)?) $(-> $req_ret:ty)?;)*
+        }
+        optional {
+            $($opt:ident: $opt_sym:literal => fn($($opt_arg:ty),* $(,)?) $(-> $opt_ret:ty)?;)*
+        }
+    ) => {
+        /// Typed function pointers resolved from libjxl.
+        pub(crate) struct $name {
+            $(pub(crate) $req: unsafe extern "C" fn($($req_arg),*) $(-> $req_ret)?,)*
+            $($opt: Option<unsafe extern "C" fn($($opt_arg),*) $(-> $opt_ret)?>,)*
+        }
+
+        impl $name {
+            /// Resolves every symbol from `lib`.
+            ///
+            /// # Safety
+            ///
+            /// `lib` must be a libjxl build whose exported signatures match the table.
+            unsafe fn resolve(lib: &Library) -> Result<Self, JxlError> {
+                Ok($name {
+                    $($req: *lib
+                        .get::<unsafe extern "C" fn($($req_arg),*) $(-> $req_ret)?>($req_sym.as_bytes())
+                        .map_err(JxlError::SymbolLoadFailed)?,)*
+                    $($opt: lib
+                        .get::<unsafe extern "C" fn($($opt_arg),*) $(-> $opt_ret)?>($opt_sym.as_bytes())
+                        .ok()
+                        .map(|symbol: Symbol<_>| *symbol),)*
+                })
+            }
+
+            $(
+                pub(crate) fn $opt(
+                    &self,
+                ) -> Result<unsafe extern "C" fn($($opt_arg),*) $(-> $opt_ret)?, JxlError> {
+                    self.$opt.ok_or(JxlError::SymbolNotFound($opt_sym))
+                }
+            )*
+        }
+    };
+}
+
+jxl_functions! {
+    struct JxlFunctions;
+    required {
+        // decode.h
+        decoder_version: "JxlDecoderVersion" => fn() -> u32;
+        signature_check: "JxlSignatureCheck" => fn(*const u8, usize) -> c_int;
+        decoder_create: "JxlDecoderCreate" => fn(*const JxlMemoryManager) -> *mut c_void;
+        decoder_reset: "JxlDecoderReset" => fn(*mut c_void);
+        decoder_destroy: "JxlDecoderDestroy" => fn(*mut c_void);
+        decoder_rewind: "JxlDecoderRewind" => fn(*mut c_void);
+        decoder_skip_frames: "JxlDecoderSkipFrames" => fn(*mut c_void, usize);
+        decoder_skip_current_frame: "JxlDecoderSkipCurrentFrame" => fn(*mut c_void) -> c_int;
+        decoder_set_parallel_runner: "JxlDecoderSetParallelRunner"
+            => fn(*mut c_void, Option<JxlParallelRunner>, *mut c_void) -> c_int;
+        decoder_size_hint_basic_info: "JxlDecoderSizeHintBasicInfo" => fn(*const c_void) -> usize;
+        decoder_subscribe_events: "JxlDecoderSubscribeEvents" => fn(*mut c_void, c_int) -> c_int;
+        decoder_set_keep_orientation: "JxlDecoderSetKeepOrientation"
+            => fn(*mut c_void, c_int) -> c_int;
+        decoder_set_unpremultiply_alpha: "JxlDecoderSetUnpremultiplyAlpha"
+            => fn(*mut c_void, c_int) -> c_int;
+        decoder_set_render_spotcolors: "JxlDecoderSetRenderSpotcolors"
+            => fn(*mut c_void, c_int) -> c_int;
+        decoder_set_coalescing: "JxlDecoderSetCoalescing" => fn(*mut c_void, c_int) -> c_int;
+        decoder_process_input: "JxlDecoderProcessInput" => fn(*mut c_void) -> c_int;
+        decoder_set_input: "JxlDecoderSetInput" => fn(*mut c_void, *const u8, usize) -> c_int;
+        decoder_release_input: "JxlDecoderReleaseInput" => fn(*mut c_void) -> usize;
+        decoder_close_input: "JxlDecoderCloseInput" => fn(*mut c_void);
+        decoder_get_basic_info: "JxlDecoderGetBasicInfo"
+            => fn(*const c_void, *mut JxlBasicInfo) -> c_int;
+        decoder_get_extra_channel_info: "JxlDecoderGetExtraChannelInfo"
+            => fn(*const c_void, usize, *mut JxlExtraChannelInfo) -> c_int;
+        decoder_get_extra_channel_name: "JxlDecoderGetExtraChannelName"
+            => fn(*const c_void, usize, *mut c_char, usize) -> c_int;
+        decoder_get_color_as_encoded_profile: "JxlDecoderGetColorAsEncodedProfile"
+            => fn(*const c_void, JxlColorProfileTarget, *mut JxlColorEncoding) -> c_int;
+        decoder_get_icc_profile_size: "JxlDecoderGetICCProfileSize"
+            => fn(*const c_void, JxlColorProfileTarget, *mut usize) -> JxlDecoderStatus;
+        decoder_get_color_as_icc_profile: "JxlDecoderGetColorAsICCProfile"
+            => fn(*const c_void, JxlColorProfileTarget, *mut u8, usize) -> JxlDecoderStatus;
+        decoder_set_preferred_color_profile: "JxlDecoderSetPreferredColorProfile"
+            => fn(*mut c_void, *const JxlColorEncoding) -> c_int;
+        decoder_set_desired_intensity_target: "JxlDecoderSetDesiredIntensityTarget"
+            => fn(*mut c_void, c_float) -> c_int;
+        decoder_set_output_color_profile: "JxlDecoderSetOutputColorProfile"
+            => fn(*mut c_void, *const JxlColorEncoding, *const u8, usize) -> c_int;
+        decoder_set_cms: "JxlDecoderSetCms" => fn(*mut c_void, JxlCmsInterface) -> c_int;
+        decoder_preview_out_buffer_size: "JxlDecoderPreviewOutBufferSize"
+            => fn(*const c_void, *const JxlPixelFormat, *mut usize) -> c_int;
+        decoder_set_preview_out_buffer: "JxlDecoderSetPreviewOutBuffer"
+            => fn(*mut c_void, *const JxlPixelFormat, *mut c_void, usize) -> c_int;
+        decoder_get_frame_header: "JxlDecoderGetFrameHeader"
+            => fn(*const c_void, *mut JxlFrameHeader) -> c_int;
+        decoder_get_frame_name: "JxlDecoderGetFrameName"
+            => fn(*const c_void, *mut c_char, usize) -> c_int;
+        decoder_get_extra_channel_blend_info: "JxlDecoderGetExtraChannelBlendInfo"
+            => fn(*const c_void, usize, *mut JxlBlendInfo) -> c_int;
+        decoder_image_out_buffer_size: "JxlDecoderImageOutBufferSize"
+            => fn(*const c_void, *const JxlPixelFormat, *mut usize) -> c_int;
+        decoder_set_image_out_buffer: "JxlDecoderSetImageOutBuffer"
+            => fn(*mut c_void, *const JxlPixelFormat, *mut c_void, usize) -> c_int;
+        decoder_set_image_out_callback: "JxlDecoderSetImageOutCallback"
+            => fn(*mut c_void, *const JxlPixelFormat, JxlImageOutCallback, *mut c_void) -> c_int;
+        decoder_set_multithreaded_image_out_callback: "JxlDecoderSetMultithreadedImageOutCallback"
+            => fn(
+                *mut c_void,
+                *const JxlPixelFormat,
+                Option<JxlImageOutInitCallback>,
+                Option<JxlImageOutRunCallback>,
+                Option<JxlImageOutDestroyCallback>,
+                *mut c_void,
+            ) -> c_int;
+        decoder_extra_channel_buffer_size: "JxlDecoderExtraChannelBufferSize"
+            => fn(*const c_void, *const JxlPixelFormat, *mut usize, u32) -> c_int;
+        decoder_set_extra_channel_buffer: "JxlDecoderSetExtraChannelBuffer"
+            => fn(*mut c_void, *const JxlPixelFormat, *mut c_void, usize, u32) -> c_int;
+        decoder_set_jpeg_buffer: "JxlDecoderSetJPEGBuffer" => fn(*mut c_void, *mut u8, usize) -> c_int;
+        decoder_release_jpeg_buffer: "JxlDecoderReleaseJPEGBuffer" => fn(*mut c_void) -> usize;
+        decoder_set_box_buffer: "JxlDecoderSetBoxBuffer" => fn(*mut c_void, *mut u8, usize) -> c_int;
+        decoder_release_box_buffer: "JxlDecoderReleaseBoxBuffer" => fn(*mut c_void) -> usize;
+        decoder_set_decompress_boxes: "JxlDecoderSetDecompressBoxes"
+            => fn(*mut c_void, c_int) -> c_int;
+        decoder_get_box_type: "JxlDecoderGetBoxType"
+            => fn(*const c_void, *mut [u8; 4], JxlBool) -> c_int;
+        decoder_get_box_size_raw: "JxlDecoderGetBoxSizeRaw" => fn(*const c_void, *mut u64) -> c_int;
+        decoder_set_progressive_detail: "JxlDecoderSetProgressiveDetail"
+            => fn(*mut c_void, JxlProgressiveDetail) -> c_int;
+        decoder_get_intended_downsampling_ratio: "JxlDecoderGetIntendedDownsamplingRatio"
+            => fn(*mut c_void) -> usize;
+        decoder_flush_image: "JxlDecoderFlushImage" => fn(*mut c_void) -> c_int;
+        decoder_set_image_out_bit_depth: "JxlDecoderSetImageOutBitDepth"
+            => fn(*mut c_void, *const JxlBitDepth) -> c_int;
+
+        // encode.h
+        encoder_version: "JxlEncoderVersion" => fn() -> u32;
+        encoder_create: "JxlEncoderCreate" => fn(*const JxlMemoryManager) -> *mut c_void;
+        encoder_reset: "JxlEncoderReset" => fn(*mut c_void);
+        encoder_destroy: "JxlEncoderDestroy" => fn(*mut c_void);
+        encoder_set_cms: "JxlEncoderSetCms" => fn(*mut c_void, JxlCmsInterface);
+        encoder_set_parallel_runner: "JxlEncoderSetParallelRunner"
+            => fn(*mut c_void, Option<JxlParallelRunner>, *mut c_void) -> JxlEncoderStatus;
+        encoder_get_error: "JxlEncoderGetError" => fn(*mut c_void) -> JxlEncoderError;
+        encoder_process_output: "JxlEncoderProcessOutput"
+            => fn(*mut c_void, *mut *mut u8, *mut usize) -> JxlEncoderStatus;
+        encoder_set_frame_header: "JxlEncoderSetFrameHeader"
+            => fn(*mut c_void, *const JxlFrameHeader) -> JxlEncoderStatus;
+        encoder_set_extra_channel_blend_info: "JxlEncoderSetExtraChannelBlendInfo"
+            => fn(*mut c_void, usize, *const JxlBlendInfo) -> JxlEncoderStatus;
+        encoder_set_frame_name: "JxlEncoderSetFrameName"
+            => fn(*mut c_void, *const c_char) -> JxlEncoderStatus;
+        encoder_set_frame_bit_depth: "JxlEncoderSetFrameBitDepth"
+            => fn(*mut c_void, *const JxlBitDepth) -> JxlEncoderStatus;
+        encoder_add_jpeg_frame: "JxlEncoderAddJPEGFrame"
+            => fn(*const c_void, *const u8, usize) -> JxlEncoderStatus;
+        encoder_add_image_frame: "JxlEncoderAddImageFrame"
+            => fn(*mut c_void, *const JxlPixelFormat, *const u8, usize) -> JxlEncoderStatus;
+        encoder_set_extra_channel_buffer: "JxlEncoderSetExtraChannelBuffer"
+            => fn(*mut c_void, *const JxlPixelFormat, *const u8, usize, u32) -> JxlEncoderStatus;
+        encoder_add_box: "JxlEncoderAddBox"
+            => fn(*mut c_void, *const JxlBoxType, *const u8, usize, JxlBool) -> JxlEncoderStatus;
+        encoder_use_boxes: "JxlEncoderUseBoxes" => fn(*mut c_void) -> JxlEncoderStatus;
+        encoder_close_boxes: "JxlEncoderCloseBoxes" => fn(*mut c_void);
+        encoder_close_frames: "JxlEncoderCloseFrames" => fn(*mut c_void);
+        encoder_close_input: "JxlEncoderCloseInput" => fn(*mut c_void);
+        encoder_set_color_encoding: "JxlEncoderSetColorEncoding"
+            => fn(*mut c_void, *const JxlColorEncoding) -> JxlEncoderStatus;
+        encoder_set_icc_profile: "JxlEncoderSetICCProfile"
+            => fn(*mut c_void, *const u8, usize) -> JxlEncoderStatus;
+        encoder_init_basic_info: "JxlEncoderInitBasicInfo" => fn(*mut JxlBasicInfo);
+        encoder_init_frame_header: "JxlEncoderInitFrameHeader" => fn(*mut JxlFrameHeader);
+        encoder_init_blend_info: "JxlEncoderInitBlendInfo" => fn(*mut JxlBlendInfo);
+        encoder_set_basic_info: "JxlEncoderSetBasicInfo"
+            => fn(*mut c_void, *const JxlBasicInfo) -> JxlEncoderStatus;
+        encoder_set_upsampling_mode: "JxlEncoderSetUpsamplingMode"
+            => fn(*mut c_void, i64, i64) -> JxlEncoderStatus;
+        encoder_init_extra_channel_info: "JxlEncoderInitExtraChannelInfo"
+            => fn(JxlExtraChannelType, *mut JxlExtraChannelInfo);
+        encoder_set_extra_channel_info: "JxlEncoderSetExtraChannelInfo"
+            => fn(*mut c_void, usize, *const JxlExtraChannelInfo) -> JxlEncoderStatus;
+        encoder_set_extra_channel_name: "JxlEncoderSetExtraChannelName"
+            => fn(*mut c_void, usize, *const c_char, usize) -> JxlEncoderStatus;
+        encoder_use_container: "JxlEncoderUseContainer"
+            => fn(*mut c_void, JxlBool) -> JxlEncoderStatus;
+        encoder_store_jpeg_metadata: "JxlEncoderStoreJPEGMetadata"
+            => fn(*mut c_void, JxlBool) -> JxlEncoderStatus;
+        encoder_set_codestream_level: "JxlEncoderSetCodestreamLevel"
+            => fn(*mut c_void, c_int) -> JxlEncoderStatus;
+        encoder_get_required_codestream_level: "JxlEncoderGetRequiredCodestreamLevel"
+            => fn(*const c_void) -> c_int;
+        color_encoding_set_to_srgb: "JxlColorEncodingSetToSRGB"
+            => fn(*mut JxlColorEncoding, JxlBool);
+        color_encoding_set_to_linear_srgb: "JxlColorEncodingSetToLinearSRGB"
+            => fn(*mut JxlColorEncoding, JxlBool);
+        encoder_allow_expert_options: "JxlEncoderAllowExpertOptions" => fn(*mut c_void);
+        encoder_frame_settings_create: "JxlEncoderFrameSettingsCreate"
+            => fn(*mut c_void, *const c_void) -> *mut c_void;
+        encoder_frame_settings_set_option: "JxlEncoderFrameSettingsSetOption"
+            => fn(*mut c_void, JxlEncoderFrameSettingId, i64) -> JxlEncoderStatus;
+        encoder_frame_settings_set_float_option: "JxlEncoderFrameSettingsSetFloatOption"
+            => fn(*mut c_void, JxlEncoderFrameSettingId, f32) -> JxlEncoderStatus;
+        encoder_set_frame_lossless: "JxlEncoderSetFrameLossless"
+            => fn(*mut c_void, JxlBool) -> JxlEncoderStatus;
+        encoder_set_frame_distance: "JxlEncoderSetFrameDistance"
+            => fn(*mut c_void, f32) -> JxlEncoderStatus;
+        encoder_set_extra_channel_distance: "JxlEncoderSetExtraChannelDistance"
+            => fn(*mut c_void, usize, f32) -> JxlEncoderStatus;
+    }
+    optional {
+        // libjxl 0.10
+        encoder_set_output_processor: "JxlEncoderSetOutputProcessor"
+            => fn(*mut c_void, JxlEncoderOutputProcessor) -> JxlEncoderStatus;
+        encoder_flush_input: "JxlEncoderFlushInput" => fn(*mut c_void) -> JxlEncoderStatus;
+        encoder_add_chunked_frame: "JxlEncoderAddChunkedFrame"
+            => fn(*mut c_void, JxlBool, JxlChunkedFrameInputSource) -> JxlEncoderStatus;
+        encoder_distance_from_quality: "JxlEncoderDistanceFromQuality" => fn(f32) -> f32;
+
+        // stats.h, missing from some builds
+        encoder_stats_create: "JxlEncoderStatsCreate" => fn() -> *mut c_void;
+        encoder_stats_destroy: "JxlEncoderStatsDestroy" => fn(*mut c_void);
+        encoder_stats_get: "JxlEncoderStatsGet"
+            => fn(*const c_void, JxlEncoderStatsKey) -> usize;
+        encoder_stats_merge: "JxlEncoderStatsMerge" => fn(*mut c_void, *const c_void);
+        encoder_collect_stats: "JxlEncoderCollectStats"
+            => fn(*mut c_void, *mut c_void) -> JxlEncoderStatus;
+
+        // libjxl 0.11
+        decoder_get_box_size_contents: "JxlDecoderGetBoxSizeContents"
+            => fn(*const c_void, *mut u64) -> c_int;
+        icc_profile_encode: "JxlICCProfileEncode"
+            => fn(*const JxlMemoryManager, *const u8, usize, *mut *mut u8, *mut usize) -> JxlBool;
+        icc_profile_decode: "JxlICCProfileDecode"
+            => fn(*const JxlMemoryManager, *const u8, usize, *mut *mut u8, *mut usize) -> JxlBool;
+        gain_map_get_bundle_size: "JxlGainMapGetBundleSize"
+            => fn(*const JxlGainMapBundle, *mut usize) -> JxlBool;
+        gain_map_write_bundle: "JxlGainMapWriteBundle"
+            => fn(*const JxlGainMapBundle, *mut u8, usize, *mut usize) -> JxlBool;
+        gain_map_read_bundle: "JxlGainMapReadBundle"
+            => fn(*mut JxlGainMapBundle, *const u8, usize, *mut usize) -> JxlBool;
+    }
+}
+
+/// A loaded libjxl with every entry point resolved once.
+///
+/// Load it once and hand clones of the `Arc` to any number of `JxlDecoder`, `JxlEncoder`
+/// and `JxlIccGainMap` instances; the library stays loaded until the last one is dropped.
+pub struct JxlLibrary {
+    pub(crate) fns: JxlFunctions,
+    path: PathBuf,
+    // Declared last so the function table never outlives the mapping it points into.
+    _lib: Library,
+}
+
+impl JxlLibrary {
+    /// Loads libjxl from `path` and resolves its function table.
+    pub fn load<P: AsRef<OsStr>>(path: P) -> Result<Arc<Self>, JxlError> {
+        let lib = unsafe { Library::new(path.as_ref()) }.map_err(JxlError::LibraryLoadFailed)?;
+        let fns = unsafe { JxlFunctions::resolve(&lib) }?;
+
+        Ok(Arc::new(JxlLibrary {
+            fns,
+            path: PathBuf::from(path.as_ref()),
+            _lib: lib,
+        }))
+    }
+
+    /// Returns the path or library name this instance was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the libjxl version as (major, minor, patch). - JxlDecoderVersion()
+    pub fn version(&self) -> (u32, u32, u32) {
+        let version = unsafe { (self.fns.decoder_version)() };
+
+        (
+            version / 1_000_000,
+            (version % 1_000_000) / 1_000,
+            version % 1_000,
+        )
+    }
+}
+
+impl fmt::Debug for JxlLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JxlLibrary")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+jxl_functions! {
+    struct JxlThreadsFunctions;
+    required {
+        // thread_parallel_runner.h
+        thread_parallel_runner: "JxlThreadParallelRunner" => fn(
+            *mut c_void,
+            *mut c_void,
+            JxlParallelRunInit,
+            JxlParallelRunFunction,
+            u32,
+            u32,
+        ) -> c_int;
+        thread_parallel_runner_create: "JxlThreadParallelRunnerCreate"
+            => fn(*const JxlMemoryManager, usize) -> *mut c_void;
+        thread_parallel_runner_destroy: "JxlThreadParallelRunnerDestroy" => fn(*mut c_void);
+        thread_parallel_runner_default_num_worker_threads:
+            "JxlThreadParallelRunnerDefaultNumWorkerThreads" => fn() -> usize;
+        // resizable_parallel_runner.h
+        resizable_parallel_runner: "JxlResizableParallelRunner" => fn(
+            *mut c_void,
+            *mut c_void,
+            JxlParallelRunInit,
+            JxlParallelRunFunction,
+            u32,
+            u32,
+        ) -> c_int;
+        resizable_parallel_runner_create: "JxlResizableParallelRunnerCreate"
+            => fn(*const JxlMemoryManager) -> *mut c_void;
+        resizable_parallel_runner_set_threads: "JxlResizableParallelRunnerSetThreads"
+            => fn(*mut c_void, usize);
+        resizable_parallel_runner_suggest_threads: "JxlResizableParallelRunnerSuggestThreads"
+            => fn(u64, u64) -> u32;
+        resizable_parallel_runner_destroy: "JxlResizableParallelRunnerDestroy" => fn(*mut c_void);
+    }
+    optional {}
+}
+
+/// A loaded jxl_threads with every entry point resolved once.
+///
+/// Shared by `JxlThreadRunner` and `JxlResizableRunner` the same way `JxlLibrary` is shared
+/// by codecs.
+pub struct JxlThreadsLibrary {
+    pub(crate) fns: JxlThreadsFunctions,
+    path: PathBuf,
+    _lib: Library,
+}
+
+impl JxlThreadsLibrary {
+    /// Loads jxl_threads from `path` and resolves its function table.
+    pub fn load<P: AsRef<OsStr>>(path: P) -> Result<Arc<Self>, JxlError> {
+        let lib = unsafe { Library::new(path.as_ref()) }.map_err(JxlError::LibraryLoadFailed)?;
+        let fns = unsafe { JxlThreadsFunctions::resolve(&lib) }?;
+
+        Ok(Arc::new(JxlThreadsLibrary {
+            fns,
+            path: PathBuf::from(path.as_ref()),
+            _lib: lib,
+        }))
+    }
+
+    /// Returns the path or library name this instance was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the worker thread count libjxl would pick by default. - JxlThreadParallelRunnerDefaultNumWorkerThreads()
+    pub fn default_num_worker_threads(&self) -> usize {
+        unsafe { (self.fns.thread_parallel_runner_default_num_worker_threads)() }
+    }
+
+    /// Suggests a thread count for an image of the given size. - JxlResizableParallelRunnerSuggestThreads()
+    pub fn suggest_threads(&self, xsize: u64, ysize: u64) -> u32 {
+        unsafe { (self.fns.resizable_parallel_runner_suggest_threads)(xsize, ysize) }
+    }
+}
+
+impl fmt::Debug for JxlThreadsLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JxlThreadsLibrary")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
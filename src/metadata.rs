@@ -3,9 +3,10 @@ use crate::{JxlBool, JxlColorEncoding};
 /// Image orientation metadata. Values 1..8 match the EXIF definitions.
 /// The name indicates the operation to perform to transform from the encoded image to the display image.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum JxlOrientation {
     ///JXL_ORIENT_IDENTITY
+    #[default]
     Identity = 1,
     ///JXL_ORIENT_FLIP_HORIZONTAL
     FlipHorizontal = 2,
@@ -23,17 +24,12 @@ pub enum JxlOrientation {
     Rotate90CCW = 8,
 }
 
-impl Default for JxlOrientation {
-    fn default() -> Self {
-        JxlOrientation::Identity
-    }
-}
-
 /// Given type of an extra channel.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum JxlExtraChannelType {
     ///JXL_CHANNEL_ALPHA
+    #[default]
     Alpha,
     ///JXL_CHANNEL_DEPTH
     Depth,
@@ -69,17 +65,12 @@ pub enum JxlExtraChannelType {
     Optional,
 }
 
-impl Default for JxlExtraChannelType {
-    fn default() -> Self {
-        JxlExtraChannelType::Alpha
-    }
-}
-
 /// Frame blend modes. When decoding, if coalescing is enabled (default), this can be ignored.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum JxlBlendMode {
     ///JXL_BLEND_REPLACE
+    #[default]
     Replace = 0,
     ///JXL_BLEND_ADD
     Add = 1,
@@ -91,14 +82,8 @@ pub enum JxlBlendMode {
     Mul = 4,
 }
 
-impl Default for JxlBlendMode {
-    fn default() -> Self {
-        JxlBlendMode::Replace
-    }
-}
-
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 /// Represents the preview header information.
 pub struct JxlPreviewHeader {
     ///Preview width in pixels
@@ -106,11 +91,6 @@ pub struct JxlPreviewHeader {
     ///Preview height in pixels
     pub ysize: u32,
 }
-impl Default for JxlPreviewHeader {
-    fn default() -> Self {
-        JxlPreviewHeader { xsize: 0, ysize: 0 }
-    }
-}
 
 /// Represents the animation header information.
 #[repr(C)]
@@ -198,7 +178,7 @@ impl Default for JxlBasicInfo {
 
 /// Represents extra channel information.
 #[repr(C)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct JxlExtraChannelInfo {
     pub type_: JxlExtraChannelType,
     pub bits_per_sample: u32,
@@ -0,0 +1,96 @@
+use crate::JxlEncoder;
+use crate::JxlError;
+
+use std::os::raw::c_char;
+
+/// Byte orders a TIFF header (and so Exif data) can start with.
+const TIFF_HEADERS: [&[u8; 4]; 2] = [b"II*\0", b"MM\0*"];
+
+/// Prefix of the Exif data in a JPEG APP1 segment.
+const EXIF_APP1_PREFIX: &[u8] = b"Exif\0\0";
+
+impl JxlEncoder {
+    /// Adds an `Exif` box, switching to the container format.
+    ///
+    /// `exif` is TIFF-structured Exif data. The `Exif\0\0` prefix of a JPEG APP1 segment is
+    /// dropped, and the 4-byte offset to the TIFF header the box starts with is added unless
+    /// it is already there. With `compress` the box is stored as `brob`, which needs libjxl
+    /// built with Brotli.
+    pub fn add_exif(&mut self, exif: &[u8], compress: bool) -> Result<(), JxlError> {
+        let contents = exif_box_contents(exif)?;
+        self.add_metadata_box(b"Exif", &contents, compress)
+    }
+
+    /// Adds an XMP packet as an `xml ` box, switching to the container format.
+    pub fn add_xmp(&mut self, xmp: &str, compress: bool) -> Result<(), JxlError> {
+        self.add_metadata_box(b"xml ", xmp.as_bytes(), compress)
+    }
+
+    /// Adds a JUMBF superbox (C2PA and the like) as a `jumb` box, switching to the container
+    /// format. `jumbf` is the superbox contents without its own box header.
+    pub fn add_jumbf(&mut self, jumbf: &[u8], compress: bool) -> Result<(), JxlError> {
+        self.add_metadata_box(b"jumb", jumbf, compress)
+    }
+
+    fn add_metadata_box(
+        &mut self,
+        box_type: &[u8; 4],
+        contents: &[u8],
+        compress: bool,
+    ) -> Result<(), JxlError> {
+        // 출력이 시작된 뒤에는 바꿀 수 없으므로 첫 박스에서만 설정
+        if !self.boxes_used {
+            self.use_container(true)?;
+        }
+        self.add_box(&box_type.map(|b| b as c_char), contents, compress)
+    }
+}
+
+fn starts_with_tiff_header(data: &[u8]) -> bool {
+    TIFF_HEADERS.iter().any(|header| data.starts_with(*header))
+}
+
+/// Builds the contents of an `Exif` box: a big-endian offset to the TIFF header, then the data.
+pub(crate) fn exif_box_contents(exif: &[u8]) -> Result<Vec<u8>, JxlError> {
+    let exif = exif.strip_prefix(EXIF_APP1_PREFIX).unwrap_or(exif);
+    if starts_with_tiff_header(exif) {
+        let mut contents = Vec::with_capacity(4 + exif.len());
+        contents.extend_from_slice(&0u32.to_be_bytes());
+        contents.extend_from_slice(exif);
+        return Ok(contents);
+    }
+
+    // 이미 오프셋이 붙어 있으면 그대로 사용
+    if let Some((offset, rest)) = exif.split_first_chunk::<4>() {
+        let offset = u32::from_be_bytes(*offset) as usize;
+        if rest.get(offset..).is_some_and(starts_with_tiff_header) {
+            return Ok(exif.to_vec());
+        }
+    }
+    Err(JxlError::InvalidInput(
+        "Exif data must start with a TIFF header".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exif_box_contents() {
+        let tiff = b"MM\0*\0\0\0\x08rest";
+        let expected = [&[0, 0, 0, 0][..], tiff].concat();
+        assert_eq!(exif_box_contents(tiff).unwrap(), expected);
+        assert_eq!(
+            exif_box_contents(&[b"Exif\0\0".as_slice(), tiff].concat()).unwrap(),
+            expected
+        );
+
+        let with_offset = [&[0, 0, 0, 2][..], b"..", b"II*\0\x08\0\0\0"].concat();
+        assert_eq!(exif_box_contents(&with_offset).unwrap(), with_offset);
+
+        assert!(exif_box_contents(b"not exif").is_err());
+        assert!(exif_box_contents(&[0, 0, 0, 9, b'I', b'I', b'*', 0]).is_err());
+        assert!(exif_box_contents(&[]).is_err());
+    }
+}
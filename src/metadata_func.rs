@@ -1,21 +1,24 @@
 ﻿use crate::error::JxlError;
-use crate::{JxlBool, JxlGainMapBundle, JxlMemoryManager};
-use libloading::{Library, Symbol};
+use crate::{JxlBool, JxlGainMapBundle, JxlLibrary, JxlMemoryManager};
 
 use std::ptr;
+use std::sync::Arc;
 
 /// ICC 프로필과 GainMap 관련 기능을 처리하는 구조체
 #[derive(Debug)]
 pub struct JxlIccGainMap {
-    lib: Library,
+    lib: Arc<JxlLibrary>,
 }
 
 impl JxlIccGainMap {
     /// 새로운 JxlIccGainMap 인스턴스를 생성합니다.
     pub fn new<P: AsRef<std::ffi::OsStr>>(path: P) -> Result<Self, JxlError> {
-        let lib = unsafe { Library::new(path) }.map_err(JxlError::LibraryLoadFailed)?;
+        Ok(Self::with_library(JxlLibrary::load(path)?))
+    }
 
-        Ok(Self { lib })
+    /// 이미 로드된 라이브러리를 공유하는 JxlIccGainMap 인스턴스를 생성합니다.
+    pub fn with_library(lib: Arc<JxlLibrary>) -> Self {
+        Self { lib }
     }
 
     /// ICC 프로필을 압축합니다.
@@ -24,15 +27,7 @@ impl JxlIccGainMap {
         memory_manager: &JxlMemoryManager,
         icc_data: &[u8],
     ) -> Result<Vec<u8>, JxlError> {
-        let encode_fn: Symbol<
-            unsafe extern "C" fn(
-                *const JxlMemoryManager,
-                *const u8,
-                usize,
-                *mut *mut u8,
-                *mut usize,
-            ) -> JxlBool,
-        > = unsafe { self.lib.get(b"JxlICCProfileEncode") }.map_err(JxlError::SymbolLoadFailed)?;
+        let encode_fn = self.lib.fns.icc_profile_encode()?;
 
         let mut compressed_icc: *mut u8 = ptr::null_mut();
         let mut compressed_size: usize = 0;
@@ -67,15 +62,7 @@ impl JxlIccGainMap {
         memory_manager: &JxlMemoryManager,
         compressed_data: &[u8],
     ) -> Result<Vec<u8>, JxlError> {
-        let decode_fn: Symbol<
-            unsafe extern "C" fn(
-                *const JxlMemoryManager,
-                *const u8,
-                usize,
-                *mut *mut u8,
-                *mut usize,
-            ) -> JxlBool,
-        > = unsafe { self.lib.get(b"JxlICCProfileDecode") }.map_err(JxlError::SymbolLoadFailed)?;
+        let decode_fn = self.lib.fns.icc_profile_decode()?;
 
         let mut icc: *mut u8 = ptr::null_mut();
         let mut icc_size: usize = 0;
@@ -108,10 +95,7 @@ impl JxlIccGainMap {
         &self,
         map_bundle: &JxlGainMapBundle,
     ) -> Result<usize, JxlError> {
-        let get_size_fn: Symbol<
-            unsafe extern "C" fn(*const JxlGainMapBundle, *mut usize) -> JxlBool,
-        > = unsafe { self.lib.get(b"JxlGainMapGetBundleSize") }
-            .map_err(JxlError::SymbolLoadFailed)?;
+        let get_size_fn = self.lib.fns.gain_map_get_bundle_size()?;
 
         let mut bundle_size: usize = 0;
         let result = unsafe { get_size_fn(map_bundle, &mut bundle_size) };
@@ -129,10 +113,7 @@ impl JxlIccGainMap {
         map_bundle: &JxlGainMapBundle,
         output_buffer: &mut [u8],
     ) -> Result<usize, JxlError> {
-        let write_fn: Symbol<
-            unsafe extern "C" fn(*const JxlGainMapBundle, *mut u8, usize, *mut usize) -> JxlBool,
-        > = unsafe { self.lib.get(b"JxlGainMapWriteBundle") }
-            .map_err(JxlError::SymbolLoadFailed)?;
+        let write_fn = self.lib.fns.gain_map_write_bundle()?;
 
         let mut bytes_written: usize = 0;
         let result = unsafe {
@@ -159,9 +140,7 @@ impl JxlIccGainMap {
         map_bundle: &mut JxlGainMapBundle,
         input_buffer: &[u8],
     ) -> Result<usize, JxlError> {
-        let read_fn: Symbol<
-            unsafe extern "C" fn(*mut JxlGainMapBundle, *const u8, usize, *mut usize) -> JxlBool,
-        > = unsafe { self.lib.get(b"JxlGainMapReadBundle") }.map_err(JxlError::SymbolLoadFailed)?;
+        let read_fn = self.lib.fns.gain_map_read_bundle()?;
 
         let mut bytes_read: usize = 0;
         let result = unsafe {
@@ -183,23 +162,17 @@ impl JxlIccGainMap {
     }
 }
 
-impl Drop for JxlIccGainMap {
-    fn drop(&mut self) {
-        // Library는 자동으로 해제됩니다
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_icc_gainmap_creation() {
-        let lib_path = "dlls/jxl.dll"; // 실제 라이브러리 경로로 변경 필요
-        let result = JxlIccGainMap::new(lib_path);
-        if result.is_err() {
-            println!("{:?}", result.unwrap_err());
-            return;
+        match JxlLibrary::discover() {
+            Ok(lib) => {
+                let _ = JxlIccGainMap::with_library(lib);
+            }
+            Err(e) => println!("{:?}", e),
         }
     }
 
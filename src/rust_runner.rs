@@ -0,0 +1,359 @@
+use crate::JxlError;
+use crate::JxlParallelRunFunction;
+use crate::JxlParallelRunInit;
+use crate::JxlParallelRunner;
+use crate::ParallelRunner;
+
+use std::ffi::{c_int, c_void};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// JXL_PARALLEL_RET_RUNNER_ERROR
+const RUNNER_ERROR: c_int = -1;
+
+/// A parallel runner implemented in Rust, so libjxl never spawns threads of its own.
+///
+/// Work is dispatched either onto a built-in pool of std threads or, with the `rayon`
+/// feature, onto a rayon `ThreadPool` owned by the application.
+pub struct RustParallelRunner {
+    backend: Backend,
+}
+
+enum Backend {
+    Std(StdThreadPool),
+    #[cfg(feature = "rayon")]
+    Rayon(Arc<rayon::ThreadPool>),
+    #[cfg(feature = "rayon")]
+    RayonGlobal,
+}
+
+impl RustParallelRunner {
+    /// Creates a runner with its own pool of `num_worker_threads` std threads.
+    ///
+    /// The calling thread also takes work, and `0` runs everything on the calling thread.
+    pub fn new(num_worker_threads: usize) -> Result<Self, JxlError> {
+        Ok(RustParallelRunner {
+            backend: Backend::Std(StdThreadPool::new(num_worker_threads)?),
+        })
+    }
+
+    /// Creates a std pool with one worker per available CPU besides the calling thread.
+    pub fn with_default_threads() -> Result<Self, JxlError> {
+        let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::new(cpus - 1)
+    }
+
+    /// Dispatches work onto an existing rayon pool.
+    #[cfg(feature = "rayon")]
+    pub fn with_rayon_pool(pool: Arc<rayon::ThreadPool>) -> Self {
+        RustParallelRunner {
+            backend: Backend::Rayon(pool),
+        }
+    }
+
+    /// Dispatches work onto the rayon pool of the calling thread (the global pool outside one).
+    #[cfg(feature = "rayon")]
+    pub fn with_rayon_global() -> Self {
+        RustParallelRunner {
+            backend: Backend::RayonGlobal,
+        }
+    }
+
+    /// Returns the thread count reported to libjxl's init callback.
+    pub fn num_threads(&self) -> usize {
+        match &self.backend {
+            Backend::Std(pool) => pool.workers.len() + 1,
+            #[cfg(feature = "rayon")]
+            Backend::Rayon(pool) => pool.current_num_threads(),
+            #[cfg(feature = "rayon")]
+            Backend::RayonGlobal => rayon::current_num_threads(),
+        }
+    }
+
+    fn run(&self, job: Job, start_range: u32, end_range: u32) {
+        match &self.backend {
+            Backend::Std(pool) => pool.run(job, start_range, end_range),
+            #[cfg(feature = "rayon")]
+            Backend::Rayon(pool) => {
+                pool.scope(|scope| rayon_run(scope, job, start_range, end_range))
+            }
+            #[cfg(feature = "rayon")]
+            Backend::RayonGlobal => {
+                rayon::scope(|scope| rayon_run(scope, job, start_range, end_range))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RustParallelRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let backend = match &self.backend {
+            Backend::Std(_) => "std",
+            #[cfg(feature = "rayon")]
+            Backend::Rayon(_) => "rayon",
+            #[cfg(feature = "rayon")]
+            Backend::RayonGlobal => "rayon-global",
+        };
+        f.debug_struct("RustParallelRunner")
+            .field("backend", &backend)
+            .field("num_threads", &self.num_threads())
+            .finish()
+    }
+}
+
+unsafe impl ParallelRunner for RustParallelRunner {
+    fn runner_fn(&self) -> JxlParallelRunner {
+        rust_parallel_runner
+    }
+
+    fn runner_opaque(&self) -> *mut c_void {
+        self as *const Self as *mut c_void
+    }
+}
+
+/// One `JxlParallelRunner` call: libjxl's callback and its opaque state.
+#[derive(Clone, Copy)]
+struct Job {
+    jpegxl_opaque: *mut c_void,
+    func: JxlParallelRunFunction,
+}
+
+// libjxl requires `func` to be callable from any thread for the duration of the run.
+unsafe impl Send for Job {}
+unsafe impl Sync for Job {}
+
+impl Job {
+    /// Takes values from `next` until the range is exhausted.
+    fn drain(&self, next: &AtomicU32, end_range: u32, thread_id: usize) {
+        loop {
+            let value = next.fetch_add(1, Ordering::Relaxed);
+            if value >= end_range {
+                break;
+            }
+            unsafe { (self.func)(self.jpegxl_opaque, value, thread_id) };
+        }
+    }
+}
+
+unsafe extern "C" fn rust_parallel_runner(
+    runner_opaque: *mut c_void,
+    jpegxl_opaque: *mut c_void,
+    init: JxlParallelRunInit,
+    func: JxlParallelRunFunction,
+    start_range: u32,
+    end_range: u32,
+) -> c_int {
+    let runner = &*(runner_opaque as *const RustParallelRunner);
+
+    let result = init(jpegxl_opaque, runner.num_threads());
+    if result != 0 {
+        return result;
+    }
+    if start_range >= end_range {
+        return 0;
+    }
+
+    let job = Job {
+        jpegxl_opaque,
+        func,
+    };
+    // 패닉이 C 코드로 넘어가지 않도록 에러 코드로 변환
+    match panic::catch_unwind(AssertUnwindSafe(|| runner.run(job, start_range, end_range))) {
+        Ok(()) => 0,
+        Err(_) => RUNNER_ERROR,
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn rayon_run<'scope>(scope: &rayon::Scope<'scope>, job: Job, start_range: u32, end_range: u32) {
+    let next = Arc::new(AtomicU32::new(start_range));
+    let num_threads = rayon::current_num_threads().min((end_range - start_range) as usize);
+    for thread_id in 1..num_threads {
+        let next = next.clone();
+        scope.spawn(move |_| job.drain(&next, end_range, thread_id));
+    }
+    job.drain(&next, end_range, 0);
+}
+
+/// Persistent std threads that wait for `JxlParallelRunner` calls.
+struct StdThreadPool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    // libjxl runs one range at a time per codec; codecs sharing the pool take turns
+    run_lock: Mutex<()>,
+}
+
+struct Shared {
+    state: Mutex<PoolState>,
+    work_ready: Condvar,
+    work_done: Condvar,
+    next: AtomicU32,
+}
+
+struct PoolState {
+    job: Option<(Job, u32)>,
+    generation: u64,
+    busy_workers: usize,
+    shutdown: bool,
+}
+
+impl StdThreadPool {
+    fn new(num_workers: usize) -> Result<Self, JxlError> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(PoolState {
+                job: None,
+                generation: 0,
+                busy_workers: 0,
+                shutdown: false,
+            }),
+            work_ready: Condvar::new(),
+            work_done: Condvar::new(),
+            next: AtomicU32::new(0),
+        });
+        let mut pool = StdThreadPool {
+            shared,
+            workers: Vec::with_capacity(num_workers),
+            run_lock: Mutex::new(()),
+        };
+        for thread_id in 1..=num_workers {
+            let shared = pool.shared.clone();
+            let worker = thread::Builder::new()
+                .name(format!("jxl-runner-{thread_id}"))
+                .spawn(move || worker_loop(&shared, thread_id))
+                // 이미 생성된 스레드는 pool 의 Drop 에서 정리됨
+                .map_err(|_| JxlError::ParallelRunnerCreationFailed)?;
+            pool.workers.push(worker);
+        }
+
+        Ok(pool)
+    }
+
+    fn run(&self, job: Job, start_range: u32, end_range: u32) {
+        if self.workers.is_empty() || end_range - start_range == 1 {
+            job.drain(&AtomicU32::new(start_range), end_range, 0);
+            return;
+        }
+
+        let _run = self.run_lock.lock().unwrap_or_else(|e| e.into_inner());
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            self.shared.next.store(start_range, Ordering::Relaxed);
+            state.job = Some((job, end_range));
+            state.generation += 1;
+            state.busy_workers = self.workers.len();
+            self.shared.work_ready.notify_all();
+        }
+
+        job.drain(&self.shared.next, end_range, 0);
+
+        let mut state = self.shared.state.lock().unwrap();
+        while state.busy_workers > 0 {
+            state = self.shared.work_done.wait(state).unwrap();
+        }
+        state.job = None;
+    }
+}
+
+fn worker_loop(shared: &Shared, thread_id: usize) {
+    let mut seen_generation = 0;
+    loop {
+        let (job, end_range) = {
+            let mut state = shared.state.lock().unwrap();
+            while !state.shutdown && state.generation == seen_generation {
+                state = shared.work_ready.wait(state).unwrap();
+            }
+            if state.shutdown {
+                return;
+            }
+            seen_generation = state.generation;
+            match state.job {
+                Some(job) => job,
+                None => continue,
+            }
+        };
+
+        job.drain(&shared.next, end_range, thread_id);
+
+        let mut state = shared.state.lock().unwrap();
+        state.busy_workers -= 1;
+        if state.busy_workers == 0 {
+            shared.work_done.notify_one();
+        }
+    }
+}
+
+impl Drop for StdThreadPool {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.shutdown = true;
+            self.shared.work_ready.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Counter {
+        threads: AtomicUsize,
+        calls: Vec<AtomicUsize>,
+    }
+
+    unsafe extern "C" fn init(opaque: *mut c_void, num_threads: usize) -> c_int {
+        (*(opaque as *const Counter))
+            .threads
+            .store(num_threads, Ordering::SeqCst);
+        0
+    }
+
+    unsafe extern "C" fn func(opaque: *mut c_void, value: u32, thread_id: usize) {
+        let counter = &*(opaque as *const Counter);
+        assert!(thread_id < counter.threads.load(Ordering::SeqCst));
+        counter.calls[value as usize].fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn run_once(runner: &RustParallelRunner) {
+        let counter = Counter {
+            threads: AtomicUsize::new(0),
+            calls: (0..1000).map(|_| AtomicUsize::new(0)).collect(),
+        };
+        let opaque = &counter as *const Counter as *mut c_void;
+        let result =
+            unsafe { (runner.runner_fn())(runner.runner_opaque(), opaque, init, func, 10, 1000) };
+
+        assert_eq!(result, 0);
+        assert_eq!(counter.threads.load(Ordering::SeqCst), runner.num_threads());
+        for (value, calls) in counter.calls.iter().enumerate() {
+            assert_eq!(calls.load(Ordering::SeqCst), usize::from(value >= 10));
+        }
+    }
+
+    #[test]
+    fn test_std_runner_covers_range_once() {
+        for workers in [0, 1, 4] {
+            let runner = RustParallelRunner::new(workers).unwrap();
+            run_once(&runner);
+            run_once(&runner);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_rayon_runner_covers_range_once() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+        run_once(&RustParallelRunner::with_rayon_pool(Arc::new(pool)));
+        run_once(&RustParallelRunner::with_rayon_global());
+    }
+}
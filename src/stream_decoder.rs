@@ -0,0 +1,133 @@
+use crate::DecodeOptions;
+use crate::DecodedImage;
+use crate::JxlDecoder;
+use crate::JxlDecoderStatus;
+use crate::JxlError;
+use crate::ParallelRunner;
+
+use std::io::{ErrorKind, Read};
+use std::sync::Arc;
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A decoder fed from a `Read` in chunks instead of one in-memory buffer.
+///
+/// Only the bytes libjxl has not consumed yet plus one chunk are held at a time, so large
+/// inputs can be decoded straight from a file or socket.
+pub struct JxlStreamDecoder<R> {
+    // libjxl holds a pointer into `input.buffer`; the decoder is dropped first
+    decoder: JxlDecoder,
+    input: StreamInput<R>,
+}
+
+struct StreamInput<R> {
+    reader: R,
+    /// Unconsumed tail of the previous chunk followed by the newest chunk.
+    buffer: Vec<u8>,
+    chunk_size: usize,
+    closed: bool,
+}
+
+impl<R: Read> JxlStreamDecoder<R> {
+    /// Wraps `decoder`, reading input from `reader` in 64 KiB chunks.
+    pub fn new(decoder: JxlDecoder, reader: R) -> Self {
+        JxlStreamDecoder {
+            decoder,
+            input: StreamInput {
+                reader,
+                buffer: Vec::new(),
+                chunk_size: DEFAULT_CHUNK_SIZE,
+                closed: false,
+            },
+        }
+    }
+
+    /// Sets how many bytes are read from the reader per `NeedMoreInput`.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.input.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Returns the wrapped decoder, e.g. to subscribe events before `process`.
+    pub fn decoder(&self) -> &JxlDecoder {
+        &self.decoder
+    }
+
+    /// Attaches a parallel runner to the wrapped decoder; see `JxlDecoder::set_parallel_runner`.
+    pub fn set_parallel_runner<P: ParallelRunner + 'static>(
+        &mut self,
+        runner: Arc<P>,
+    ) -> Result<(), JxlError> {
+        self.decoder.set_parallel_runner(runner)
+    }
+
+    /// Returns the number of input bytes currently held in memory.
+    pub fn buffered_len(&self) -> usize {
+        self.input.buffer.len()
+    }
+
+    /// Processes input until libjxl reports an event, reading more from the reader on
+    /// `NeedMoreInput`. - JxlDecoderProcessInput()
+    ///
+    /// Never returns `NeedMoreInput`; a reader that ends early gives `JxlError::NotEnoughInput`.
+    pub fn process(&mut self) -> Result<JxlDecoderStatus, JxlError> {
+        loop {
+            match self.decoder.process_input()? {
+                JxlDecoderStatus::NeedMoreInput => self.input.refill(&self.decoder)?,
+                status => return Ok(status),
+            }
+        }
+    }
+
+    /// Decodes the whole stream like `JxlDecoder::decode`.
+    ///
+    /// Resets the decoder, so call it on a fresh stream rather than after `process`.
+    pub fn decode(&mut self, options: &DecodeOptions) -> Result<DecodedImage, JxlError> {
+        self.input.buffer.clear();
+        let decoder = &self.decoder;
+        let input = &mut self.input;
+        decoder.decode_with(options, || input.refill(decoder))
+    }
+
+    /// Returns the reader, dropping any input that was read but not consumed.
+    pub fn into_inner(self) -> R {
+        self.input.reader
+    }
+}
+
+impl<R: Read> StreamInput<R> {
+    /// Keeps the unconsumed tail, appends the next chunk and hands both to libjxl.
+    /// - JxlDecoderReleaseInput() / JxlDecoderSetInput()
+    fn refill(&mut self, decoder: &JxlDecoder) -> Result<(), JxlError> {
+        if self.closed {
+            return Err(JxlError::NotEnoughInput);
+        }
+
+        // 디코더가 아직 읽지 않은 뒷부분만 남김
+        let unconsumed = decoder.release_input().min(self.buffer.len());
+        self.buffer.drain(..self.buffer.len() - unconsumed);
+
+        let tail = self.buffer.len();
+        self.buffer.resize(tail + self.chunk_size, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[tail..]) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    // 남은 입력을 다시 넘겨 재시도할 수 있게 함
+                    self.buffer.truncate(tail);
+                    decoder.set_input(&self.buffer)?;
+                    return Err(e.into());
+                }
+            }
+        };
+        self.buffer.truncate(tail + read);
+
+        decoder.set_input(&self.buffer)?;
+        if read == 0 {
+            self.closed = true;
+            decoder.close_input();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use crate::FrameSettings;
+use crate::JxlBasicInfo;
+use crate::JxlBoxType;
+use crate::JxlColorEncoding;
+use crate::JxlEncoder;
+use crate::JxlEncoderOutputProcessor;
+use crate::JxlError;
+use crate::JxlExtraChannelInfo;
+use crate::ParallelRunner;
+
+use std::ffi::{c_int, c_void};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+const MIN_BUFFER_SIZE: usize = 64 * 1024;
+
+type SeekFn = unsafe extern "C" fn(opaque: *mut c_void, position: u64);
+type SeekToEndFn<W> = fn(&mut Sink<W>) -> io::Result<()>;
+
+/// An encoder that writes its output straight into a `Write` through libjxl's output
+/// processor, instead of collecting it with `process_output`. - JxlEncoderSetOutputProcessor()
+///
+/// With a seekable writer libjxl can go back and patch sizes in place, so nothing but the
+/// current chunk is held in memory. A plain `Write` works too, but then libjxl buffers the
+/// parts it may still rewrite. Needs libjxl 0.10 or newer.
+pub struct JxlStreamEncoder<W> {
+    // libjxl holds `opaque` pointing into `sink`; the encoder is dropped first and never
+    // handed out mutably, so it can't be swapped away from its sink
+    pub(crate) encoder: JxlEncoder,
+    sink: Box<Sink<W>>,
+}
+
+struct Sink<W> {
+    writer: W,
+    buffer: Vec<u8>,
+    /// Writer position of byte 0 of the output.
+    start: u64,
+    position: u64,
+    end: u64,
+    finalized: u64,
+    /// Moves the writer back to `end` before finishing; `None` for unseekable writers.
+    seek_to_end: Option<SeekToEndFn<W>>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write + Seek> JxlStreamEncoder<W> {
+    /// Streams the output of `encoder` into a seekable `writer`, starting at its current position.
+    ///
+    /// Call this before adding frames or boxes.
+    pub fn new(encoder: JxlEncoder, mut writer: W) -> Result<Self, JxlError> {
+        let start = writer.stream_position()?;
+        let mut sink = Sink::new(writer, start);
+        sink.seek_to_end = Some(Sink::seek_to_end);
+        Self::with_sink(encoder, sink, Some(seek::<W>))
+    }
+}
+
+impl<W: Write> JxlStreamEncoder<W> {
+    /// Streams the output of `encoder` into a writer that cannot seek, e.g. a socket.
+    ///
+    /// Call this before adding frames or boxes.
+    pub fn new_unseekable(encoder: JxlEncoder, writer: W) -> Result<Self, JxlError> {
+        Self::with_sink(encoder, Sink::new(writer, 0), None)
+    }
+
+    fn with_sink(
+        mut encoder: JxlEncoder,
+        sink: Sink<W>,
+        seek: Option<SeekFn>,
+    ) -> Result<Self, JxlError> {
+        let mut sink = Box::new(sink);
+        let output_processor = JxlEncoderOutputProcessor {
+            opaque: &mut *sink as *mut Sink<W> as *mut c_void,
+            get_buffer: Some(get_buffer::<W>),
+            release_buffer: Some(release_buffer::<W>),
+            seek,
+            set_finalized_position: Some(set_finalized_position::<W>),
+        };
+        encoder.set_output_processor(output_processor)?;
+
+        Ok(JxlStreamEncoder { encoder, sink })
+    }
+
+    /// Returns the wrapped encoder, e.g. for `init_basic_info`.
+    pub fn encoder(&self) -> &JxlEncoder {
+        &self.encoder
+    }
+
+    /// Creates frame settings for adding frames. - JxlEncoderFrameSettingsCreate()
+    pub fn create_frame_settings(&self) -> Result<FrameSettings<'_>, JxlError> {
+        self.encoder.create_frame_settings()
+    }
+
+    /// Attaches a parallel runner; see `JxlEncoder::set_parallel_runner`.
+    pub fn set_parallel_runner<P: ParallelRunner + 'static>(
+        &mut self,
+        runner: Arc<P>,
+    ) -> Result<(), JxlError> {
+        self.encoder.set_parallel_runner(runner)
+    }
+
+    /// Sets the basic info of the image. - JxlEncoderSetBasicInfo()
+    pub fn set_basic_info(&mut self, info: &JxlBasicInfo) -> Result<(), JxlError> {
+        self.encoder.set_basic_info(info)
+    }
+
+    /// Sets the original color encoding of the image. - JxlEncoderSetColorEncoding()
+    pub fn set_color_encoding(&mut self, color: &JxlColorEncoding) -> Result<(), JxlError> {
+        self.encoder.set_color_encoding(color)
+    }
+
+    /// Sets the original ICC profile of the image. - JxlEncoderSetICCProfile()
+    pub fn set_icc_profile(&mut self, icc_profile: &[u8]) -> Result<(), JxlError> {
+        self.encoder.set_icc_profile(icc_profile)
+    }
+
+    /// Sets the info of extra channel `index`. - JxlEncoderSetExtraChannelInfo()
+    pub fn set_extra_channel_info(
+        &mut self,
+        index: usize,
+        info: &JxlExtraChannelInfo,
+    ) -> Result<(), JxlError> {
+        self.encoder.set_extra_channel_info(index, info)
+    }
+
+    /// Sets the name of extra channel `index`. - JxlEncoderSetExtraChannelName()
+    pub fn set_extra_channel_name(&mut self, index: usize, name: &str) -> Result<(), JxlError> {
+        self.encoder.set_extra_channel_name(index, name)
+    }
+
+    /// Forces the container format on or off. - JxlEncoderUseContainer()
+    pub fn use_container(&mut self, use_container: bool) -> Result<(), JxlError> {
+        self.encoder.use_container(use_container)
+    }
+
+    /// Keeps JPEG reconstruction data for added JPEG frames. - JxlEncoderStoreJPEGMetadata()
+    pub fn store_jpeg_metadata(&mut self, store_jpeg_metadata: bool) -> Result<(), JxlError> {
+        self.encoder.store_jpeg_metadata(store_jpeg_metadata)
+    }
+
+    /// Sets the codestream level. - JxlEncoderSetCodestreamLevel()
+    pub fn set_codestream_level(&mut self, level: c_int) -> Result<(), JxlError> {
+        self.encoder.set_codestream_level(level)
+    }
+
+    /// Enables adding boxes. - JxlEncoderUseBoxes()
+    pub fn use_boxes(&mut self) -> Result<(), JxlError> {
+        self.encoder.use_boxes()
+    }
+
+    /// Adds a metadata box; see `JxlEncoder::add_box`. - JxlEncoderAddBox()
+    pub fn add_box(
+        &mut self,
+        type_: &JxlBoxType,
+        contents: &[u8],
+        compress_box: bool,
+    ) -> Result<(), JxlError> {
+        self.encoder.add_box(type_, contents, compress_box)
+    }
+
+    /// Adds an `Exif` box; see `JxlEncoder::add_exif`.
+    pub fn add_exif(&mut self, exif: &[u8], compress: bool) -> Result<(), JxlError> {
+        self.encoder.add_exif(exif, compress)
+    }
+
+    /// Adds an XMP packet as an `xml ` box; see `JxlEncoder::add_xmp`.
+    pub fn add_xmp(&mut self, xmp: &str, compress: bool) -> Result<(), JxlError> {
+        self.encoder.add_xmp(xmp, compress)
+    }
+
+    /// Adds a JUMBF superbox as a `jumb` box; see `JxlEncoder::add_jumbf`.
+    pub fn add_jumbf(&mut self, jumbf: &[u8], compress: bool) -> Result<(), JxlError> {
+        self.encoder.add_jumbf(jumbf, compress)
+    }
+
+    /// Tells libjxl that no more boxes follow. - JxlEncoderCloseBoxes()
+    pub fn close_boxes(&mut self) -> Result<(), JxlError> {
+        self.encoder.close_boxes()
+    }
+
+    /// Tells libjxl that no more frames follow. - JxlEncoderCloseFrames()
+    pub fn close_frames(&mut self) -> Result<(), JxlError> {
+        self.encoder.close_frames()
+    }
+
+    /// Returns how many bytes of output have been written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.sink.end
+    }
+
+    /// Returns the offset before which libjxl will not rewrite the output any more.
+    pub fn finalized_position(&self) -> u64 {
+        self.sink.finalized
+    }
+
+    /// Writes out everything libjxl can produce from the input so far. - JxlEncoderFlushInput()
+    pub fn flush(&mut self) -> Result<(), JxlError> {
+        let result = self.encoder.flush_input();
+        self.take_error(result)
+    }
+
+    /// Closes the input, writes the rest of the output and returns the writer.
+    /// - JxlEncoderCloseInput() / JxlEncoderFlushInput()
+    ///
+    /// A write error from any earlier encoder call is reported here as `JxlError::Io`.
+    pub fn finish(mut self) -> Result<W, JxlError> {
+        let result = self.encoder.close_input();
+        self.take_error(result)?;
+        self.flush()?;
+
+        let JxlStreamEncoder { encoder, mut sink } = self;
+        drop(encoder);
+        if let Some(seek_to_end) = sink.seek_to_end {
+            seek_to_end(&mut sink)?;
+        }
+        sink.writer.flush()?;
+        Ok(sink.writer)
+    }
+
+    /// Prefers the writer's error over the status libjxl reported for it.
+    fn take_error<T>(&mut self, result: Result<T, JxlError>) -> Result<T, JxlError> {
+        match self.sink.error.take() {
+            Some(e) => Err(JxlError::Io(e)),
+            None => result,
+        }
+    }
+}
+
+impl<W> Sink<W> {
+    fn new(writer: W, start: u64) -> Self {
+        Sink {
+            writer,
+            buffer: Vec::new(),
+            start,
+            position: 0,
+            end: 0,
+            finalized: 0,
+            seek_to_end: None,
+            error: None,
+        }
+    }
+
+    /// Runs `f` unless an earlier call failed, keeping the first error and any panic.
+    fn guard<T>(&mut self, f: impl FnOnce(&mut Self) -> io::Result<T>) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+        // 패닉이 C 코드로 넘어가지 않도록 에러로 변환
+        match panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            Err(_) => {
+                self.error = Some(io::Error::other("output writer panicked"));
+                None
+            }
+        }
+    }
+}
+
+impl<W: Write + Seek> Sink<W> {
+    fn seek_to_end(&mut self) -> io::Result<()> {
+        if self.position != self.end {
+            self.writer.seek(SeekFrom::Start(self.start + self.end))?;
+            self.position = self.end;
+        }
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn get_buffer<W: Write>(opaque: *mut c_void, size: *mut usize) -> *mut c_void {
+    let sink = &mut *(opaque as *mut Sink<W>);
+    if sink.error.is_some() {
+        return ptr::null_mut();
+    }
+    let len = (*size).max(MIN_BUFFER_SIZE);
+    if sink.buffer.len() < len {
+        sink.buffer.resize(len, 0);
+    }
+    *size = sink.buffer.len();
+    sink.buffer.as_mut_ptr() as *mut c_void
+}
+
+unsafe extern "C" fn release_buffer<W: Write>(opaque: *mut c_void, written_bytes: usize) {
+    let sink = &mut *(opaque as *mut Sink<W>);
+    sink.guard(|sink| {
+        let written = written_bytes.min(sink.buffer.len());
+        sink.writer.write_all(&sink.buffer[..written])?;
+        sink.position += written as u64;
+        sink.end = sink.end.max(sink.position);
+        Ok(())
+    });
+}
+
+unsafe extern "C" fn seek<W: Write + Seek>(opaque: *mut c_void, position: u64) {
+    let sink = &mut *(opaque as *mut Sink<W>);
+    sink.guard(|sink| {
+        sink.writer.seek(SeekFrom::Start(sink.start + position))?;
+        sink.position = position;
+        Ok(())
+    });
+}
+
+unsafe extern "C" fn set_finalized_position<W>(opaque: *mut c_void, finalized_position: u64) {
+    let sink = &mut *(opaque as *mut Sink<W>);
+    sink.finalized = sink.finalized.max(finalized_position);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    unsafe fn write(opaque: *mut c_void, data: &[u8]) {
+        let mut size = data.len();
+        let buffer = get_buffer::<Cursor<Vec<u8>>>(opaque, &mut size) as *mut u8;
+        assert!(!buffer.is_null() && size >= data.len());
+        ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len());
+        release_buffer::<Cursor<Vec<u8>>>(opaque, data.len());
+    }
+
+    #[test]
+    fn test_sink_seeks_relative_to_start() {
+        let mut writer = Cursor::new(b"xx".to_vec());
+        writer.set_position(2);
+        let mut sink = Sink::new(writer, 2);
+        let opaque = &mut sink as *mut Sink<Cursor<Vec<u8>>> as *mut c_void;
+
+        unsafe {
+            write(opaque, b"header....");
+            write(opaque, b"body");
+            seek::<Cursor<Vec<u8>>>(opaque, 6);
+            write(opaque, b"SIZE");
+            set_finalized_position::<Cursor<Vec<u8>>>(opaque, 10);
+        }
+        assert_eq!(sink.end, 14);
+        assert_eq!(sink.position, 10);
+        assert_eq!(sink.finalized, 10);
+
+        sink.seek_to_end().unwrap();
+        assert_eq!(sink.writer.position(), 16);
+        assert_eq!(sink.writer.into_inner(), b"xxheaderSIZEbody");
+    }
+
+    #[test]
+    fn test_sink_keeps_first_error() {
+        struct Failing;
+        impl Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut sink = Sink::new(Failing, 0);
+        let opaque = &mut sink as *mut Sink<Failing> as *mut c_void;
+        unsafe {
+            let mut size = 0;
+            assert!(!get_buffer::<Failing>(opaque, &mut size).is_null());
+            release_buffer::<Failing>(opaque, 4);
+            assert!(get_buffer::<Failing>(opaque, &mut size).is_null());
+        }
+        assert_eq!(sink.error.unwrap().to_string(), "disk full");
+    }
+}
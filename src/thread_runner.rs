@@ -0,0 +1,163 @@
+use crate::JxlError;
+use crate::JxlMemoryManager;
+use crate::JxlParallelRunner;
+use crate::JxlThreadsLibrary;
+
+use std::ffi::c_void;
+use std::fmt;
+use std::ptr;
+use std::sync::Arc;
+
+/// A parallel runner that can be attached to `JxlDecoder` and `JxlEncoder`.
+///
+/// Codecs keep an `Arc` to the runner for as long as it is attached, so the runner outlives
+/// every call libjxl makes into it.
+///
+/// # Safety
+///
+/// `runner_fn` called with `runner_opaque` must implement the `JxlParallelRunner` contract,
+/// and `runner_opaque` must stay valid for as long as `self` is alive.
+pub unsafe trait ParallelRunner: fmt::Debug {
+    /// The runner entry point passed to libjxl.
+    fn runner_fn(&self) -> JxlParallelRunner;
+
+    /// The `runner_opaque` pointer passed to `runner_fn`.
+    fn runner_opaque(&self) -> *mut c_void;
+}
+
+/// Fixed-size thread pool from jxl_threads. - JxlThreadParallelRunner
+#[derive(Debug)]
+pub struct JxlThreadRunner {
+    lib: Arc<JxlThreadsLibrary>,
+    runner: *mut c_void,
+    num_worker_threads: usize,
+}
+
+impl JxlThreadRunner {
+    /// Creates a runner with `num_worker_threads` workers. - JxlThreadParallelRunnerCreate()
+    ///
+    /// `0` runs everything on the calling thread.
+    pub fn new(lib: Arc<JxlThreadsLibrary>, num_worker_threads: usize) -> Result<Self, JxlError> {
+        Self::with_memory_manager(lib, num_worker_threads, None)
+    }
+
+    /// Creates a runner using libjxl's default worker count. - JxlThreadParallelRunnerDefaultNumWorkerThreads()
+    pub fn with_default_threads(lib: Arc<JxlThreadsLibrary>) -> Result<Self, JxlError> {
+        let num_worker_threads = lib.default_num_worker_threads();
+        Self::new(lib, num_worker_threads)
+    }
+
+    /// Creates a runner with a custom memory manager. - JxlThreadParallelRunnerCreate()
+    pub fn with_memory_manager(
+        lib: Arc<JxlThreadsLibrary>,
+        num_worker_threads: usize,
+        memory_manager: Option<&JxlMemoryManager>,
+    ) -> Result<Self, JxlError> {
+        let runner = unsafe {
+            (lib.fns.thread_parallel_runner_create)(
+                memory_manager.map_or(ptr::null(), |m| m as *const _),
+                num_worker_threads,
+            )
+        };
+        if runner.is_null() {
+            return Err(JxlError::ParallelRunnerCreationFailed);
+        }
+
+        Ok(JxlThreadRunner {
+            lib,
+            runner,
+            num_worker_threads,
+        })
+    }
+
+    /// Returns the number of worker threads this runner was created with.
+    pub fn num_worker_threads(&self) -> usize {
+        self.num_worker_threads
+    }
+}
+
+// The C runner owns its worker threads and rejects concurrent runs with an error code.
+unsafe impl Send for JxlThreadRunner {}
+unsafe impl Sync for JxlThreadRunner {}
+
+unsafe impl ParallelRunner for JxlThreadRunner {
+    fn runner_fn(&self) -> JxlParallelRunner {
+        self.lib.fns.thread_parallel_runner
+    }
+
+    fn runner_opaque(&self) -> *mut c_void {
+        self.runner
+    }
+}
+
+impl Drop for JxlThreadRunner {
+    fn drop(&mut self) {
+        unsafe { (self.lib.fns.thread_parallel_runner_destroy)(self.runner) };
+    }
+}
+
+/// Thread pool from jxl_threads whose size can change between images. - JxlResizableParallelRunner
+///
+/// Starts with no worker threads; call `set_threads` (typically with `suggest_threads` once
+/// the image size is known) before decoding or encoding pixels.
+#[derive(Debug)]
+pub struct JxlResizableRunner {
+    lib: Arc<JxlThreadsLibrary>,
+    runner: *mut c_void,
+}
+
+impl JxlResizableRunner {
+    /// Creates a resizable runner. - JxlResizableParallelRunnerCreate()
+    pub fn new(lib: Arc<JxlThreadsLibrary>) -> Result<Self, JxlError> {
+        Self::with_memory_manager(lib, None)
+    }
+
+    /// Creates a resizable runner with a custom memory manager. - JxlResizableParallelRunnerCreate()
+    pub fn with_memory_manager(
+        lib: Arc<JxlThreadsLibrary>,
+        memory_manager: Option<&JxlMemoryManager>,
+    ) -> Result<Self, JxlError> {
+        let runner = unsafe {
+            (lib.fns.resizable_parallel_runner_create)(
+                memory_manager.map_or(ptr::null(), |m| m as *const _),
+            )
+        };
+        if runner.is_null() {
+            return Err(JxlError::ParallelRunnerCreationFailed);
+        }
+
+        Ok(JxlResizableRunner { lib, runner })
+    }
+
+    /// Changes the number of worker threads. - JxlResizableParallelRunnerSetThreads()
+    ///
+    /// Call it between codec calls, e.g. once basic info is known.
+    pub fn set_threads(&self, num_threads: usize) {
+        unsafe { (self.lib.fns.resizable_parallel_runner_set_threads)(self.runner, num_threads) };
+    }
+
+    /// Suggests a thread count for an image of the given size. - JxlResizableParallelRunnerSuggestThreads()
+    pub fn suggest_threads(&self, xsize: u64, ysize: u64) -> u32 {
+        self.lib.suggest_threads(xsize, ysize)
+    }
+}
+
+// Runs and resizes are serialized by the runner's internal state mutex.
+unsafe impl Send for JxlResizableRunner {}
+unsafe impl Sync for JxlResizableRunner {}
+
+unsafe impl ParallelRunner for JxlResizableRunner {
+    fn runner_fn(&self) -> JxlParallelRunner {
+        self.lib.fns.resizable_parallel_runner
+    }
+
+    fn runner_opaque(&self) -> *mut c_void {
+        self.runner
+    }
+}
+
+impl Drop for JxlResizableRunner {
+    fn drop(&mut self) {
+        unsafe { (self.lib.fns.resizable_parallel_runner_destroy)(self.runner) };
+    }
+}
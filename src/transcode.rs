@@ -0,0 +1,755 @@
+use crate::decode_to_image;
+use crate::AnimationEncoder;
+use crate::AnimationFrame;
+use crate::DecodeOptions;
+use crate::EncodeOptions;
+use crate::FrameCrop;
+use crate::JxlAnimationHeader;
+use crate::JxlBlendMode;
+use crate::JxlBool;
+use crate::JxlDataType;
+use crate::JxlDecoder;
+use crate::JxlError;
+use crate::JxlLibrary;
+use crate::PixelBuffer;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::tiff::TiffEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// Reference slot that holds the canvas between frames.
+const CANVAS_SLOT: u32 = 1;
+
+/// How `jxl_to_png` brings float samples, which PNG can't store, into 16-bit range.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ToneMap {
+    /// Clamps to 0 to 1; in-range values keep 16-bit precision.
+    #[default]
+    Clamp,
+    /// Maps color samples `x` to `x / (1 + x)` (Reinhard), so highlights above 1 stay apart.
+    /// Darkens SDR content.
+    Reinhard,
+    /// Lets libjxl tone map to a display with this peak luminance in nits, then clamps.
+    /// Also applies to integer HDR images. - JxlDecoderSetDesiredIntensityTarget()
+    IntensityTarget(f32),
+}
+
+/// What happens to a frame's area after it was shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dispose {
+    /// Keep the frame on the canvas.
+    Keep,
+    /// Clear the frame's area to transparent.
+    Background,
+    /// Restore the canvas from before the frame.
+    Previous,
+}
+
+/// A GIF or APNG frame before it is handed to `AnimationEncoder`.
+struct SourceFrame {
+    pixels: Vec<u8>,
+    crop: FrameCrop,
+    duration: u32,
+    /// Blend over the canvas by alpha instead of replacing it.
+    blend: bool,
+    dispose: Dispose,
+}
+
+/// Converts an animated GIF to an animated JPEG XL.
+///
+/// Frame delays (in 1/100 s), the loop count, transparency and disposal are kept; frames stay
+/// cropped to their rectangle and are blended onto the canvas kept in a reference slot, so
+/// playback matches the GIF. Lossless with `EncodeOptions::default()`.
+///
+/// A zero delay becomes one tick, since a JPEG XL frame without duration is never shown on its
+/// own. Browsers slow down very short GIF delays; JPEG XL players don't.
+pub fn gif_to_jxl(
+    lib: &Arc<JxlLibrary>,
+    data: &[u8],
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, JxlError> {
+    let mut decode_options = gif::DecodeOptions::new();
+    decode_options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = decode_options
+        .read_info(data)
+        .map_err(|e| JxlError::InvalidInput(e.to_string()))?;
+    let width = u32::from(decoder.width());
+    let height = u32::from(decoder.height());
+
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder
+        .read_next_frame()
+        .map_err(|e| JxlError::InvalidInput(e.to_string()))?
+    {
+        let mut pixels = frame.buffer.to_vec();
+        // 투명 픽셀의 색은 보이지 않으므로 0으로 맞춰 압축률을 높임
+        let mut blend = false;
+        for pixel in pixels.chunks_exact_mut(4) {
+            if pixel[3] == 0 {
+                pixel.fill(0);
+                blend = true;
+            }
+        }
+        frames.push(SourceFrame {
+            pixels,
+            crop: FrameCrop {
+                x0: i32::from(frame.left),
+                y0: i32::from(frame.top),
+                width: u32::from(frame.width),
+                height: u32::from(frame.height),
+            },
+            duration: u32::from(frame.delay),
+            blend,
+            dispose: match frame.dispose {
+                gif::DisposalMethod::Background => Dispose::Background,
+                gif::DisposalMethod::Previous => Dispose::Previous,
+                _ => Dispose::Keep,
+            },
+        });
+    }
+
+    // NETSCAPE 확장의 값은 반복 횟수이므로 재생 횟수는 1을 더함
+    let num_loops = match decoder.repeat() {
+        gif::Repeat::Infinite => 0,
+        gif::Repeat::Finite(repeat) => u32::from(repeat) + 1,
+    };
+    let animation = JxlAnimationHeader {
+        tps_numerator: 100,
+        tps_denominator: 1,
+        num_loops,
+        have_timecodes: JxlBool::False,
+    };
+
+    let has_alpha =
+        frames.iter().any(|frame| frame.blend) || canvas_shows_through(&frames, width, height);
+    if !has_alpha {
+        for frame in &mut frames {
+            frame.pixels = strip_alpha(&frame.pixels, 4, 1);
+        }
+    }
+    let color_type = if has_alpha {
+        ExtendedColorType::Rgba8
+    } else {
+        ExtendedColorType::Rgb8
+    };
+    let options = with_icc_profile(options, decoder.icc_profile());
+    encode_frames(
+        lib, width, height, color_type, &animation, &frames, &options,
+    )
+}
+
+/// Converts an animated PNG to an animated JPEG XL.
+///
+/// Frame delays, the play count, alpha, bit depth, the ICC profile and the dispose/blend
+/// operations are kept, the same way as in `gif_to_jxl`. The default image is skipped when it
+/// is not part of the animation. Lossless with `EncodeOptions::default()`.
+pub fn apng_to_jxl(
+    lib: &Arc<JxlLibrary>,
+    data: &[u8],
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, JxlError> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| JxlError::InvalidInput(e.to_string()))?;
+    let info = reader.info();
+    let control = info
+        .animation_control
+        .ok_or_else(|| JxlError::InvalidInput("PNG is not animated".to_string()))?;
+    let width = info.width;
+    let height = info.height;
+    let hidden_default_image = info.frame_control.is_none();
+    let icc_profile = info.icc_profile.as_ref().map(|icc| icc.to_vec());
+    let (color_type, bit_depth) = reader.output_color_type();
+    let bytes_per_sample = if bit_depth == png::BitDepth::Sixteen {
+        2
+    } else {
+        1
+    };
+    let channels = color_type.samples();
+
+    let buffer_size = reader
+        .output_buffer_size()
+        .ok_or_else(|| JxlError::InvalidInput("PNG is too large".to_string()))?;
+    let mut buffer = vec![0; buffer_size];
+    if hidden_default_image {
+        reader
+            .next_frame(&mut buffer)
+            .map_err(|e| JxlError::InvalidInput(e.to_string()))?;
+    }
+
+    let mut controls = Vec::new();
+    let mut frames = Vec::new();
+    for _ in 0..control.num_frames {
+        let output = reader
+            .next_frame(&mut buffer)
+            .map_err(|e| JxlError::InvalidInput(e.to_string()))?;
+        let frame_control = reader
+            .info()
+            .frame_control
+            .ok_or_else(|| JxlError::InvalidInput("APNG frame without fcTL".to_string()))?;
+        frames.push(SourceFrame {
+            pixels: buffer[..output.buffer_size()].to_vec(),
+            crop: FrameCrop {
+                x0: i32::try_from(frame_control.x_offset)
+                    .map_err(|_| JxlError::InvalidInput("Frame offset too large".to_string()))?,
+                y0: i32::try_from(frame_control.y_offset)
+                    .map_err(|_| JxlError::InvalidInput("Frame offset too large".to_string()))?,
+                width: output.width,
+                height: output.height,
+            },
+            duration: 0,
+            blend: frame_control.blend_op == png::BlendOp::Over,
+            dispose: match frame_control.dispose_op {
+                png::DisposeOp::None => Dispose::Keep,
+                png::DisposeOp::Background => Dispose::Background,
+                png::DisposeOp::Previous => Dispose::Previous,
+            },
+        });
+        controls.push(frame_control);
+    }
+
+    let tps = apng_ticks_per_second(&controls);
+    let animation = JxlAnimationHeader {
+        tps_numerator: tps,
+        tps_denominator: 1,
+        num_loops: control.num_plays,
+        have_timecodes: JxlBool::False,
+    };
+    for (frame, frame_control) in frames.iter_mut().zip(&controls) {
+        frame.duration = apng_ticks(frame_control, tps);
+    }
+
+    let source_alpha = matches!(
+        color_type,
+        png::ColorType::GrayscaleAlpha | png::ColorType::Rgba
+    );
+    let has_alpha = source_alpha || canvas_shows_through(&frames, width, height);
+    for frame in &mut frames {
+        if !source_alpha && has_alpha {
+            frame.pixels = add_opaque_alpha(&frame.pixels, channels, bytes_per_sample);
+        }
+        if bytes_per_sample == 2 {
+            // PNG은 빅엔디언, PixelLayout은 네이티브 엔디언
+            for sample in frame.pixels.chunks_exact_mut(2) {
+                let value = u16::from_be_bytes([sample[0], sample[1]]);
+                sample.copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+    }
+
+    let color_channels = if channels <= 2 { 1 } else { 3 };
+    let color_type = match (color_channels, has_alpha, bytes_per_sample) {
+        (1, false, 1) => ExtendedColorType::L8,
+        (1, true, 1) => ExtendedColorType::La8,
+        (3, false, 1) => ExtendedColorType::Rgb8,
+        (3, true, 1) => ExtendedColorType::Rgba8,
+        (1, false, _) => ExtendedColorType::L16,
+        (1, true, _) => ExtendedColorType::La16,
+        (3, false, _) => ExtendedColorType::Rgb16,
+        _ => ExtendedColorType::Rgba16,
+    };
+    let options = with_icc_profile(options, icc_profile.as_deref());
+    encode_frames(
+        lib, width, height, color_type, &animation, &frames, &options,
+    )
+}
+
+/// Adds the frames, keeping the canvas in `CANVAS_SLOT` between them.
+///
+/// Each frame blends onto the canvas slot and is saved back to it unless it is disposed to
+/// the previous canvas. Disposing to the background adds a zero-duration layer that clears
+/// the frame's area before the next frame.
+fn encode_frames(
+    lib: &Arc<JxlLibrary>,
+    width: u32,
+    height: u32,
+    color_type: ExtendedColorType,
+    animation: &JxlAnimationHeader,
+    frames: &[SourceFrame],
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, JxlError> {
+    if frames.is_empty() {
+        return Err(JxlError::InvalidInput(
+            "Animation has no frames".to_string(),
+        ));
+    }
+    let mut encoder = AnimationEncoder::new(lib, width, height, color_type, animation, options)?;
+    let has_alpha = matches!(
+        color_type,
+        ExtendedColorType::La8
+            | ExtendedColorType::Rgba8
+            | ExtendedColorType::La16
+            | ExtendedColorType::Rgba16
+    );
+
+    for (index, frame) in frames.iter().enumerate() {
+        let is_last = index + 1 == frames.len();
+        let crop = (!covers_canvas(&frame.crop, width, height)).then_some(frame.crop);
+        encoder.add_frame(
+            &frame.pixels,
+            &AnimationFrame {
+                duration: frame.duration.max(1),
+                crop,
+                blend_mode: if frame.blend && has_alpha {
+                    JxlBlendMode::Blend
+                } else {
+                    JxlBlendMode::Replace
+                },
+                blend_source: CANVAS_SLOT,
+                save_as_reference: if frame.dispose == Dispose::Previous {
+                    0
+                } else {
+                    CANVAS_SLOT
+                },
+                ..AnimationFrame::default()
+            },
+        )?;
+
+        if frame.dispose == Dispose::Background && !is_last {
+            encoder.add_frame(
+                &vec![0; frame.pixels.len()],
+                &AnimationFrame {
+                    duration: 0,
+                    crop,
+                    blend_mode: JxlBlendMode::Replace,
+                    blend_source: CANVAS_SLOT,
+                    save_as_reference: CANVAS_SLOT,
+                    ..AnimationFrame::default()
+                },
+            )?;
+        }
+    }
+    encoder.finish()
+}
+
+fn covers_canvas(crop: &FrameCrop, width: u32, height: u32) -> bool {
+    crop.x0 == 0 && crop.y0 == 0 && crop.width == width && crop.height == height
+}
+
+/// Whether the empty (transparent) canvas can become visible: the first frame leaves part of
+/// it uncovered, or a frame before the last is disposed.
+fn canvas_shows_through(frames: &[SourceFrame], width: u32, height: u32) -> bool {
+    let Some((last, rest)) = frames.split_last() else {
+        return false;
+    };
+    let first = rest.first().unwrap_or(last);
+    !covers_canvas(&first.crop, width, height)
+        || rest.iter().any(|frame| frame.dispose != Dispose::Keep)
+}
+
+fn strip_alpha(pixels: &[u8], channels: usize, bytes_per_sample: usize) -> Vec<u8> {
+    let color_len = (channels - 1) * bytes_per_sample;
+    pixels
+        .chunks_exact(channels * bytes_per_sample)
+        .flat_map(|pixel| &pixel[..color_len])
+        .copied()
+        .collect()
+}
+
+fn add_opaque_alpha(pixels: &[u8], channels: usize, bytes_per_sample: usize) -> Vec<u8> {
+    let pixel_len = channels * bytes_per_sample;
+    let mut out = Vec::with_capacity(pixels.len() / pixel_len * (pixel_len + bytes_per_sample));
+    for pixel in pixels.chunks_exact(pixel_len) {
+        out.extend_from_slice(pixel);
+        out.extend(std::iter::repeat_n(0xFF, bytes_per_sample));
+    }
+    out
+}
+
+fn with_icc_profile(options: &EncodeOptions, icc_profile: Option<&[u8]>) -> EncodeOptions {
+    let mut options = options.clone();
+    if options.icc_profile.is_none() {
+        options.icc_profile = icc_profile.map(<[u8]>::to_vec);
+    }
+    options
+}
+
+/// Converts an APNG delay to ticks at `tps`, rounding when the denominator doesn't divide it.
+fn apng_ticks(frame_control: &png::FrameControl, tps: u32) -> u32 {
+    // 분모가 0이면 1/100초 단위
+    let den = match frame_control.delay_den {
+        0 => 100,
+        den => u64::from(den),
+    };
+    let ticks = (u64::from(frame_control.delay_num) * u64::from(tps) + den / 2) / den;
+    u32::try_from(ticks).unwrap_or(u32::MAX)
+}
+
+/// Picks a tick rate that represents every APNG delay exactly: the least common multiple of
+/// the delay denominators, or milliseconds if that gets unreasonably large.
+fn apng_ticks_per_second(controls: &[png::FrameControl]) -> u32 {
+    let mut tps = 1u64;
+    for frame_control in controls {
+        let den = match frame_control.delay_den {
+            0 => 100,
+            den => u64::from(den),
+        };
+        tps = tps / gcd(tps, den) * den;
+        if tps > 1_000_000 {
+            return 1000;
+        }
+    }
+    u32::try_from(tps).unwrap_or(1000)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Converts a JPEG XL image to PNG, keeping its ICC profile and 8 or 16-bit depth.
+///
+/// Float images become 16-bit through `tone_map`. Animations become APNG with their delays
+/// and loop count.
+pub fn jxl_to_png(
+    lib: &Arc<JxlLibrary>,
+    data: &[u8],
+    tone_map: ToneMap,
+) -> Result<Vec<u8>, JxlError> {
+    let options = DecodeOptions {
+        extra_channels: false,
+        desired_intensity_target: match tone_map {
+            ToneMap::IntensityTarget(nits) => Some(nits),
+            _ => None,
+        },
+        ..DecodeOptions::default()
+    };
+    let image = decode_to_image(lib, data, &options)?;
+    let channels = image.pixel_format.num_channels as usize;
+    let color_type = match channels {
+        1 => png::ColorType::Grayscale,
+        2 => png::ColorType::GrayscaleAlpha,
+        3 => png::ColorType::Rgb,
+        _ => png::ColorType::Rgba,
+    };
+    let bit_depth = match image.pixel_format.data_type {
+        JxlDataType::Uint8 => png::BitDepth::Eight,
+        _ => png::BitDepth::Sixteen,
+    };
+    let animation = &image.basic_info.animation;
+    let animated = image.frames.len() > 1;
+
+    let mut info = png::Info::with_size(image.width(), image.height());
+    info.icc_profile = image.icc_profile.as_deref().map(Cow::Borrowed);
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::with_info(&mut out, info).map_err(export_error)?;
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    if animated {
+        let num_frames = u32::try_from(image.frames.len())
+            .map_err(|_| JxlError::ExportFailed("Too many frames".to_string()))?;
+        encoder
+            .set_animated(num_frames, animation.num_loops)
+            .map_err(export_error)?;
+    }
+    let mut writer = encoder.write_header().map_err(export_error)?;
+    for frame in &image.frames {
+        if animated {
+            let (num, den) = png_delay(animation, frame.header.duration);
+            writer.set_frame_delay(num, den).map_err(export_error)?;
+        }
+        // PNG은 16비트 샘플을 빅엔디언으로 저장
+        let bytes = match &frame.pixels {
+            PixelBuffer::U8(pixels) => Cow::Borrowed(pixels.as_slice()),
+            PixelBuffer::U16(pixels) => {
+                Cow::Owned(pixels.iter().flat_map(|v| v.to_be_bytes()).collect())
+            }
+            PixelBuffer::F32(pixels) => Cow::Owned(
+                float_to_u16(pixels, channels, tone_map)
+                    .into_iter()
+                    .flat_map(u16::to_be_bytes)
+                    .collect(),
+            ),
+            PixelBuffer::F16(_) => return Err(JxlError::PixelFormatError),
+        };
+        writer.write_image_data(&bytes).map_err(export_error)?;
+    }
+    writer.finish().map_err(export_error)?;
+    Ok(out)
+}
+
+/// Converts a JPEG XL image to JPEG.
+///
+/// Files transcoded from JPEG (with a `jbrd` box) give back the original JPEG bit for bit.
+/// Anything else is decoded to 8 bits and re-encoded at `quality` (1 to 100), with alpha
+/// flattened onto white and the ICC profile kept.
+pub fn jxl_to_jpeg(lib: &Arc<JxlLibrary>, data: &[u8], quality: u8) -> Result<Vec<u8>, JxlError> {
+    if !(1..=100).contains(&quality) {
+        return Err(JxlError::InvalidInput(format!(
+            "JPEG quality must be 1 to 100, got {quality}"
+        )));
+    }
+    let decoder = JxlDecoder::with_library(lib.clone())?;
+    if let Some(jpeg) = decoder.reconstruct_jpeg(data)? {
+        return Ok(jpeg);
+    }
+
+    let options = DecodeOptions {
+        data_type: Some(JxlDataType::Uint8),
+        extra_channels: false,
+        ..DecodeOptions::default()
+    };
+    let image = decoder.decode(data, &options)?;
+    let frame = image.frames.first().ok_or(JxlError::DecodingFailed)?;
+    let PixelBuffer::U8(pixels) = &frame.pixels else {
+        return Err(JxlError::PixelFormatError);
+    };
+    let channels = image.pixel_format.num_channels as usize;
+    let (pixels, color_type) = match channels {
+        1 => (Cow::Borrowed(pixels.as_slice()), ExtendedColorType::L8),
+        2 => (Cow::Owned(flatten_alpha(pixels, 2)), ExtendedColorType::L8),
+        3 => (Cow::Borrowed(pixels.as_slice()), ExtendedColorType::Rgb8),
+        _ => (
+            Cow::Owned(flatten_alpha(pixels, 4)),
+            ExtendedColorType::Rgb8,
+        ),
+    };
+
+    let mut out = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(&mut out, quality);
+    if let Some(icc_profile) = image.icc_profile.clone() {
+        encoder
+            .set_icc_profile(icc_profile)
+            .map_err(|e| JxlError::ExportFailed(e.to_string()))?;
+    }
+    encoder
+        .write_image(&pixels, image.width(), image.height(), color_type)
+        .map_err(|e| JxlError::ExportFailed(e.to_string()))?;
+    Ok(out)
+}
+
+/// Converts a JPEG XL image to TIFF, keeping its ICC profile, 8 or 16-bit depth and float
+/// samples. Grayscale with alpha and float grayscale are widened to RGB, which the TIFF
+/// encoder needs.
+pub fn jxl_to_tiff(lib: &Arc<JxlLibrary>, data: &[u8]) -> Result<Vec<u8>, JxlError> {
+    let options = DecodeOptions {
+        extra_channels: false,
+        ..DecodeOptions::default()
+    };
+    let image = decode_to_image(lib, data, &options)?;
+    let frame = image.frames.first().ok_or(JxlError::DecodingFailed)?;
+    let channels = image.pixel_format.num_channels as usize;
+    let widen = channels == 2 || (channels == 1 && matches!(frame.pixels, PixelBuffer::F32(_)));
+    let pixels = if widen {
+        match &frame.pixels {
+            PixelBuffer::U8(pixels) => PixelBuffer::U8(gray_to_rgb(pixels, channels)),
+            PixelBuffer::U16(pixels) => PixelBuffer::U16(gray_to_rgb(pixels, channels)),
+            PixelBuffer::F32(pixels) => PixelBuffer::F32(gray_to_rgb(pixels, channels)),
+            PixelBuffer::F16(_) => return Err(JxlError::PixelFormatError),
+        }
+    } else {
+        frame.pixels.clone()
+    };
+    let channels = if widen { channels + 2 } else { channels };
+    let color_type = match (&pixels, channels) {
+        (PixelBuffer::U8(_), 1) => ExtendedColorType::L8,
+        (PixelBuffer::U8(_), 3) => ExtendedColorType::Rgb8,
+        (PixelBuffer::U8(_), _) => ExtendedColorType::Rgba8,
+        (PixelBuffer::U16(_), 1) => ExtendedColorType::L16,
+        (PixelBuffer::U16(_), 3) => ExtendedColorType::Rgb16,
+        (PixelBuffer::U16(_), _) => ExtendedColorType::Rgba16,
+        (PixelBuffer::F32(_), 3) => ExtendedColorType::Rgb32F,
+        (PixelBuffer::F32(_), _) => ExtendedColorType::Rgba32F,
+        _ => return Err(JxlError::PixelFormatError),
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut out);
+    if let Some(icc_profile) = image.icc_profile.clone() {
+        encoder
+            .set_icc_profile(icc_profile)
+            .map_err(|e| JxlError::ExportFailed(e.to_string()))?;
+    }
+    encoder
+        .write_image(pixels.as_bytes(), image.width(), image.height(), color_type)
+        .map_err(|e| JxlError::ExportFailed(e.to_string()))?;
+    Ok(out.into_inner())
+}
+
+fn export_error(e: png::EncodingError) -> JxlError {
+    JxlError::ExportFailed(e.to_string())
+}
+
+/// Converts a frame duration in ticks to an APNG delay fraction, in milliseconds if the exact
+/// fraction doesn't fit.
+fn png_delay(animation: &JxlAnimationHeader, ticks: u32) -> (u16, u16) {
+    let num = u64::from(ticks) * u64::from(animation.tps_denominator);
+    let den = u64::from(animation.tps_numerator.max(1));
+    let divisor = gcd(num, den).max(1);
+    match (u16::try_from(num / divisor), u16::try_from(den / divisor)) {
+        (Ok(num), Ok(den)) => (num, den),
+        _ => {
+            let millis = (num * 1000 + den / 2) / den;
+            (u16::try_from(millis).unwrap_or(u16::MAX), 1000)
+        }
+    }
+}
+
+fn float_to_u16(pixels: &[f32], channels: usize, tone_map: ToneMap) -> Vec<u16> {
+    let alpha = if channels == 2 || channels == 4 {
+        channels - 1
+    } else {
+        channels
+    };
+    pixels
+        .chunks_exact(channels)
+        .flat_map(|pixel| {
+            pixel.iter().enumerate().map(move |(channel, &value)| {
+                let value = if tone_map == ToneMap::Reinhard && channel != alpha {
+                    let value = value.max(0.0);
+                    value / (1.0 + value)
+                } else {
+                    value
+                };
+                (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+            })
+        })
+        .collect()
+}
+
+/// Composites gray+alpha or RGBA pixels onto white and drops the alpha.
+fn flatten_alpha(pixels: &[u8], channels: usize) -> Vec<u8> {
+    pixels
+        .chunks_exact(channels)
+        .flat_map(|pixel| {
+            let (color, alpha) = pixel.split_at(channels - 1);
+            let alpha = u32::from(alpha[0]);
+            color
+                .iter()
+                .map(move |&c| ((u32::from(c) * alpha + 255 * (255 - alpha) + 127) / 255) as u8)
+        })
+        .collect()
+}
+
+/// Widens gray or gray+alpha pixels to RGB or RGBA.
+fn gray_to_rgb<T: Copy>(pixels: &[T], channels: usize) -> Vec<T> {
+    pixels
+        .chunks_exact(channels)
+        .flat_map(|pixel| {
+            let gray = pixel[0];
+            [gray, gray, gray].into_iter().chain(pixel.get(1).copied())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(crop: FrameCrop, dispose: Dispose) -> SourceFrame {
+        SourceFrame {
+            pixels: Vec::new(),
+            crop,
+            duration: 10,
+            blend: false,
+            dispose,
+        }
+    }
+
+    #[test]
+    fn test_canvas_shows_through() {
+        let full = FrameCrop {
+            x0: 0,
+            y0: 0,
+            width: 8,
+            height: 8,
+        };
+        let part = FrameCrop {
+            x0: 2,
+            y0: 2,
+            width: 4,
+            height: 4,
+        };
+        assert!(!canvas_shows_through(
+            &[frame(full, Dispose::Keep), frame(part, Dispose::Background)],
+            8,
+            8
+        ));
+        assert!(canvas_shows_through(
+            &[frame(full, Dispose::Background), frame(part, Dispose::Keep)],
+            8,
+            8
+        ));
+        assert!(canvas_shows_through(&[frame(part, Dispose::Keep)], 8, 8));
+    }
+
+    #[test]
+    fn test_alpha_helpers() {
+        assert_eq!(
+            strip_alpha(&[1, 2, 3, 4, 5, 6, 7, 8], 4, 1),
+            [1, 2, 3, 5, 6, 7]
+        );
+        assert_eq!(strip_alpha(&[1, 2, 3, 4], 2, 2), [1, 2]);
+        assert_eq!(add_opaque_alpha(&[1, 2], 1, 1), [1, 0xFF, 2, 0xFF]);
+        assert_eq!(add_opaque_alpha(&[1, 2], 1, 2), [1, 2, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_apng_ticks_per_second() {
+        let delay = |delay_num, delay_den| png::FrameControl {
+            delay_num,
+            delay_den,
+            ..png::FrameControl::default()
+        };
+        assert_eq!(apng_ticks_per_second(&[delay(1, 10), delay(1, 0)]), 100);
+        assert_eq!(apng_ticks_per_second(&[delay(1, 30), delay(1, 25)]), 150);
+        assert_eq!(
+            apng_ticks_per_second(&[delay(1, 65521), delay(1, 65519)]),
+            1000
+        );
+        assert_eq!(apng_ticks(&delay(1, 30), 150), 5);
+        assert_eq!(apng_ticks(&delay(3, 25), 150), 18);
+        assert_eq!(apng_ticks(&delay(7, 0), 100), 7);
+        assert_eq!(apng_ticks(&delay(1, 3), 1000), 333);
+    }
+
+    #[test]
+    fn test_png_delay() {
+        let animation = JxlAnimationHeader {
+            tps_numerator: 100,
+            tps_denominator: 1,
+            num_loops: 0,
+            have_timecodes: JxlBool::False,
+        };
+        assert_eq!(png_delay(&animation, 10), (1, 10));
+        assert_eq!(png_delay(&animation, 0), (0, 1));
+        let ntsc = JxlAnimationHeader {
+            tps_numerator: 30000,
+            tps_denominator: 1001,
+            ..animation
+        };
+        assert_eq!(png_delay(&ntsc, 1), (1001, 30000));
+        let fine = JxlAnimationHeader {
+            tps_numerator: 1_000_003,
+            ..animation
+        };
+        assert_eq!(png_delay(&fine, 5000), (5, 1000));
+    }
+
+    #[test]
+    fn test_export_pixel_helpers() {
+        assert_eq!(
+            float_to_u16(&[0.5, 2.0, -1.0, 1.0], 4, ToneMap::Clamp),
+            [32768, 65535, 0, 65535]
+        );
+        assert_eq!(
+            float_to_u16(&[1.0, 3.0, 0.0, 0.5], 4, ToneMap::Reinhard),
+            [32768, 49151, 0, 32768]
+        );
+        assert_eq!(
+            flatten_alpha(&[0, 0, 0, 255, 0, 0, 0, 0], 4),
+            [0, 0, 0, 255, 255, 255]
+        );
+        assert_eq!(flatten_alpha(&[100, 128], 2), [177]);
+        assert_eq!(gray_to_rgb(&[7u16, 9], 2), [7, 7, 7, 9]);
+        assert_eq!(gray_to_rgb(&[0.5f32], 1), [0.5, 0.5, 0.5]);
+    }
+}
@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+
+use jxl_dynlink::JxlLibrary;
+use std::sync::{Arc, OnceLock};
+
+/// Loads libjxl once per test binary via `JxlLibrary::discover` (set `JXL_DYNLINK_PATH` to
+/// point the tests at a specific build).
+pub fn library() -> Arc<JxlLibrary> {
+    static LIBRARY: OnceLock<Arc<JxlLibrary>> = OnceLock::new();
+    LIBRARY
+        .get_or_init(|| JxlLibrary::discover().expect("Failed to find libjxl"))
+        .clone()
+}
+
+/// Same as `library`, but returns `None` instead of panicking when libjxl is missing.
+pub fn try_library() -> Option<Arc<JxlLibrary>> {
+    match JxlLibrary::discover() {
+        Ok(lib) => Some(lib),
+        Err(e) => {
+            println!("libjxl not found: {:?}", e);
+            None
+        }
+    }
+}
@@ -7,19 +7,38 @@ use std::{
     ptr,
 };
 
-const DLL_PATH: &str = "dlls/jxl.dll";
+mod common;
+use common::library;
 
 #[test]
 fn test_decoder_creation_and_version() -> Result<(), JxlError> {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let (major, minor, patch) = decoder.version()?;
     println!("JxlDecoder version: {}.{}.{}", major, minor, patch);
     Ok(())
 }
 
+#[test]
+fn test_shared_library() -> Result<(), JxlError> {
+    let lib = JxlLibrary::discover()?;
+    let decoders = (0..4)
+        .map(|_| JxlDecoder::with_library(lib.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let encoder = JxlEncoder::with_library(lib.clone(), None)?;
+
+    assert_eq!(decoders[0].version()?, lib.version());
+    assert_eq!(encoder.version()?, lib.version());
+    assert_eq!(std::sync::Arc::strong_count(&lib), 6);
+
+    drop(decoders);
+    drop(encoder);
+    assert_eq!(std::sync::Arc::strong_count(&lib), 1);
+    Ok(())
+}
+
 #[test]
 fn test_signature_check() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
 
     // Valid JPEG XL signature (container format)
     let valid_jxl_data = [
@@ -56,15 +75,15 @@ fn test_signature_check() {
 
 #[test]
 fn test_decoder_reset_and_rewind() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
-    decoder.reset();
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
+    decoder.reset().expect("Failed to reset decoder");
     decoder.rewind();
-    // These functions don't return anything, so we just ensure they don't panic
+    // rewind doesn't return anything, so we just ensure it doesn't panic
 }
 
 #[test]
 fn test_frame_operations() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
 
     let buffer = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
     assert!(decoder.set_input(&buffer).is_ok());
@@ -74,29 +93,75 @@ fn test_frame_operations() {
     if frame_name.is_err() {
         // If we can't get frame name, we can't set it either
         println!("Failed to get frame name: {:?}", frame_name);
-        return;
     }
 }
 
 #[test]
 fn test_parallel_runner() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let mut decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
 
     unsafe extern "C" fn dummy_runner(
-        _opaque_runner: *mut c_void,
-        _opaque_jpegxl: *mut c_void,
+        _runner_opaque: *mut c_void,
+        jpegxl_opaque: *mut c_void,
+        init: JxlParallelRunInit,
+        func: JxlParallelRunFunction,
+        start_range: u32,
+        end_range: u32,
     ) -> c_int {
-        0 // Just a dummy implementation
+        // Runs everything on the calling thread
+        let result = init(jpegxl_opaque, 1);
+        if result != 0 {
+            return result;
+        }
+        for value in start_range..end_range {
+            func(jpegxl_opaque, value, 0);
+        }
+        0
     }
 
-    assert!(decoder
-        .set_parallel_runner(Some(dummy_runner), ptr::null_mut())
-        .is_ok());
+    assert!(
+        unsafe { decoder.set_parallel_runner_raw(Some(dummy_runner), ptr::null_mut()) }.is_ok()
+    );
+}
+
+#[test]
+fn test_thread_runner() -> Result<(), JxlError> {
+    let lib = library();
+    let threads = JxlThreadsLibrary::discover_near(&lib)?;
+
+    let runner = std::sync::Arc::new(JxlThreadRunner::with_default_threads(threads.clone())?);
+    let mut decoder = JxlDecoder::with_library(lib.clone())?;
+    let mut encoder = JxlEncoder::with_library(lib, None)?;
+    decoder.set_parallel_runner(runner.clone())?;
+    encoder.set_parallel_runner(runner.clone())?;
+    assert_eq!(std::sync::Arc::strong_count(&runner), 3);
+
+    let resizable = std::sync::Arc::new(JxlResizableRunner::new(threads)?);
+    resizable.set_threads(resizable.suggest_threads(1024, 1024) as usize);
+    decoder.set_parallel_runner(resizable.clone())?;
+    assert_eq!(std::sync::Arc::strong_count(&runner), 2);
+
+    encoder.reset()?;
+    assert_eq!(std::sync::Arc::strong_count(&runner), 1);
+    drop(decoder);
+    assert_eq!(std::sync::Arc::strong_count(&resizable), 1);
+    Ok(())
+}
+
+#[test]
+fn test_rust_parallel_runner() -> Result<(), JxlError> {
+    let runner = std::sync::Arc::new(RustParallelRunner::new(2)?);
+    let mut decoder = JxlDecoder::with_library(library())?;
+    let mut encoder = JxlEncoder::with_library(library(), None)?;
+    decoder.set_parallel_runner(runner.clone())?;
+    encoder.set_parallel_runner(runner.clone())?;
+    assert_eq!(std::sync::Arc::strong_count(&runner), 3);
+    Ok(())
 }
 
 #[test]
 fn test_size_hint_and_events() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let hint = decoder.size_hint_basic_info();
     assert!(hint > 0, "Size hint should be greater than 0");
 
@@ -107,7 +172,7 @@ fn test_size_hint_and_events() {
 
 #[test]
 fn test_orientation_and_alpha_settings() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     assert!(decoder.set_keep_orientation(true).is_ok());
     assert!(decoder.set_unpremultiply_alpha(true).is_ok());
     assert!(decoder.set_render_spotcolors(false).is_ok());
@@ -116,7 +181,7 @@ fn test_orientation_and_alpha_settings() {
 
 #[test]
 fn test_input_processing() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let dummy_input = vec![0xFF, 0x0A]; // Dummy JPEG XL data
     assert!(decoder.set_input(&dummy_input).is_ok());
 
@@ -133,7 +198,7 @@ fn test_input_processing() {
 
 #[test]
 fn test_basic_info_and_color_encoding() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     // We need to set some input and process it before we can get basic info
     let dummy_input = vec![0xFF, 0x0A]; // Dummy JPEG XL data
     decoder
@@ -154,7 +219,7 @@ fn test_basic_info_and_color_encoding() {
 
 #[test]
 fn test_extra_channel_info() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     // Assuming we've processed enough input to have extra channel info
     match decoder.get_extra_channel_info(0) {
         Ok(info) => println!("Extra channel info: {:?}", info),
@@ -169,7 +234,7 @@ fn test_extra_channel_info() {
 
 #[test]
 fn test_icc_profile() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     // Assuming we've processed enough input to have ICC profile info
     let mut size: usize = 0;
     match decoder.get_icc_profile_size(JxlColorProfileTarget::Original, Some(&mut size)) {
@@ -186,7 +251,7 @@ fn test_icc_profile() {
 //need jxl file with icc profile
 #[test]
 fn test_color_management() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
     decoder.set_input(&file).expect("Failed to set input");
     decoder.process_input().expect("Failed to process input");
@@ -205,13 +270,14 @@ fn test_color_management() {
     assert!(decoder.set_desired_intensity_target(100.0).is_ok());
 
     let cms = JxlCmsInterface::default();
-    assert!(decoder.set_cms(&cms).is_ok());
+    assert!(decoder.set_cms(cms).is_ok());
 }
 
 //need jxl file with metadata
 #[test]
+#[allow(unreachable_code)]
 fn test_preview_and_frame_info() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
     decoder.set_input(&file).expect("Failed to set input");
     decoder.process_input().expect("Failed to process input");
@@ -223,7 +289,7 @@ fn test_preview_and_frame_info() {
         Ok(size) => println!("Preview buffer size: {}", size),
         Err(e) => println!("Failed to get preview buffer size: {:?}", e),
     }
-    return ();
+    return;
 
     let mut buffer = vec![0u8; 1024]; // Dummy buffer
     assert!(decoder.set_preview_out_buffer(&format, &mut buffer).is_ok());
@@ -247,7 +313,7 @@ fn test_preview_and_frame_info() {
 //need jxl file with metadata
 #[test]
 fn test_image_out_buffer() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let format = JxlPixelFormat::default();
 
     match decoder.image_out_buffer_size(&format) {
@@ -257,45 +323,13 @@ fn test_image_out_buffer() {
 
     let mut buffer = vec![0u8; 1024]; // Dummy buffer
     assert!(decoder.set_image_out_buffer(&format, &mut buffer).is_ok());
-
-    // Test image out callback
-    decoder
-        .set_image_out_callback(&format, |_, x, y, num_pixels, _| {
-            println!(
-                "Callback called for region: x={}, y={}, num_pixels={}",
-                x, y, num_pixels
-            );
-        })
-        .expect("Failed to set image out callback");
-
-    // Test multithreaded image out callback
-    decoder
-        .set_multithreaded_image_out_callback(
-            &format,
-            |_, num_threads, num_pixels_per_thread| {
-                println!(
-                    "Init callback: num_threads={}, num_pixels_per_thread={}",
-                    num_threads, num_pixels_per_thread
-                );
-                Box::into_raw(Box::new(())) as *mut c_void
-            },
-            |_, thread_id, x, y, num_pixels, _| {
-                println!(
-                    "Run callback: thread_id={}, x={}, y={}, num_pixels={}",
-                    thread_id, x, y, num_pixels
-                );
-            },
-            Some(|_| println!("Destroy callback called")),
-            ptr::null_mut(),
-        )
-        .expect("Failed to set multithreaded image out callback");
 }
 
 //need jxl with extra channel
 #[test]
+#[ignore = "needs a jxl file with an extra channel"]
 fn test_extra_channel_buffer() {
-    return ();
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let format = JxlPixelFormat::default();
 
     match decoder.extra_channel_buffer_size(&format, 0) {
@@ -311,7 +345,7 @@ fn test_extra_channel_buffer() {
 
 #[test]
 fn test_jpeg_reconstruction() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let mut buffer = vec![0u8; 1024]; // Dummy buffer
     assert!(decoder.set_jpeg_buffer(&mut buffer).is_ok());
     let remaining = decoder.release_jpeg_buffer();
@@ -320,9 +354,9 @@ fn test_jpeg_reconstruction() {
 
 #[test]
 fn test_box_operations() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let _decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
 
-    let mut buffer = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
+    let _buffer = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
 
     // Set box buffer - pass
 
@@ -332,7 +366,7 @@ fn test_box_operations() {
 
     // assert!(decoder.set_decompress_boxes(true).is_ok());
 
-    // match decoder.get_box_type() {
+    // match decoder.get_box_type(false) {
     //     Ok(box_type) => println!("Box type: {:?}", box_type),
     //     Err(e) => println!("Failed to get box type: {:?}", e),
     // }
@@ -348,9 +382,38 @@ fn test_box_operations() {
     // }
 }
 
+#[test]
+fn test_decode_to_image() -> Result<(), JxlError> {
+    let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
+    let image = decode_to_image(&library(), &file, &DecodeOptions::default())?;
+
+    assert!(!image.frames.is_empty());
+    assert_eq!(
+        image.extra_channels.len(),
+        image.basic_info.num_extra_channels as usize
+    );
+    let frame = &image.frames[0];
+    let expected =
+        image.width() as usize * image.height() as usize * image.pixel_format.num_channels as usize;
+    assert_eq!(frame.pixels.len(), expected);
+    assert_eq!(frame.extra_channels.len(), image.extra_channels.len());
+
+    let float = decode_to_image(
+        &library(),
+        &file,
+        &DecodeOptions {
+            data_type: Some(JxlDataType::Float),
+            ..Default::default()
+        },
+    )?;
+    assert!(matches!(float.frames[0].pixels, PixelBuffer::F32(_)));
+    assert_eq!(float.frames[0].pixels.len(), expected);
+    Ok(())
+}
+
 #[test]
 fn test_progressive_decoding() {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
     let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
     decoder.set_input(&file).expect("Failed to set input");
     decoder.process_input().expect("Failed to process input");
@@ -367,7 +430,7 @@ fn test_progressive_decoding() {
 
 #[test]
 fn test_image_out_bit_depth() -> Result<(), JxlError> {
-    let decoder = JxlDecoder::new(DLL_PATH).expect("Failed to create decoder");
+    let decoder = JxlDecoder::with_library(library()).expect("Failed to create decoder");
 
     // JPEG XL 파일 읽기
     let mut file = File::open("test_imgs/test.jxl").expect("Failed to open test.jxl");
@@ -382,7 +445,6 @@ fn test_image_out_bit_depth() -> Result<(), JxlError> {
     decoder.set_input(&jxl_data).expect("Failed to set input");
 
     let mut basic_info = None;
-    let mut frame_header = None;
 
     // 디코딩 프로세스 실행
     loop {
@@ -395,12 +457,10 @@ fn test_image_out_bit_depth() -> Result<(), JxlError> {
                 break; // 기본 정보를 얻었으므로 루프 종료
             }
             Ok(JxlDecoderStatus::Frame) => {
-                frame_header = Some(
-                    decoder
-                        .get_frame_header()
-                        .expect("Failed to get frame header"),
-                );
-                println!("Frame header: {:?}", frame_header.as_ref().unwrap());
+                let frame_header = decoder
+                    .get_frame_header()
+                    .expect("Failed to get frame header");
+                println!("Frame header: {:?}", frame_header);
                 break; // 프레임 헤더를 얻었으므로 루프 종료
             }
             Ok(JxlDecoderStatus::NeedMoreInput) => {
@@ -521,14 +581,13 @@ fn create_pixel_format_from_basic_info(info: &JxlBasicInfo) -> JxlPixelFormat {
 }
 
 fn decode_buffer(decoder: &JxlDecoder, format: &JxlPixelFormat) -> Result<Vec<u8>, JxlError> {
-    let mut buffer_size = 0;
     let mut buffer = Vec::new();
 
     loop {
         match decoder.process_input() {
             Ok(JxlDecoderStatus::Frame) => {
                 // JXL_DEC_FRAME 이벤트 발생 시 버퍼 크기 계산
-                buffer_size = match decoder.image_out_buffer_size(format) {
+                let buffer_size = match decoder.image_out_buffer_size(format) {
                     Ok(size) => {
                         println!("Buffer size calculated: {}", size);
                         size
@@ -569,3 +628,478 @@ fn read_jxl_file(file_path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
     file.read_to_end(&mut buffer)?;
     Ok(buffer)
 }
+
+#[test]
+fn test_stream_decoder() -> Result<(), JxlError> {
+    let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
+    let expected = decode_to_image(&library(), &file, &DecodeOptions::default())?;
+
+    let mut stream = JxlStreamDecoder::new(
+        JxlDecoder::with_library(library())?,
+        std::io::Cursor::new(&file),
+    )
+    .with_chunk_size(1024);
+    let image = stream.decode(&DecodeOptions::default())?;
+    assert_eq!(image.frames.len(), expected.frames.len());
+    assert_eq!(image.frames[0].pixels, expected.frames[0].pixels);
+    assert!(stream.buffered_len() <= file.len());
+
+    let truncated = JxlStreamDecoder::new(
+        JxlDecoder::with_library(library())?,
+        &file[..file.len() / 2],
+    )
+    .decode(&DecodeOptions::default());
+    assert!(truncated.is_err());
+
+    let mut events =
+        JxlStreamDecoder::new(JxlDecoder::with_library(library())?, &file[..]).with_chunk_size(16);
+    events
+        .decoder()
+        .subscribe_events(JxlDecoderStatus::BasicInfo.bits())?;
+    assert_eq!(events.process()?, JxlDecoderStatus::BasicInfo);
+    assert_eq!(events.decoder().get_basic_info()?.xsize, expected.width());
+    Ok(())
+}
+
+#[test]
+fn test_image_out_callback_rows() -> Result<(), JxlError> {
+    let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
+    let expected = decode_to_image(
+        &library(),
+        &file,
+        &DecodeOptions {
+            data_type: Some(JxlDataType::Uint8),
+            num_channels: Some(4),
+            extra_channels: false,
+            ..DecodeOptions::default()
+        },
+    )?;
+    let width = expected.width() as usize;
+    let format = JxlPixelFormat {
+        num_channels: 4,
+        data_type: JxlDataType::Uint8,
+        endianness: JxlEndianness::NativeEndian,
+        align: 0,
+    };
+
+    let decoder = JxlDecoder::with_library(library())?;
+    decoder.subscribe_events(JxlDecoderStatus::FullImage.bits())?;
+    decoder.set_input(&file)?;
+    decoder.close_input();
+
+    let pixels = std::sync::Arc::new(std::sync::Mutex::new(vec![
+        0u8;
+        expected.frames[0]
+            .pixels
+            .as_bytes()
+            .len()
+    ]));
+    loop {
+        match decoder.process_input()? {
+            JxlDecoderStatus::NeedImageOutBuffer => {
+                let out = pixels.clone();
+                decoder.set_image_out_callback(&format, move |x, y, row: &[u8]| {
+                    let start = (y * width + x) * 4;
+                    out.lock().unwrap()[start..start + row.len()].copy_from_slice(row);
+                })?;
+                let again = decoder.set_image_out_callback(&format, |_, _, _: &[u8]| {});
+                assert!(matches!(again, Err(JxlError::UnsupportedOperation(_))));
+                assert!(decoder
+                    .set_image_out_callback(&format, |_, _, _: &[f32]| {})
+                    .is_err());
+            }
+            JxlDecoderStatus::FullImage => break,
+            JxlDecoderStatus::NeedMoreInput => return Err(JxlError::NotEnoughInput),
+            _ => {}
+        }
+    }
+    assert_eq!(
+        pixels.lock().unwrap().as_slice(),
+        expected.frames[0].pixels.as_bytes()
+    );
+
+    // reset 이 콜백을 해제함
+    decoder.reset()?;
+    assert_eq!(std::sync::Arc::strong_count(&pixels), 1);
+    Ok(())
+}
+
+#[test]
+fn test_multithreaded_image_out_callback() -> Result<(), JxlError> {
+    type Row = (usize, usize, Vec<u8>);
+    struct Staging {
+        rows: std::sync::Arc<std::sync::Mutex<Vec<Row>>>,
+        pending: Vec<Row>,
+    }
+    impl Drop for Staging {
+        fn drop(&mut self) {
+            self.rows.lock().unwrap().append(&mut self.pending);
+        }
+    }
+
+    let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
+    let options = DecodeOptions {
+        data_type: Some(JxlDataType::Uint8),
+        num_channels: Some(3),
+        extra_channels: false,
+        ..DecodeOptions::default()
+    };
+    let expected = decode_to_image(&library(), &file, &options)?;
+    let width = expected.width() as usize;
+    let format = JxlPixelFormat {
+        num_channels: 3,
+        data_type: JxlDataType::Uint8,
+        endianness: JxlEndianness::NativeEndian,
+        align: 0,
+    };
+
+    let mut decoder = JxlDecoder::with_library(library())?;
+    decoder.set_parallel_runner(std::sync::Arc::new(RustParallelRunner::new(4)?))?;
+    decoder.subscribe_events(JxlDecoderStatus::FullImage.bits())?;
+    decoder.set_input(&file)?;
+    decoder.close_input();
+
+    let rows = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let init_rows = rows.clone();
+    loop {
+        match decoder.process_input()? {
+            JxlDecoderStatus::NeedImageOutBuffer => {
+                let init_rows = init_rows.clone();
+                decoder.set_multithreaded_image_out_callback(
+                    &format,
+                    move |_| Staging {
+                        rows: init_rows.clone(),
+                        pending: Vec::new(),
+                    },
+                    |staging: &mut Staging, x, y, row: &[u8]| {
+                        staging.pending.push((x, y, row.to_vec()));
+                    },
+                )?;
+            }
+            JxlDecoderStatus::FullImage => break,
+            JxlDecoderStatus::NeedMoreInput => return Err(JxlError::NotEnoughInput),
+            _ => {}
+        }
+    }
+
+    let mut pixels = vec![0u8; expected.frames[0].pixels.as_bytes().len()];
+    for (x, y, row) in rows.lock().unwrap().iter() {
+        let start = (y * width + x) * 3;
+        pixels[start..start + row.len()].copy_from_slice(row);
+    }
+    assert_eq!(pixels, expected.frames[0].pixels.as_bytes());
+    Ok(())
+}
+
+#[test]
+fn test_frames_iterator() -> Result<(), JxlError> {
+    let file = read_jxl_file("test_imgs/test.jxl").expect("Failed to read test.jxl");
+    let expected = decode_to_image(&library(), &file, &DecodeOptions::default())?;
+
+    let decoder = JxlDecoder::with_library(library())?;
+    let mut frames = decoder.frames(&file, &DecodeOptions::default())?;
+    let first = frames.next().expect("no frames")?;
+    assert_eq!(first.pixels, expected.frames[0].pixels);
+    assert_eq!(first.duration, std::time::Duration::ZERO);
+    assert_eq!(
+        frames.basic_info().map(|info| info.xsize),
+        Some(expected.width())
+    );
+    let rest = frames.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(rest.len() + 1, expected.frames.len());
+
+    let layers = decoder
+        .frames(
+            &file,
+            &DecodeOptions {
+                coalescing: false,
+                ..DecodeOptions::default()
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(!layers.is_empty());
+
+    let truncated = decoder
+        .frames(&file[..file.len() / 2], &DecodeOptions::default())?
+        .collect::<Result<Vec<_>, _>>();
+    assert!(truncated.is_err());
+    Ok(())
+}
+
+/// Encodes `img` losslessly with default settings.
+fn encode_lossless(img: &image::DynamicImage) -> Result<Vec<u8>, JxlError> {
+    encode_dynamic_image(&library(), img, &EncodeOptions::lossless())
+}
+
+#[test]
+fn test_jxl_to_png_keeps_depth_and_icc() -> Result<(), JxlError> {
+    let img = image::DynamicImage::ImageRgba16(image::ImageBuffer::from_fn(16, 8, |x, y| {
+        image::Rgba([x as u16 * 4000, y as u16 * 8000, 1234, 65535 - x as u16])
+    }));
+    let png_data = jxl_to_png(&library(), &encode_lossless(&img)?, ToneMap::default())?;
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(&png_data))
+        .read_info()
+        .expect("invalid PNG");
+    assert!(reader.info().icc_profile.is_some());
+    let mut buffer = vec![0; reader.output_buffer_size().unwrap()];
+    let output = reader.next_frame(&mut buffer).expect("invalid PNG");
+    assert_eq!(output.bit_depth, png::BitDepth::Sixteen);
+    assert_eq!(output.color_type, png::ColorType::Rgba);
+    let expected: Vec<u8> = img
+        .as_rgba16()
+        .unwrap()
+        .as_raw()
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    assert_eq!(buffer, expected);
+    Ok(())
+}
+
+#[test]
+fn test_jxl_to_jpeg_reconstructs_original() -> Result<(), JxlError> {
+    let img = image::DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(32, 16, |x, y| {
+        image::Rgb([x as u8 * 8, y as u8 * 16, 200])
+    }));
+    let mut original = Vec::new();
+    img.write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+        &mut original,
+        85,
+    ))
+    .expect("Failed to write JPEG");
+
+    let mut encoder = JxlEncoder::with_library(library(), None)?;
+    encoder.store_jpeg_metadata(true)?;
+    let stream = JxlStreamEncoder::new_unseekable(encoder, Vec::new())?;
+    stream.create_frame_settings()?.add_jpeg_frame(&original)?;
+    let transcoded = stream.finish()?;
+    assert_eq!(jxl_to_jpeg(&library(), &transcoded, 90)?, original);
+
+    // jbrd가 없으면 다시 인코딩
+    let reencoded = jxl_to_jpeg(&library(), &encode_lossless(&img)?, 90)?;
+    assert_ne!(reencoded, original);
+    let decoded = image::load_from_memory(&reencoded).expect("invalid JPEG");
+    assert_eq!((decoded.width(), decoded.height()), (32, 16));
+    assert!(jxl_to_jpeg(&library(), &transcoded, 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_jxl_to_tiff_widens_gray_alpha() -> Result<(), JxlError> {
+    let img = image::DynamicImage::ImageLumaA8(image::ImageBuffer::from_fn(8, 8, |x, y| {
+        image::LumaA([(x * 30) as u8, (y * 30) as u8])
+    }));
+    let tiff = jxl_to_tiff(&library(), &encode_lossless(&img)?)?;
+    let decoded =
+        image::load_from_memory_with_format(&tiff, image::ImageFormat::Tiff).expect("invalid TIFF");
+    assert_eq!(decoded.color(), image::ColorType::Rgba8);
+    assert_eq!(decoded.to_rgba8(), img.to_rgba8());
+    Ok(())
+}
+
+#[test]
+fn test_reconstruct_and_verify_jpeg() -> Result<(), JxlError> {
+    use sha2::{Digest, Sha256};
+
+    let img = image::DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(64, 48, |x, y| {
+        image::Rgb([x as u8 * 4, y as u8 * 5, (x ^ y) as u8])
+    }));
+    let mut original = Vec::new();
+    img.write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+        &mut original,
+        80,
+    ))
+    .expect("Failed to write JPEG");
+
+    let lib = library();
+    let jxl = jpg_to_lossless_jxl_with_library(&lib, &original, 7)?;
+    assert_eq!(reconstruct_jpeg(&lib, &jxl)?, Some(original.clone()));
+
+    let expected: [u8; 32] = Sha256::digest(&original).into();
+    let verified =
+        verify_jpeg_reconstruction(&lib, &jxl, Some(&expected))?.expect("no reconstruction data");
+    assert_eq!(verified.jpeg, original);
+    assert_eq!(verified.sha256, expected);
+    assert!(matches!(
+        verify_jpeg_reconstruction(&lib, &jxl, Some(&[0; 32])),
+        Err(JxlError::JpegVerificationFailed(_))
+    ));
+
+    let pixels_only = encode_lossless(&img)?;
+    assert_eq!(reconstruct_jpeg(&lib, &pixels_only)?, None);
+    assert!(verify_jpeg_reconstruction(&lib, &pixels_only, None)?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_read_boxes() -> Result<(), JxlError> {
+    let exif = b"\0\0\0\0II*\0exif payload".to_vec();
+    let xmp = b"<x:xmpmeta xmlns:x='adobe:ns:meta/'></x:xmpmeta>".repeat(8);
+
+    let encoder = JxlEncoder::with_library(library(), None)?;
+    let mut stream = JxlStreamEncoder::new_unseekable(encoder, Vec::new())?;
+    stream.use_boxes()?;
+    stream.add_box(&b"Exif".map(|b| b as _), &exif, false)?;
+    stream.add_box(&b"xml ".map(|b| b as _), &xmp, true)?;
+    stream.close_boxes()?;
+    let mut basic_info = JxlBasicInfo::default();
+    stream.encoder().init_basic_info(&mut basic_info)?;
+    basic_info.xsize = 4;
+    basic_info.ysize = 4;
+    stream.set_basic_info(&basic_info)?;
+    let mut color_encoding = JxlColorEncoding::default();
+    stream
+        .encoder()
+        .color_encoding_set_to_srgb(&mut color_encoding, false)?;
+    stream.set_color_encoding(&color_encoding)?;
+    let format = JxlPixelFormat {
+        num_channels: 3,
+        data_type: JxlDataType::Uint8,
+        endianness: JxlEndianness::NativeEndian,
+        align: 0,
+    };
+    stream
+        .create_frame_settings()?
+        .add_image_frame(&format, &[128; 4 * 4 * 3])?;
+    let data = stream.finish()?;
+
+    let lib = library();
+    let boxes = read_boxes(&lib, &data, true)?;
+    let exif_box = boxes.iter().find(|b| &b.box_type == b"Exif").unwrap();
+    assert!(!exif_box.compressed);
+    assert_eq!(exif_box.contents, exif);
+    assert_eq!(exif_box.raw_size, 8 + exif.len() as u64);
+    let xmp_box = boxes.iter().find(|b| &b.box_type == b"xml ").unwrap();
+    assert!(xmp_box.compressed);
+    assert_eq!(xmp_box.contents, xmp);
+
+    // 압축을 풀지 않으면 brob 그대로
+    let raw = read_boxes(&lib, &data, false)?;
+    let brob = raw.iter().find(|b| b.compressed).unwrap();
+    assert_eq!(&brob.box_type, b"brob");
+    assert_eq!(&brob.contents[..4], b"xml ");
+
+    // 필요한 만큼만 읽음
+    let decoder = JxlDecoder::with_library(lib.clone())?;
+    let first = decoder.boxes(&data, true)?.next().unwrap()?;
+    assert_eq!(&first.box_type, b"JXL ");
+    Ok(())
+}
+
+#[test]
+fn test_container_editor_keeps_codestream() -> Result<(), JxlError> {
+    let pixels: Vec<u8> = (0..8 * 8 * 3).map(|i| (i * 7) as u8).collect();
+    let encoder = JxlEncoder::with_library(library(), None)?;
+    let mut stream = JxlStreamEncoder::new_unseekable(encoder, Vec::new())?;
+    stream.add_exif(b"II*\0\x08\0\0\0gps", false)?;
+    let mut basic_info = JxlBasicInfo::default();
+    stream.encoder().init_basic_info(&mut basic_info)?;
+    basic_info.xsize = 8;
+    basic_info.ysize = 8;
+    basic_info.uses_original_profile = JxlBool::True;
+    stream.set_basic_info(&basic_info)?;
+    let mut color_encoding = JxlColorEncoding::default();
+    stream
+        .encoder()
+        .color_encoding_set_to_srgb(&mut color_encoding, false)?;
+    stream.set_color_encoding(&color_encoding)?;
+    let frame_settings = stream.create_frame_settings()?;
+    frame_settings.set_lossless(true)?;
+    let format = JxlPixelFormat {
+        num_channels: 3,
+        data_type: JxlDataType::Uint8,
+        endianness: JxlEndianness::NativeEndian,
+        align: 0,
+    };
+    frame_settings.add_image_frame(&format, &pixels)?;
+    let original = stream.finish()?;
+
+    let mut editor = ContainerEditor::parse(&original)?;
+    assert_eq!(editor.remove(b"Exif")?, 1);
+    editor.set_xmp("<x:xmpmeta xmlns:x='adobe:ns:meta/'/>")?;
+    let edited = editor.to_vec();
+
+    let lib = library();
+    let boxes = read_boxes(&lib, &edited, false)?;
+    assert!(boxes.iter().all(|b| &b.box_type != b"Exif"));
+    assert!(boxes.iter().any(|b| &b.box_type == b"xml "));
+    let codestream = |data: &[u8]| -> Vec<u8> {
+        read_boxes(&lib, data, false)
+            .unwrap()
+            .into_iter()
+            .filter(|b| matches!(&b.box_type, b"jxlc" | b"jxlp"))
+            .flat_map(|b| b.contents)
+            .collect()
+    };
+    assert_eq!(codestream(&edited), codestream(&original));
+    let decoded = decode_to_image(&lib, &edited, &DecodeOptions::default())?;
+    assert_eq!(decoded.frames[0].pixels, PixelBuffer::U8(pixels));
+    Ok(())
+}
+
+#[test]
+fn test_inspect_container_matches_libjxl() -> Result<(), JxlError> {
+    let img = image::DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(8, 8, |x, y| {
+        image::Rgb([x as u8 * 30, y as u8 * 30, 90])
+    }));
+    let codestream = encode_lossless(&img)?;
+    let mut container = JXL_CONTAINER_SIGNATURE.to_vec();
+    container.extend_from_slice(ContainerBox::new(*b"ftyp", b"jxl \0\0\0\0jxl ").bytes());
+    container.extend_from_slice(ContainerBox::new(*b"jxlc", &codestream).bytes());
+
+    let decoder = JxlDecoder::with_library(library())?;
+    for data in [&codestream, &container] {
+        assert_eq!(detect_signature(data), decoder.check_signature(data)?);
+        let info = inspect_container(data);
+        assert!(info.is_valid(), "{:?}", info.problems);
+        assert_eq!(extract_codestream(data)?.as_ref(), codestream.as_slice());
+    }
+    let decoded = decode_to_image(&library(), &container, &DecodeOptions::default())?;
+    assert_eq!(
+        decoded.frames[0].pixels,
+        PixelBuffer::U8(img.to_rgb8().into_raw())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_wrap_codestream_round_trip() -> Result<(), JxlError> {
+    let img = image::DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(8, 8, |x, y| {
+        image::Rgb([x as u8 * 20, 100, y as u8 * 20])
+    }));
+    let codestream = extract_codestream(&encode_lossless(&img)?)?.into_owned();
+    let xmp = "<x:xmpmeta xmlns:x='adobe:ns:meta/'/>";
+    let container = wrap_codestream(
+        &codestream,
+        Some(5),
+        &[
+            ContainerBox::exif(b"MM\0*\0\0\0\x08")?,
+            ContainerBox::xmp(xmp),
+        ],
+    )?;
+
+    let lib = library();
+    let decoder = JxlDecoder::with_library(lib.clone())?;
+    assert_eq!(
+        decoder.check_signature(&container)?,
+        JxlSignature::Container
+    );
+    let boxes = read_boxes(&lib, &container, false)?;
+    let exif = boxes.iter().find(|b| &b.box_type == b"Exif").unwrap();
+    assert_eq!(exif.contents, b"\0\0\0\0MM\0*\0\0\0\x08");
+    assert!(boxes
+        .iter()
+        .any(|b| &b.box_type == b"xml " && b.contents == xmp.as_bytes()));
+
+    let decoded = decode_to_image(&lib, &container, &DecodeOptions::default())?;
+    assert_eq!(
+        decoded.frames[0].pixels,
+        PixelBuffer::U8(img.to_rgb8().into_raw())
+    );
+    assert_eq!(
+        extract_codestream(&container)?.as_ref(),
+        codestream.as_slice()
+    );
+    Ok(())
+}
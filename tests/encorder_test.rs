@@ -1,13 +1,15 @@
 use image::{
-    codecs::png::PngDecoder, ColorType, DynamicImage, GenericImageView, ImageDecoder, ImageFormat,
-    ImageReader,
+    codecs::png::PngDecoder, ColorType, DynamicImage, ExtendedColorType, GenericImageView,
+    ImageDecoder, ImageFormat, ImageReader,
 };
 use jxl_dynlink::*;
 use std::{
     fs::File,
     io::{BufReader, Read, Write},
 };
-const DLL_PATH: &str = "jxl.dll";
+
+mod common;
+use common::library;
 
 #[test]
 fn jpg_to_lossless_jxl() -> Result<(), JxlError> {
@@ -16,16 +18,15 @@ fn jpg_to_lossless_jxl() -> Result<(), JxlError> {
 
     // Load the image using the image crate
     // let img = image::open(input_path).expect("Failed to load image");
-    let dll_path = std::path::PathBuf::from(DLL_PATH);
     // Create a new JxlEncoder
-    let mut encoder = JxlEncoder::new(&dll_path, None)?;
+    let mut encoder = JxlEncoder::with_library(library(), None)?;
 
     // // Create frame settings
-    let frame_settings: *mut std::ffi::c_void = encoder.create_frame_settings(None)?;
+    let frame_settings = encoder.create_frame_settings()?;
     println!("Frame settings created_1");
 
     println!("frame_settings: {:?}", frame_settings);
-    encoder.set_frame_option(frame_settings, JxlEncoderFrameSettingId::Effort, 9)?;
+    frame_settings.set_effort(9)?;
     println!("Effort set");
 
     let file = File::open(input_path).map_err(|e| JxlError::InvalidInput(e.to_string()))?;
@@ -33,7 +34,7 @@ fn jpg_to_lossless_jxl() -> Result<(), JxlError> {
     let pixels = buffer_file.bytes().map(|b| b.unwrap()).collect::<Vec<u8>>();
 
     // Add jpg frame
-    encoder.add_jpeg_frame(frame_settings, &pixels)?;
+    frame_settings.add_jpeg_frame(&pixels)?;
     println!("Image frame added");
 
     // Close input
@@ -92,8 +93,7 @@ fn png_to_lossless_jxl() -> Result<(), JxlError> {
     let (width, height) = img.dimensions();
 
     // Create a new JxlEncoder
-    let dll_path = std::path::PathBuf::from(DLL_PATH);
-    let mut encoder = JxlEncoder::new(&dll_path, None)?;
+    let mut encoder = JxlEncoder::with_library(library(), None)?;
 
     // Set up basic info
     let mut basic_info = JxlBasicInfo::default();
@@ -152,23 +152,23 @@ fn png_to_lossless_jxl() -> Result<(), JxlError> {
     }
 
     // Create frame settings
-    let frame_settings = encoder.create_frame_settings(None)?;
+    let frame_settings = encoder.create_frame_settings()?;
     println!("Frame settings created");
 
     // Set frame options
-    encoder.set_frame_option(frame_settings, JxlEncoderFrameSettingId::Effort, 7)?;
+    frame_settings.set_effort(7)?;
     println!("Effort set");
 
-    encoder.set_frame_distance(frame_settings, 0.0)?;
+    frame_settings.set_distance(0.0)?;
     println!("Distance set");
 
     let bit_depth = JxlBitDepth::default();
 
-    encoder.set_frame_bit_depth(frame_settings, &bit_depth)?;
+    frame_settings.set_bit_depth(&bit_depth)?;
     println!("Bit depth set");
 
     // error.
-    // encoder.set_frame_lossless(frame_settings, true)?;
+    // frame_settings.set_lossless(true)?;
     // println!("Lossless set");
 
     // Prepare pixel format based on the input image
@@ -287,7 +287,7 @@ fn png_to_lossless_jxl() -> Result<(), JxlError> {
     // let pixels = buffer_file.bytes().map(|b| b.unwrap()).collect::<Vec<u8>>();
 
     // Add frame
-    encoder.add_image_frame(frame_settings, &pixel_format, &pixels)?;
+    frame_settings.add_image_frame(&pixel_format, &pixels)?;
     println!("Image frame added");
 
     // Close input
@@ -323,3 +323,459 @@ fn png_to_lossless_jxl() -> Result<(), JxlError> {
 
     Ok(())
 }
+
+#[test]
+fn image_codec_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let img = image::RgbImage::from_fn(32, 16, |x, y| image::Rgb([x as u8 * 8, y as u8 * 16, 128]));
+
+    let mut encoded = Vec::new();
+    img.write_with_encoder(JxlImageEncoder::with_library(&mut encoded, library()))?;
+    assert_eq!(&encoded[..2], &[0xff, 0x0a]);
+
+    let decoder = JxlImageDecoder::with_library(std::io::Cursor::new(&encoded), library())?;
+    assert_eq!(decoder.dimensions(), (32, 16));
+    assert_eq!(decoder.color_type(), ColorType::Rgb8);
+    let decoded = DynamicImage::from_decoder(decoder)?;
+    assert_eq!(decoded.to_rgb8(), img);
+
+    register_image_hooks(library());
+    let guessed = ImageReader::new(std::io::Cursor::new(&encoded))
+        .with_guessed_format()?
+        .decode()?;
+    assert_eq!(guessed.to_rgb8(), img);
+    Ok(())
+}
+
+#[test]
+fn encode_dynamic_image_all_color_types() -> Result<(), JxlError> {
+    let rgb = DynamicImage::ImageRgb8(image::RgbImage::from_fn(8, 8, |x, y| {
+        image::Rgb([x as u8 * 30, y as u8 * 30, 200])
+    }));
+    let images = [
+        rgb.clone(),
+        DynamicImage::ImageLumaA8(rgb.to_luma_alpha8()),
+        DynamicImage::ImageLuma16(rgb.to_luma16()),
+        DynamicImage::ImageRgba16(rgb.to_rgba16()),
+        DynamicImage::ImageRgb32F(rgb.to_rgb32f()),
+        DynamicImage::ImageRgba32F(rgb.to_rgba32f()),
+    ];
+
+    for img in &images {
+        let lossless = encode_dynamic_image(&library(), img, &EncodeOptions::default())?;
+        let decoded = decode_to_image(&library(), &lossless, &DecodeOptions::default())?;
+        assert_eq!((decoded.width(), decoded.height()), (8, 8));
+        assert_eq!(
+            decoded.basic_info.alpha_bits > 0,
+            img.color().has_alpha(),
+            "{:?}",
+            img.color()
+        );
+
+        let lossy = encode_dynamic_image(&library(), img, &EncodeOptions::lossy(1.0))?;
+        assert!(!lossy.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_encoder_to_writer() -> Result<(), JxlError> {
+    let img = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(64, 48, |x, y| {
+        image::Rgba([x as u8 * 4, y as u8 * 5, 60, 255 - x as u8])
+    }));
+    let in_memory = encode_dynamic_image(&library(), &img, &EncodeOptions::default())?;
+
+    let unseekable =
+        encode_dynamic_image_to(&library(), &img, &EncodeOptions::default(), Vec::new())?;
+    let expected = decode_to_image(&library(), &in_memory, &DecodeOptions::default())?;
+    let streamed = decode_to_image(&library(), &unseekable, &DecodeOptions::default())?;
+    assert_eq!(streamed.frames[0].pixels, expected.frames[0].pixels);
+
+    let mut stream = JxlStreamEncoder::new(
+        JxlEncoder::with_library(library(), None)?,
+        std::io::Cursor::new(Vec::new()),
+    )?;
+    let mut basic_info = JxlBasicInfo::default();
+    stream.encoder().init_basic_info(&mut basic_info)?;
+    basic_info.xsize = 64;
+    basic_info.ysize = 48;
+    basic_info.num_extra_channels = 1;
+    basic_info.alpha_bits = 8;
+    stream.set_basic_info(&basic_info)?;
+    let mut color_encoding = JxlColorEncoding::default();
+    stream
+        .encoder()
+        .color_encoding_set_to_srgb(&mut color_encoding, false)?;
+    stream.set_color_encoding(&color_encoding)?;
+    let frame_settings = stream.encoder().create_frame_settings()?;
+    let format = JxlPixelFormat {
+        num_channels: 4,
+        ..Default::default()
+    };
+    frame_settings.add_image_frame(&format, img.as_bytes())?;
+    let seekable = stream.finish()?.into_inner();
+
+    let decoded = decode_to_image(&library(), &seekable, &DecodeOptions::default())?;
+    assert_eq!((decoded.width(), decoded.height()), (64, 48));
+    Ok(())
+}
+
+struct GradientSource {
+    width: usize,
+    pixels: Vec<u8>,
+}
+
+impl ChunkedFrameSource for GradientSource {
+    fn pixel_format(&self) -> JxlPixelFormat {
+        JxlPixelFormat::default()
+    }
+
+    fn color_rect(
+        &self,
+        x: usize,
+        y: usize,
+        _width: usize,
+        _height: usize,
+    ) -> Result<ChunkedRect<'_>, JxlError> {
+        let start = (y * self.width + x) * 3;
+        Ok((
+            std::borrow::Cow::Borrowed(&self.pixels[start..]),
+            self.width * 3,
+        ))
+    }
+}
+
+#[test]
+fn add_chunked_frame_from_source() -> Result<(), JxlError> {
+    let img = image::RgbImage::from_fn(300, 200, |x, y| {
+        image::Rgb([x as u8, y as u8, (x + y) as u8])
+    });
+    let source = GradientSource {
+        width: 300,
+        pixels: img.as_raw().clone(),
+    };
+
+    let mut encoder = JxlEncoder::with_library(library(), None)?;
+    let mut basic_info = JxlBasicInfo::default();
+    encoder.init_basic_info(&mut basic_info)?;
+    basic_info.xsize = 300;
+    basic_info.ysize = 200;
+    basic_info.uses_original_profile = JxlBool::True;
+    encoder.set_basic_info(&basic_info)?;
+    let mut color_encoding = JxlColorEncoding::default();
+    encoder.color_encoding_set_to_srgb(&mut color_encoding, false)?;
+    encoder.set_color_encoding(&color_encoding)?;
+    let frame_settings = encoder.create_frame_settings()?;
+    frame_settings.set_lossless(true)?;
+    frame_settings.add_chunked_frame_from(true, &source)?;
+    encoder.close_input()?;
+
+    let mut compressed = vec![0u8; 1 << 20];
+    let mut next_out = compressed.as_mut_ptr();
+    let mut avail_out = compressed.len();
+    assert_eq!(
+        encoder.process_output(&mut next_out, &mut avail_out)?,
+        JxlEncoderStatus::Success
+    );
+    compressed.truncate(compressed.len() - avail_out);
+
+    let decoded = decode_to_image(&library(), &compressed, &DecodeOptions::default())?;
+    assert_eq!(decoded.frames[0].pixels.as_bytes(), img.as_raw().as_slice());
+    Ok(())
+}
+
+fn encode_with_stats(stats: &EncoderStats) -> Result<Vec<u8>, JxlError> {
+    let (width, height) = (64u32, 64u32);
+    let pixels: Vec<u8> = (0..width * height)
+        .flat_map(|i| [(i % width * 4) as u8, (i / width * 4) as u8, 128])
+        .collect();
+
+    let encoder = JxlEncoder::with_library(library(), None)?;
+    let mut stream = JxlStreamEncoder::new_unseekable(encoder, Vec::new())?;
+    let mut basic_info = JxlBasicInfo::default();
+    stream.encoder().init_basic_info(&mut basic_info)?;
+    basic_info.xsize = width;
+    basic_info.ysize = height;
+    stream.set_basic_info(&basic_info)?;
+    let mut color_encoding = JxlColorEncoding::default();
+    stream
+        .encoder()
+        .color_encoding_set_to_srgb(&mut color_encoding, false)?;
+    stream.set_color_encoding(&color_encoding)?;
+
+    let frame_settings = stream.create_frame_settings()?;
+    frame_settings.set_distance(1.0)?;
+    frame_settings.collect_stats(stats)?;
+    let format = JxlPixelFormat {
+        num_channels: 3,
+        data_type: JxlDataType::Uint8,
+        endianness: JxlEndianness::NativeEndian,
+        align: 0,
+    };
+    frame_settings.add_image_frame(&format, &pixels)?;
+    stream.finish()
+}
+
+#[test]
+fn encoder_stats_collect_and_merge() -> Result<(), JxlError> {
+    let stats = EncoderStats::new(&library())?;
+    let encoded = encode_with_stats(&stats)?;
+    assert!(!encoded.is_empty());
+
+    let header_bits = stats.get(JxlEncoderStatsKey::HeaderBits);
+    assert!(header_bits > 0);
+    assert!(stats.get(JxlEncoderStatsKey::AcBits) > 0);
+    assert_eq!(stats.to_vec().len(), JxlEncoderStatsKey::ALL.len());
+
+    let mut total = EncoderStats::new(&library())?;
+    total.merge(&stats)?;
+    total.merge(&stats)?;
+    assert_eq!(total.get(JxlEncoderStatsKey::HeaderBits), 2 * header_bits);
+    Ok(())
+}
+
+#[test]
+fn animation_encoder_round_trip() -> Result<(), JxlError> {
+    let (width, height) = (16u32, 16u32);
+    let animation = JxlAnimationHeader {
+        tps_numerator: 100,
+        tps_denominator: 1,
+        num_loops: 0,
+        have_timecodes: JxlBool::False,
+    };
+    let mut encoder = AnimationEncoder::new(
+        &library(),
+        width,
+        height,
+        ExtendedColorType::Rgba8,
+        &animation,
+        &EncodeOptions::lossless(),
+    )?;
+
+    let background = vec![255u8; (width * height * 4) as usize];
+    encoder.add_frame(
+        &background,
+        &AnimationFrame {
+            duration: animation.duration_to_ticks(std::time::Duration::from_millis(100)),
+            name: "background".to_string(),
+            ..AnimationFrame::default()
+        },
+    )?;
+    let patch = [0u8, 0, 255, 128].repeat(4 * 4);
+    encoder.add_frame(
+        &patch,
+        &AnimationFrame {
+            duration: 5,
+            crop: Some(FrameCrop {
+                x0: 4,
+                y0: 4,
+                width: 4,
+                height: 4,
+            }),
+            blend_mode: JxlBlendMode::Blend,
+            ..AnimationFrame::default()
+        },
+    )?;
+    assert!(encoder
+        .add_frame(&patch, &AnimationFrame::default())
+        .is_err());
+    encoder.close_frames()?;
+    assert!(encoder
+        .add_frame(&background, &AnimationFrame::default())
+        .is_err());
+    let encoded = encoder.finish()?;
+
+    let decoder = JxlDecoder::with_library(library())?;
+    let mut frames = decoder.frames(&encoded, &DecodeOptions::default())?;
+    let first = frames.next().expect("no frames")?;
+    assert_eq!(first.name, "background");
+    assert_eq!(first.duration, std::time::Duration::from_millis(100));
+    assert_eq!(
+        frames.basic_info().map(|info| info.animation.num_loops),
+        Some(0)
+    );
+    let second = frames.next().expect("missing second frame")?;
+    assert_eq!(second.duration, std::time::Duration::from_millis(50));
+    assert!(frames.next().is_none());
+    Ok(())
+}
+
+/// Fills the `w` x `h` rect at (`x`, `y`) of an 8x8 RGBA canvas with `color`.
+fn fill_rect(canvas: &mut [u8], x: usize, y: usize, w: usize, h: usize, color: [u8; 4]) {
+    for row in y..y + h {
+        for col in x..x + w {
+            canvas[(row * 8 + col) * 4..][..4].copy_from_slice(&color);
+        }
+    }
+}
+
+#[test]
+fn gif_to_jxl_keeps_timing_and_disposal() -> Result<(), JxlError> {
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0];
+
+    let mut gif_data = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut gif_data, 8, 8, &palette).unwrap();
+        encoder.set_repeat(gif::Repeat::Finite(2)).unwrap();
+        let frames = [
+            (0, 0, 8, 8, vec![0; 64], 10, gif::DisposalMethod::Keep),
+            // 초록 체크무늬, 인덱스 3은 투명
+            (
+                2,
+                2,
+                4,
+                4,
+                (0..16).map(|i| if i % 2 == 0 { 1 } else { 3 }).collect(),
+                20,
+                gif::DisposalMethod::Background,
+            ),
+            (0, 0, 2, 2, vec![2; 4], 30, gif::DisposalMethod::Keep),
+        ];
+        for (left, top, width, height, indices, delay, dispose) in frames {
+            encoder
+                .write_frame(&gif::Frame {
+                    left,
+                    top,
+                    width,
+                    height,
+                    delay,
+                    dispose,
+                    transparent: Some(3),
+                    buffer: indices.into(),
+                    ..gif::Frame::default()
+                })
+                .unwrap();
+        }
+    }
+
+    let encoded = gif_to_jxl(&library(), &gif_data, &EncodeOptions::default())?;
+    let decoder = JxlDecoder::with_library(library())?;
+    let mut frames = decoder.frames(&encoded, &DecodeOptions::default())?;
+
+    let mut canvas = [RED; 64].concat();
+    let first = frames.next().expect("no frames")?;
+    assert_eq!(first.pixels, PixelBuffer::U8(canvas.clone()));
+    assert_eq!(first.duration, std::time::Duration::from_millis(100));
+    assert_eq!(
+        frames.basic_info().map(|info| info.animation.num_loops),
+        Some(3)
+    );
+
+    for i in (0..16).step_by(2) {
+        fill_rect(&mut canvas, 2 + i % 4, 2 + i / 4, 1, 1, GREEN);
+    }
+    let second = frames.next().expect("missing second frame")?;
+    assert_eq!(second.pixels, PixelBuffer::U8(canvas.clone()));
+    assert_eq!(second.duration, std::time::Duration::from_millis(200));
+
+    fill_rect(&mut canvas, 2, 2, 4, 4, [0; 4]);
+    fill_rect(&mut canvas, 0, 0, 2, 2, BLUE);
+    let third = frames.next().expect("missing third frame")?;
+    assert_eq!(third.pixels, PixelBuffer::U8(canvas.clone()));
+    assert_eq!(third.duration, std::time::Duration::from_millis(300));
+    assert!(frames.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn apng_to_jxl_keeps_timing_and_disposal() -> Result<(), JxlError> {
+    let mut apng = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut apng, 8, 8);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(2, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.set_frame_delay(1, 30).unwrap();
+        writer.set_dispose_op(png::DisposeOp::Background).unwrap();
+        writer.write_image_data(&[200; 8 * 8 * 3]).unwrap();
+
+        writer.set_frame_delay(1, 25).unwrap();
+        writer.set_dispose_op(png::DisposeOp::None).unwrap();
+        writer.set_frame_dimension(2, 2).unwrap();
+        writer.set_frame_position(3, 3).unwrap();
+        writer.write_image_data(&[50; 2 * 2 * 3]).unwrap();
+        writer.finish().unwrap();
+    }
+
+    let encoded = apng_to_jxl(&library(), &apng, &EncodeOptions::default())?;
+    let decoder = JxlDecoder::with_library(library())?;
+    let frames = decoder
+        .frames(&encoded, &DecodeOptions::default())?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(
+        frames[0].pixels,
+        PixelBuffer::U8([200u8, 200, 200, 255].repeat(64))
+    );
+    assert_eq!(
+        frames[0].duration.as_nanos(),
+        std::time::Duration::from_secs(1).as_nanos() / 30
+    );
+
+    // 첫 프레임은 배경으로 지워지므로 두 번째 프레임 밖은 투명
+    let mut canvas = vec![0; 8 * 8 * 4];
+    fill_rect(&mut canvas, 3, 3, 2, 2, [50, 50, 50, 255]);
+    assert_eq!(frames[1].pixels, PixelBuffer::U8(canvas.clone()));
+    assert_eq!(frames[1].duration, std::time::Duration::from_millis(40));
+    Ok(())
+}
+
+#[test]
+fn metadata_boxes_round_trip() -> Result<(), JxlError> {
+    let tiff = b"II*\0\x08\0\0\0\0\0".to_vec();
+    let xmp = "<x:xmpmeta xmlns:x='adobe:ns:meta/'></x:xmpmeta>";
+    let jumbf = b"\0\0\0\x11jumdc2pa\0\0\0\0\0".to_vec();
+
+    let encoder = JxlEncoder::with_library(library(), None)?;
+    let mut stream = JxlStreamEncoder::new_unseekable(encoder, Vec::new())?;
+    // APP1 접두사는 제거되고 오프셋이 붙음
+    stream.add_exif(&[b"Exif\0\0".as_slice(), &tiff].concat(), false)?;
+    stream.add_xmp(xmp, true)?;
+    stream.add_jumbf(&jumbf, false)?;
+    stream.close_boxes()?;
+    assert!(matches!(
+        stream.add_xmp(xmp, false),
+        Err(JxlError::BoxError(_))
+    ));
+    assert!(matches!(
+        stream.add_exif(b"not exif", false),
+        Err(JxlError::InvalidInput(_))
+    ));
+
+    let mut basic_info = JxlBasicInfo::default();
+    stream.encoder().init_basic_info(&mut basic_info)?;
+    basic_info.xsize = 4;
+    basic_info.ysize = 4;
+    stream.set_basic_info(&basic_info)?;
+    let mut color_encoding = JxlColorEncoding::default();
+    stream
+        .encoder()
+        .color_encoding_set_to_srgb(&mut color_encoding, false)?;
+    stream.set_color_encoding(&color_encoding)?;
+    let format = JxlPixelFormat {
+        num_channels: 3,
+        data_type: JxlDataType::Uint8,
+        endianness: JxlEndianness::NativeEndian,
+        align: 0,
+    };
+    stream
+        .create_frame_settings()?
+        .add_image_frame(&format, &[64; 4 * 4 * 3])?;
+    let data = stream.finish()?;
+    assert_eq!(&data[4..12], b"JXL \r\n\x87\n");
+
+    let boxes = read_boxes(&library(), &data, true)?;
+    let contents = |box_type: &[u8; 4]| {
+        boxes
+            .iter()
+            .find(|b| &b.box_type == box_type)
+            .map(|b| b.contents.clone())
+    };
+    assert_eq!(contents(b"Exif"), Some([&[0, 0, 0, 0][..], &tiff].concat()));
+    assert_eq!(contents(b"xml "), Some(xmp.as_bytes().to_vec()));
+    assert_eq!(contents(b"jumb"), Some(jumbf));
+    Ok(())
+}
@@ -1,14 +1,11 @@
 use std::path::PathBuf;
 
+mod common;
+
 fn get_test_dll_path() -> Result<PathBuf, &'static str> {
-    let mut path = std::env::current_dir().unwrap();
-    path.push("dlls");
-    path.push("jxl.dll");
-
-    if path.exists() {
-        Ok(path)
-    } else {
-        Err("jxl.dll not found")
+    match common::try_library() {
+        Some(lib) => Ok(lib.path().to_path_buf()),
+        None => Err("libjxl not found"),
     }
 }
 
@@ -16,18 +13,19 @@ fn get_test_dll_path() -> Result<PathBuf, &'static str> {
 fn test_encode_jpg() {
     use jxl_dynlink::jpg_to_lossless_jxl;
 
-    let path = get_test_dll_path();
-    if path.is_err() {
-        println!("{}", path.unwrap_err());
-        return;
-    }
+    let dll_path = match get_test_dll_path() {
+        Ok(path) => path,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
 
-    println!("dll path: {:?}", path.clone().unwrap());
+    println!("dll path: {:?}", dll_path);
 
     let input = "test_imgs/test_encode.jpg";
     let output = "test_imgs/test_encode.jxl";
     let effort: i64 = 9;
-    let dll_path: PathBuf = path.unwrap();
 
     let input_data = std::fs::read(input).unwrap();
 
@@ -40,17 +38,18 @@ fn test_encode_jpg() {
 fn test_encode_png() {
     use jxl_dynlink::png_to_lossless_jxl;
 
-    let path = get_test_dll_path();
-    if path.is_err() {
-        println!("{}", path.unwrap_err());
-        return;
-    }
+    let dll_path = match get_test_dll_path() {
+        Ok(path) => path,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
 
     let input = "test_imgs/test_encode2.png";
     let output = "test_imgs/test_encode2.jxl";
     let effort: i64 = 9;
     let distance: f32 = 0.0;
-    let dll_path: PathBuf = path.unwrap();
 
     let input_data = std::fs::read(input).unwrap();
 